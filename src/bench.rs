@@ -0,0 +1,246 @@
+use crate::distance::{ratio, Metric};
+use crate::util::{
+    collect_files, load_file, tokenize, SuggestIndex, SuggestOpts, Suggester, WalkOpts,
+};
+use crate::wordset::{SetImpl, WordSet};
+use clap::ValueEnum;
+use std::{
+    hint::black_box,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// How one dictionary set implementation did
+#[derive(Debug)]
+pub struct BenchResult {
+    pub set_impl: SetImpl,
+    pub words: usize,
+    /// How long the set took to build
+    pub build: Duration,
+    /// How long all the lookups took
+    pub lookups: Duration,
+    /// How many of the queries were found
+    pub found: usize,
+}
+
+/// How long finding suggestions took
+#[derive(Debug)]
+pub struct SuggestResult {
+    pub index: SuggestIndex,
+    /// How long the index took to build
+    pub build: Duration,
+    /// How many misspelled words suggestions were found for
+    pub queries: usize,
+    pub elapsed: Duration,
+}
+
+/// How long working out the difflib ratio of every dictionary word took
+#[derive(Debug)]
+pub struct RatioResult {
+    /// How many misspelled words the ratios were worked out for
+    pub queries: usize,
+    /// With the misspelled word indexed once, like finding suggestions does
+    pub indexed: Duration,
+    /// With difflib, which indexes each dictionary word instead
+    pub difflib: Duration,
+}
+
+/// How many misspelled words to time finding suggestions for, since each
+/// one goes through the whole dictionary
+const SUGGEST_QUERIES: usize = 20;
+
+/// A spread of the queries that aren't in the dictionary, to time finding
+/// suggestions for
+fn sample_unknown<'q>(words: &[&str], queries: &'q [String]) -> Vec<&'q String> {
+    let set = WordSet::new(words, SetImpl::default());
+    let unknown: Vec<&String> = queries.iter().filter(|q| !set.contains(q)).collect();
+    let step = (unknown.len() / SUGGEST_QUERIES).max(1);
+
+    return unknown
+        .into_iter()
+        .step_by(step)
+        .take(SUGGEST_QUERIES)
+        .collect();
+}
+
+/// The words to look up: the tokens in the files, or if there aren't any,
+/// every dictionary word along with a misspelled (reversed) copy of it
+pub fn bench_queries(
+    paths: &[PathBuf],
+    walk: &WalkOpts,
+    words: &[&str],
+    max_file_size: u64,
+) -> Vec<String> {
+    let mut ret = vec![];
+    for path in collect_files(paths, walk) {
+        if let Ok(text) = load_file(&path, max_file_size) {
+            ret.extend(text.lines().flat_map(tokenize));
+        }
+    }
+
+    if ret.is_empty() {
+        for word in words {
+            ret.push(word.to_string());
+            ret.push(word.chars().rev().collect());
+        }
+    }
+
+    return ret;
+}
+
+/// Build every kind of set from the words and time looking up the queries
+/// in each
+pub fn bench_sets(words: &[&str], queries: &[String]) -> Vec<BenchResult> {
+    let mut ret = vec![];
+    for set_impl in SetImpl::value_variants() {
+        let start = Instant::now();
+        let set = WordSet::new(words, *set_impl);
+        let build = start.elapsed();
+
+        let start = Instant::now();
+        let found = queries.iter().filter(|q| set.contains(q)).count();
+        let lookups = start.elapsed();
+
+        ret.push(BenchResult {
+            set_impl: *set_impl,
+            words: set.len(),
+            build,
+            lookups,
+            found,
+        });
+    }
+
+    return ret;
+}
+
+/// Time finding suggestions for a spread of the queries that aren't in the
+/// dictionary, with each way of finding them
+pub fn bench_suggest(
+    words: &[&str],
+    queries: &[String],
+    suggest: SuggestOpts,
+) -> Vec<SuggestResult> {
+    let unknown = sample_unknown(words, queries);
+
+    let mut ret = vec![];
+    for index in SuggestIndex::value_variants() {
+        let start = Instant::now();
+        let opts = SuggestOpts {
+            index: *index,
+            ..suggest
+        };
+        let suggester = Suggester::new(words, opts);
+        let build = start.elapsed();
+
+        let start = Instant::now();
+        for query in &unknown {
+            suggester.find(query);
+        }
+
+        ret.push(SuggestResult {
+            index: *index,
+            build,
+            queries: unknown.len(),
+            elapsed: start.elapsed(),
+        });
+    }
+
+    return ret;
+}
+
+/// Time working out the ratio of every dictionary word for a spread of the
+/// queries that aren't in it, the way finding suggestions does and with
+/// difflib as it is, which shows what indexing the query once saves
+pub fn bench_ratio(words: &[&str], queries: &[String]) -> RatioResult {
+    let unknown = sample_unknown(words, queries);
+
+    let start = Instant::now();
+    for query in &unknown {
+        let mut scorer = Metric::Ratio.scorer(query);
+        for word in words {
+            black_box(scorer.score(word));
+        }
+    }
+    let indexed = start.elapsed();
+
+    let start = Instant::now();
+    for query in &unknown {
+        for word in words {
+            black_box(ratio(query, word));
+        }
+    }
+
+    return RatioResult {
+        queries: unknown.len(),
+        indexed,
+        difflib: start.elapsed(),
+    };
+}
+
+/// Print the results as a table
+pub fn print_bench(results: &[BenchResult], queries: usize) {
+    println!(
+        "{:<12}{:>10}{:>12}{:>16}{:>10}",
+        "set", "words", "build ms", "ns per lookup", "found"
+    );
+    for r in results {
+        let name = r.set_impl.to_possible_value().unwrap();
+        println!(
+            "{:<12}{:>10}{:>12.1}{:>16.1}{:>10}",
+            name.get_name(),
+            r.words,
+            r.build.as_secs_f64() * 1000.0,
+            r.lookups.as_nanos() as f64 / queries.max(1) as f64,
+            r.found
+        );
+    }
+}
+
+pub fn print_suggest(results: &[SuggestResult]) {
+    let queries = results.first().map_or(0, |r| r.queries);
+    println!("\nsuggestions for {} misspelled words:", queries);
+    println!("{:<12}{:>12}{:>16}", "index", "build ms", "ms per word");
+    for r in results {
+        let name = r.index.to_possible_value().unwrap();
+        println!(
+            "{:<12}{:>12.1}{:>16.1}",
+            name.get_name(),
+            r.build.as_secs_f64() * 1000.0,
+            r.elapsed.as_secs_f64() * 1000.0 / r.queries.max(1) as f64
+        );
+    }
+}
+
+pub fn print_ratio(result: &RatioResult) {
+    println!(
+        "\nratio of every word for {} misspelled words:",
+        result.queries
+    );
+    println!("{:<12}{:>16}", "query", "ms per word");
+    for (name, elapsed) in [("indexed", result.indexed), ("difflib", result.difflib)] {
+        println!(
+            "{:<12}{:>16.1}",
+            name,
+            elapsed.as_secs_f64() * 1000.0 / result.queries.max(1) as f64
+        );
+    }
+}
+
+#[test]
+fn test_bench_sets() {
+    let words = ["cat", "dog"];
+    let queries = bench_queries(&[], &WalkOpts::default(), &words, 1024);
+    assert_eq!(queries, vec!["cat", "tac", "dog", "god"]);
+
+    let results = bench_sets(&words, &queries);
+    assert_eq!(results.len(), SetImpl::value_variants().len());
+    for r in results {
+        assert_eq!((r.words, r.found), (2, 2), "{:?}", r.set_impl);
+    }
+
+    let queries = ["cat".to_string(), "dgo".to_string(), "tca".to_string()];
+    let results = bench_suggest(&words, &queries, SuggestOpts::new(5));
+    assert_eq!(results.len(), SuggestIndex::value_variants().len());
+    assert!(results.iter().all(|r| r.queries == 2));
+    assert_eq!(bench_ratio(&words, &queries).queries, 2);
+}
@@ -0,0 +1,34 @@
+//! The `cargo spel` entry point.  Cargo runs this as `cargo-spel spel
+//! [args]`, which hands off to `spel [args] cargo` from the same install.
+#![allow(clippy::needless_return)]
+use std::{
+    env,
+    path::PathBuf,
+    process::{self, Command},
+};
+
+/// Find the spel binary installed alongside this one, falling back to
+/// whatever is on the PATH
+fn spel_exe() -> PathBuf {
+    let name = format!("spel{}", env::consts::EXE_SUFFIX);
+    return env::current_exe()
+        .ok()
+        .map(|p| p.with_file_name(&name))
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| PathBuf::from(name));
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "spel") {
+        args.remove(0);
+    }
+
+    match Command::new(spel_exe()).args(args).arg("cargo").status() {
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("Failed to run spel: {}", e);
+            process::exit(1);
+        }
+    }
+}
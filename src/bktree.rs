@@ -0,0 +1,267 @@
+#[cfg(test)]
+use crate::util::find_word;
+
+/// A node of the tree: a word, by its position in the list, and the nodes
+/// under it, each with its distance from this word
+#[derive(Debug)]
+struct Node {
+    word: u32,
+    children: Vec<(usize, u32)>,
+}
+
+/// A BK-tree of the words in a list, by their edit distance.  Every word
+/// under a child is the child's distance from its parent, so by the
+/// triangle inequality a search for the words within N edits only has to
+/// go down the children within N of the distance to the parent.
+#[derive(Debug, Default)]
+pub struct BkTree {
+    nodes: Vec<Node>,
+}
+
+impl BkTree {
+    /// Build the tree over the words, which have to be the ones it's
+    /// searched with later
+    pub fn new(words: &[&str]) -> Self {
+        let mut ret = Self { nodes: vec![] };
+        for (i, word) in words.iter().enumerate() {
+            ret.insert(words, i as u32, word);
+        }
+
+        return ret;
+    }
+
+    fn insert(&mut self, words: &[&str], index: u32, word: &str) {
+        if self.nodes.is_empty() {
+            self.nodes.push(Node {
+                word: index,
+                children: vec![],
+            });
+            return;
+        }
+
+        let mut cur = 0;
+        loop {
+            let dist = edit_distance(word, words[self.nodes[cur].word as usize]);
+            if dist == 0 {
+                // Already in the tree
+                return;
+            }
+
+            match self.nodes[cur].children.iter().find(|(d, _)| *d == dist) {
+                Some(&(_, child)) => cur = child as usize,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes.push(Node {
+                        word: index,
+                        children: vec![],
+                    });
+                    self.nodes[cur].children.push((dist, child));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// The words within `max_distance` edits of the word, in list order
+    pub fn within<'a>(&self, word: &str, words: &[&'a str], max_distance: usize) -> Vec<&'a str> {
+        let query = Pattern::new(word);
+        let mut found = vec![];
+        let mut stack = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![0],
+        };
+        while let Some(cur) = stack.pop() {
+            let node = &self.nodes[cur];
+            let dist = query.distance(words[node.word as usize]);
+            if dist <= max_distance {
+                found.push(node.word);
+            }
+
+            let range = dist.saturating_sub(max_distance)..=dist + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(d, _)| range.contains(d))
+                    .map(|(_, child)| *child as usize),
+            );
+        }
+        found.sort_unstable();
+
+        return found.into_iter().map(|i| words[i as usize]).collect();
+    }
+
+    /// Find the `top` most similar words out of the ones within
+    /// `max_distance` edits of the word, the same as find_word() does over
+    /// those words
+    #[cfg(test)]
+    pub fn find<'a>(
+        &self,
+        word: &str,
+        words: &[&'a str],
+        top: usize,
+        max_distance: usize,
+    ) -> Vec<(f32, &'a str)> {
+        return find_word(word, &self.within(word, words, max_distance), top);
+    }
+}
+
+/// A word that's compared with a lot of others.  An ascii word of up to
+/// 64 letters is turned into a bit mask for each letter, for Myers' bit
+/// parallel edit distance, which works out a whole column of the table at
+/// once.
+struct Pattern<'a> {
+    word: &'a str,
+    masks: Option<Box<[u64; 256]>>,
+}
+
+impl<'a> Pattern<'a> {
+    fn new(word: &'a str) -> Self {
+        if !word.is_ascii() || word.is_empty() || word.len() > 64 {
+            return Self { word, masks: None };
+        }
+
+        let mut masks = Box::new([0; 256]);
+        for (i, b) in word.bytes().enumerate() {
+            masks[b as usize] |= 1 << i;
+        }
+
+        return Self {
+            word,
+            masks: Some(masks),
+        };
+    }
+
+    /// The same as edit_distance() with the word
+    fn distance(&self, other: &str) -> usize {
+        let Some(masks) = self.masks.as_ref().filter(|_| other.is_ascii()) else {
+            return edit_distance(self.word, other);
+        };
+
+        let last = 1 << (self.word.len() - 1);
+        let (mut pv, mut mv) = (!0_u64, 0_u64);
+        let mut score = self.word.len();
+        for b in other.bytes() {
+            let eq = masks[b as usize];
+            let xv = eq | mv;
+            let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+            let ph = mv | !(xh | pv);
+            let mh = pv & xh;
+            if ph & last != 0 {
+                score += 1;
+            } else if mh & last != 0 {
+                score -= 1;
+            }
+            let ph = (ph << 1) | 1;
+            pv = (mh << 1) | !(xv | ph);
+            mv = ph & xv;
+        }
+
+        return score;
+    }
+}
+
+/// The number of chars added, removed or changed to get from one word to
+/// the other
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    // Most words are ascii, where a byte is a char
+    if a.is_ascii() && b.is_ascii() {
+        return levenshtein(a.as_bytes(), b.as_bytes());
+    }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    return levenshtein(&a, &b);
+}
+
+/// The words that fit are worked out on the stack, since a search works
+/// out a lot of them
+const STACK_LEN: usize = 64;
+
+fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    if b.len() >= STACK_LEN {
+        let mut rows = vec![0; 2 * (b.len() + 1)];
+        return levenshtein_in(a, b, &mut rows);
+    }
+
+    return levenshtein_in(a, b, &mut [0; 2 * STACK_LEN]);
+}
+
+/// The distance, keeping the previous and current rows of the table in
+/// `rows`, which has room for 2 of them
+fn levenshtein_in<T: PartialEq>(a: &[T], b: &[T], rows: &mut [usize]) -> usize {
+    let (prev, cur) = rows.split_at_mut(rows.len() / 2);
+    let (mut prev, mut cur) = (&mut prev[..=b.len()], &mut cur[..=b.len()]);
+    for (j, p) in prev.iter_mut().enumerate() {
+        *p = j;
+    }
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let change = prev[j] + (ca != cb) as usize;
+            cur[j + 1] = change.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    return prev[b.len()];
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("teh", "the"), 2);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("naïve", "naive"), 1);
+    assert_eq!(edit_distance("same", "same"), 0);
+    let long = "a".repeat(100);
+    assert_eq!(edit_distance(&long, &long[1..]), 1);
+
+    // The bit parallel version agrees
+    let words = [
+        "", "a", "teh", "the", "kitten", "sitting", "naïve", "naive", &long,
+    ];
+    for a in words {
+        let pat = Pattern::new(a);
+        for b in words {
+            assert_eq!(pat.distance(b), edit_distance(a, b), "{} {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn test_bk_tree() {
+    use crate::util::{dict_text, get_words, within_distance, BUILTIN_DICT};
+
+    let words = get_words(dict_text(BUILTIN_DICT));
+    let sample: Vec<&str> = words.iter().step_by(97).copied().collect();
+    let tree = BkTree::new(&sample);
+
+    // The same as filtering the whole list by distance
+    for word in [
+        "recieve",
+        "teh",
+        "a",
+        "spelling",
+        "xyzzy",
+        "naïve",
+        "acommodation",
+    ] {
+        for max in [1, 2, 3] {
+            let near: Vec<&str> = sample
+                .iter()
+                .copied()
+                .filter(|w| within_distance(word, w, max))
+                .collect();
+            assert_eq!(
+                tree.within(word, &sample, max),
+                near,
+                "{} max {}",
+                word,
+                max
+            );
+            assert_eq!(tree.find(word, &sample, 5, max), find_word(word, &near, 5));
+        }
+    }
+
+    assert!(BkTree::new(&[]).within("cat", &[], 2).is_empty());
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+use crate::util::within_distance;
+use crate::util::{by_rank, dedup_variants, find_word, ratio_for};
+use crate::wordset::{fold_case, Caseless};
+use memchr::memchr_iter;
+use std::collections::HashSet;
+
+/// The dictionary words split up by their length in bytes.  Only the
+/// lengths a lookup can match are split out of the dictionary text, so
+/// checking a word or 2 doesn't pay to load the whole list.
+#[derive(Debug)]
+pub struct WordBuckets<'a> {
+    text: &'a str,
+    /// The words of each length loaded so far, in dictionary order
+    buckets: Vec<Option<Vec<&'a str>>>,
+    /// The words in all the buckets loaded so far, to look them up in in
+    /// any case
+    known: HashSet<Caseless<&'a str>>,
+    /// The longest word, known after the first pass over the text
+    max_len: Option<usize>,
+}
+
+impl<'a> WordBuckets<'a> {
+    pub fn new(text: &'a str) -> Self {
+        return Self {
+            text,
+            buckets: vec![],
+            known: HashSet::new(),
+            max_len: None,
+        };
+    }
+
+    /// Split out the words for any of the lengths that aren't loaded yet,
+    /// in a single pass over the text
+    fn load(&mut self, lens: &[usize]) {
+        let mut missing = vec![false; lens.iter().max().map_or(0, |m| m + 1)];
+        for &len in lens {
+            if self.buckets.len() <= len {
+                self.buckets.resize(len + 1, None);
+            }
+            if self.buckets[len].is_none() {
+                missing[len] = true;
+                self.buckets[len] = Some(vec![]);
+            }
+        }
+        if self.max_len.is_some() && !missing.contains(&true) {
+            return;
+        }
+
+        let mut max_len = 0;
+        let mut start = 0;
+        let ends = memchr_iter(b'\n', self.text.as_bytes()).chain([self.text.len()]);
+        for end in ends {
+            // Like get_words, there's no word after a trailing newline
+            if end == self.text.len() && start == end {
+                break;
+            }
+            let len = end - start;
+            max_len = max_len.max(len);
+            if missing.get(len) == Some(&true) {
+                let word = &self.text[start..end];
+                self.buckets[len].as_mut().unwrap().push(word);
+                self.known.insert(Caseless(word));
+            }
+            start = end + 1;
+        }
+        self.max_len = Some(max_len);
+    }
+
+    /// Check for the word in any case, the same as the checker does, so
+    /// "The" and "paris" are both known if the dictionary has "the" and
+    /// "Paris"
+    pub fn knows(&mut self, word: &str) -> bool {
+        self.load(&[word.len(), fold_case(word).len()]);
+        return self.known.contains(&Caseless(word));
+    }
+
+    /// Find the best matches in each of the buckets, merged into the `top`
+    /// best overall
+    fn find_in(&self, word: &str, lens: &[usize], top: usize, found: &mut Vec<(f32, &'a str)>) {
+        for &len in lens {
+            if let Some(Some(bucket)) = self.buckets.get(len) {
+                found.extend(find_word(word, bucket, top));
+            }
+        }
+        found.sort_by(by_rank);
+        dedup_variants(found);
+        found.truncate(top);
+    }
+
+    /// Find the `top` most similar words, the same as find_word does over
+    /// the whole list.  The lengths nearest the word are searched first,
+    /// which rules out the lengths too far off to beat the worst of those.
+    pub fn find_word(&mut self, word: &str, top: usize) -> Vec<(f32, &'a str)> {
+        let mut ret = vec![];
+        if top == 0 {
+            return ret;
+        }
+
+        let near: Vec<usize> = (word.len().saturating_sub(1)..=word.len() + 1).collect();
+        self.load(&near);
+        self.find_in(word, &near, top, &mut ret);
+        let cutoff = match ret.len() == top {
+            true => ret[top - 1].0,
+            false => -1.0,
+        };
+
+        // A tie can still win if the word comes first alphabetically
+        let rest: Vec<usize> = (0..=self.max_len.unwrap_or(0))
+            .filter(|len| !near.contains(len))
+            .filter(|&len| ratio_for(word.len().min(len), word.len() + len) >= cutoff)
+            .collect();
+        self.load(&rest);
+        self.find_in(word, &rest, top, &mut ret);
+
+        return ret;
+    }
+
+    /// Find the `top` most similar words out of the ones within
+    /// `max_distance` edits of the word.  Only the lengths that could be
+    /// that close are loaded.
+    #[cfg(test)]
+    pub fn find_near(
+        &mut self,
+        word: &str,
+        top: usize,
+        max_distance: usize,
+    ) -> Vec<(f32, &'a str)> {
+        let near: Vec<&'a str> = self
+            .near(word, max_distance)
+            .into_iter()
+            .filter(|w| within_distance(word, w, max_distance))
+            .collect();
+
+        return find_word(word, &near, top);
+    }
+
+    /// The words of the lengths that could be within `max_distance` edits
+    /// of the word, in order of length
+    pub fn near(&mut self, word: &str, max_distance: usize) -> Vec<&'a str> {
+        // A char is 4 bytes at most
+        let spread = max_distance * 4;
+        let lens: Vec<usize> = (word.len().saturating_sub(spread)..=word.len() + spread).collect();
+
+        return self.words_of(&lens);
+    }
+
+    /// Every word, in order of length
+    pub fn all(&mut self) -> Vec<&'a str> {
+        // The first load finds the longest word
+        self.load(&[]);
+        let lens: Vec<usize> = (0..=self.max_len.unwrap_or(0)).collect();
+
+        return self.words_of(&lens);
+    }
+
+    fn words_of(&mut self, lens: &[usize]) -> Vec<&'a str> {
+        self.load(lens);
+        return lens
+            .iter()
+            .filter_map(|&len| self.buckets.get(len)?.as_ref())
+            .flatten()
+            .copied()
+            .collect();
+    }
+}
+
+#[test]
+fn test_word_buckets() {
+    use crate::util::{dict_text, get_words, BUILTIN_DICT};
+
+    let text = dict_text(BUILTIN_DICT);
+    let sample: String = get_words(text)
+        .iter()
+        .step_by(97)
+        .map(|w| format!("{}\n", w))
+        .collect();
+    let words = get_words(&sample);
+
+    let mut buckets = WordBuckets::new(&sample);
+    for word in ["recieve", "teh", "a", "spelling", "xyzzy", "naïve"] {
+        for top in [1, 5, 20] {
+            assert_eq!(
+                buckets.find_word(word, top),
+                find_word(word, &words, top),
+                "{} top {}",
+                word,
+                top
+            );
+        }
+    }
+
+    // Only the lengths near the word are needed
+    let mut buckets = WordBuckets::new(&sample);
+    buckets.find_word("spelling", 5);
+    let loaded = buckets.buckets.iter().filter(|b| b.is_some()).count();
+    assert!(loaded < buckets.max_len.unwrap());
+
+    // The same as filtering the whole list by distance
+    for word in ["recieve", "teh", "naïve"] {
+        let near: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|w| within_distance(word, w, 2))
+            .collect();
+        assert_eq!(buckets.find_near(word, 5, 2), find_word(word, &near, 5));
+    }
+
+    let mut all = WordBuckets::new(&sample).all();
+    all.sort_unstable();
+    let mut want = words.clone();
+    want.sort_unstable();
+    assert_eq!(all, want);
+
+    assert!(buckets.knows(words[10]));
+    assert!(!buckets.knows("xyzzy"));
+    assert!(buckets.knows(&words[10].to_uppercase()));
+    let mut names = WordBuckets::new("Paris\nZzyzx\nthe");
+    assert!(names.knows("paris") && names.knows("ZZYZX") && names.knows("The"));
+    assert!(buckets.find_word("a", 0).is_empty());
+    assert!(WordBuckets::new("").find_word("a", 5).is_empty());
+}
@@ -0,0 +1,160 @@
+use crate::diagnostic::Diagnostic;
+use crate::util::{
+    dict_text, get_words, read_bytes, CheckOpts, Lookups, SuggestOpts, BUILTIN_DICT,
+};
+use crate::wordset::{fold_case, SetImpl, WordSet};
+use anyhow::Result;
+use std::{collections::HashSet, io::BufRead, path::Path};
+
+/// Checks words and text against a dictionary, the same way the `spel`
+/// command does.  The words are borrowed from the dictionary text.
+#[derive(Debug)]
+pub struct SpellChecker<'a> {
+    /// The dictionary words in their order, for finding suggestions
+    words: Vec<&'a str>,
+    set: WordSet<'a>,
+    ign_list: HashSet<String>,
+    opts: CheckOpts,
+    lookups: Lookups,
+}
+
+impl<'a> SpellChecker<'a> {
+    /// A checker for a dictionary of 1 word per line
+    pub fn new(dict: &'a str) -> Self {
+        return Self::with_options(dict, HashSet::new(), CheckOpts::default());
+    }
+
+    /// A checker that also accepts the words in the ignore list, which
+    /// should be lowercase, and checks text the way the options say
+    pub fn with_options(dict: &'a str, ign_list: HashSet<String>, opts: CheckOpts) -> Self {
+        let words = get_words(dict);
+        let set = WordSet::new(&words, SetImpl::default());
+        let lookups = Lookups::build(&set, &ign_list, &opts, None);
+
+        return Self {
+            words,
+            set,
+            ign_list,
+            opts,
+            lookups,
+        };
+    }
+
+    /// A checker for the built in English dictionary
+    pub fn builtin() -> SpellChecker<'static> {
+        return SpellChecker::new(dict_text(BUILTIN_DICT));
+    }
+
+    /// A checker for a dictionary file, which is read into `buf` for the
+    /// words to borrow from
+    pub fn load(path: &Path, buf: &'a mut String) -> Result<Self> {
+        *buf = match String::from_utf8(read_bytes(path)?) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        };
+        let text: &'a String = buf;
+
+        return Ok(Self::new(text));
+    }
+
+    /// Check whether a word is spelled right, in any case
+    pub fn is_correct(&self, word: &str) -> bool {
+        return self
+            .lookups
+            .is_correct(word, &self.set, &self.ign_list, &self.opts);
+    }
+
+    /// The `top` dictionary words most like the word, best first
+    pub fn suggest(&self, word: &str, top: usize) -> Vec<&'a str> {
+        return self.suggest_with(word, SuggestOpts::new(top));
+    }
+
+    /// The dictionary words most like the word, best first, limited the
+    /// way the options say
+    pub fn suggest_with(&self, word: &str, opts: SuggestOpts) -> Vec<&'a str> {
+        return opts
+            .find(&fold_case(word), &self.words)
+            .into_iter()
+            .map(|(_, w)| w)
+            .collect();
+    }
+
+    /// Check the text from a reader, like `spel --file` does.  The name is
+    /// used in the findings and picks the filter for the text, the same as
+    /// a file's name would.
+    pub fn check_reader<R: BufRead>(&self, name: &Path, reader: R) -> Vec<Diagnostic> {
+        return crate::util::check_file(
+            name,
+            reader,
+            &self.set,
+            &self.ign_list,
+            &self.opts,
+            &self.lookups,
+        );
+    }
+}
+
+#[test]
+fn test_spell_checker() {
+    use crate::diagnostic::Kind;
+    use std::io::Cursor;
+
+    let checker = SpellChecker::with_options(
+        "cat\ndog\nhouse\nmouse\n",
+        ["spel".to_string()].into_iter().collect(),
+        CheckOpts::default(),
+    );
+    assert!(checker.is_correct("cat"));
+    assert!(checker.is_correct("DOG"));
+    assert!(checker.is_correct("spel"));
+    assert!(!checker.is_correct("hose"));
+
+    assert_eq!(checker.suggest("Hose", 2), ["house", "mouse"]);
+    assert!(checker.suggest("hose", 0).is_empty());
+    let near = SuggestOpts {
+        max_distance: Some(1),
+        ..SuggestOpts::new(2)
+    };
+    assert_eq!(checker.suggest_with("Hose", near), ["house"]);
+
+    let diags = checker.check_reader(
+        Path::new("a.md"),
+        Cursor::new("The cat\n\n`hose` and a dgo\n"),
+    );
+    let found: Vec<(u64, &str, Kind)> = diags
+        .iter()
+        .map(|d| (d.line, d.word.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, "the", Kind::Misspelling),
+            (3, "and", Kind::Misspelling),
+            (3, "a", Kind::Misspelling),
+            (3, "dgo", Kind::Misspelling)
+        ]
+    );
+
+    // Set to check all of it, the code span's word is found too
+    let plain = SpellChecker::with_options(
+        "the\ncat\n",
+        HashSet::new(),
+        CheckOpts::default().with_filter(Some(crate::filter::Filter::Plain)),
+    );
+    let diags = plain.check_reader(Path::new("a.md"), Cursor::new("The `hose`\n"));
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].word, "hose");
+
+    let builtin = SpellChecker::builtin();
+    assert!(builtin.is_correct("spelling"));
+    let mut buf = String::new();
+    assert!(SpellChecker::load(Path::new("/nonexistent/dict"), &mut buf).is_err());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dict.txt");
+    std::fs::write(&path, b"caf\xe9\ncat\n").unwrap();
+    let checker = SpellChecker::load(&path, &mut buf).unwrap();
+    assert!(checker.is_correct("Cat"));
+    assert!(!checker.is_correct("dog"));
+    assert_eq!(checker.suggest("caf", 1), ["caf\u{fffd}"]);
+}
@@ -0,0 +1,1517 @@
+//! The `spel` command, which src/main.rs runs
+use crate::bench::{
+    bench_queries, bench_ratio, bench_sets, bench_suggest, print_bench, print_ratio, print_suggest,
+};
+use crate::buckets::WordBuckets;
+use crate::compound::CompoundConfig;
+use crate::config::{Config, EffectiveConfig, Settings};
+use crate::diagnostic::{Kind, PositionEncoding};
+use crate::distance::{Distances, Metric};
+use crate::doctor::Doctor;
+use crate::filter::Filter;
+use crate::fix::{
+    apply_edits, fix_named_words, journal_entries, AutoFixer, Corrections, Fixer, SessionState,
+};
+use crate::git::commit_messages;
+use crate::history::{history_table, is_database, read_history, RunSummary, BAR_WIDTH};
+use crate::hyphenate::hyphenated;
+use crate::journal::{revert, Journal};
+use crate::keyboard::Layout;
+use crate::ngram::BigramModel;
+use crate::output::{plain, ColorWhen, Format, GroupBy, OutputOpts, Printer};
+use crate::personal::PersonalDict;
+use crate::project::{ProjectLists, PROJECT_DIR};
+use crate::report::{print_vocab, vocab_report};
+use crate::results::{diff_results, read_results, show_result, ResultsDiff};
+use crate::util::*;
+use crate::wordset::{SetImpl, WordSet};
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fmt, fs,
+    io::{self, stdin, stdout, Cursor, IsTerminal},
+    path::{Path, PathBuf},
+    process, thread,
+};
+
+/// The kinds of informational notes that can be turned on with --notes
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Note {
+    /// A usage hint for commonly confused words, like affect/effect
+    Confusables,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Reports across a set of files or directories
+    Report {
+        #[command(subcommand)]
+        report: Report,
+    },
+    /// Interactively fix the misspellings in files, prompting for a
+    /// replacement for each one
+    Fix {
+        /// A file of "wrong=right" corrections that are always applied
+        /// without asking.  "c" at the prompt adds to it
+        #[arg(long, default_value = "~/.spel_corrections")]
+        corrections: PathBuf,
+        /// Where the progress and decisions of the session are saved, so
+        /// that it can be resumed after quitting
+        #[arg(long, default_value = ".spel_session.json")]
+        session: PathBuf,
+        /// Pick up a previous session where it left off
+        #[arg(long, default_value_t = false)]
+        resume: bool,
+        /// Every replacement is recorded here so that the run can be
+        /// undone with `spel revert`.  It is started over by the first
+        /// replacement unless resuming, so a run that fixes nothing keeps
+        /// the last one's
+        #[arg(long, default_value = ".spel_journal.jsonl")]
+        journal: PathBuf,
+        /// Only replace this misspelling, given as "wrong=right", without
+        /// asking.  Can be given more than once
+        #[arg(long, value_name = "WRONG=RIGHT", value_parser = parse_fix_word)]
+        fix_word: Vec<(String, String)>,
+        /// Don't prompt, just apply the confident fixes and report the
+        /// rest
+        #[arg(long, default_value_t = false)]
+        auto: bool,
+        /// With --auto, only apply the top suggestion when its similarity
+        /// score is above this, from 0 to 1.  Common typos and the
+        /// corrections file are always applied
+        #[arg(long, default_value = "0.9", requires = "auto")]
+        fix_threshold: f32,
+        /// With --auto, only apply fixes from the built in table of common
+        /// typos and the corrections file
+        #[arg(long, default_value_t = false, requires = "auto")]
+        typos_only: bool,
+        /// The files and/or directories to fix
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Check the commit messages in a range of revisions, reporting
+    /// misspellings as "sha:line:col"
+    GitLog {
+        /// The revision range to check, e.g. v1.0..HEAD
+        #[arg(long, default_value = "HEAD")]
+        range: String,
+    },
+    /// Check the doc comments (`///`, `//!` and `#[doc]`) in Rust
+    /// sources, skipping code blocks, inline code and intra-doc links
+    Rustdoc {
+        /// The .rs files and/or directories to check
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Check the current crate: the doc comments under src/, the README.md
+    /// and the Cargo.toml metadata.  This is what `cargo spel` runs
+    Cargo,
+    /// Compare how fast each way of storing the dictionary is to build and
+    /// look words up in
+    Bench {
+        /// Files and/or directories whose words are looked up.  Without
+        /// any, every dictionary word and a misspelling of it are
+        paths: Vec<PathBuf>,
+    },
+    /// Show the setup: the config file, ignore lists, dictionary and the
+    /// filter used for each type of file, along with any problems with
+    /// them
+    Doctor {
+        /// The files and/or directories to show the filters for
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+    },
+    /// Add words to your personal dictionary, which is used for every
+    /// check
+    Add {
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
+    /// Remove words from your personal dictionary
+    Remove {
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
+    /// Show suggestions for each of a list of words, like single word mode
+    Suggest {
+        /// The file of words, 1 per line (or NUL separated, as printed
+        /// with --words-only --print0).  "-" reads them from stdin
+        #[arg(long, value_name = "FILE", required = true)]
+        from_file: PathBuf,
+    },
+    /// Show how far apart 2 words are by each of the metrics, and the
+    /// score suggestions are ranked by with --metric
+    Distance {
+        /// The misspelling, for the metrics where the order matters
+        word: String,
+        /// The suggestion to compare it with
+        suggestion: String,
+    },
+    /// Show where each word can be broken at the end of a line, like
+    /// "hy-phen-ation", noting the words that aren't in the dictionary
+    Hyphenate {
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
+    /// Compare the findings of 2 runs saved with `spel -f --format json`,
+    /// listing the new, fixed and persisting ones.  Exits with an error if
+    /// there are new ones
+    DiffResults {
+        /// The results from before, like from the base of a PR
+        old: PathBuf,
+        /// The results from after
+        new: PathBuf,
+    },
+    /// Follow the findings over time, from the runs written with --history
+    History {
+        #[command(subcommand)]
+        history: History,
+    },
+    /// Undo the replacements recorded in a `spel fix` journal
+    Revert {
+        /// The journal file written by `spel fix`
+        #[arg(default_value = ".spel_journal.jsonl")]
+        journal: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Report {
+    /// List every unknown word, sorted by how often it occurs, with example
+    /// locations
+    Vocab {
+        /// The number of example locations to show for each word
+        #[arg(short, long, default_value = "3")]
+        examples: usize,
+        /// The files and/or directories to report on
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum History {
+    /// List the runs in the history file, with a graph of their findings
+    /// and how they've changed since the first
+    Show {
+        /// The history file, the --history one by default
+        file: Option<PathBuf>,
+        /// Only show the last N runs
+        #[arg(long, value_name = "N")]
+        last: Option<usize>,
+    },
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    author = "Jay Deiman",
+    version,
+    about = "Check spelling",
+    long_about = "Check the spelling of a word on the command line or check \
+        the spelling of the words in a file or files"
+)]
+struct Args {
+    /// The argument(s) here are file(s) instead of a word
+    #[arg(short, long, default_value_t = false)]
+    file: bool,
+    /// Treat the arguments as a phrase, only showing suggestions for the
+    /// misspelled words followed by the corrected phrase
+    #[arg(short, long, default_value_t = false, conflicts_with = "file")]
+    phrase: bool,
+    /// Check this text like --phrase does, so a sentence can be pasted in
+    /// as it is, in quotes
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["file", "phrase", "word"])]
+    text: Option<String>,
+    /// A comma-separated list of words to ignore, or "@FILE" for the
+    /// words in a file, 1 per line.  Can be given more than once.  Only
+    /// relevant with --file or --phrase
+    #[arg(short, long, value_name = "WORDS")]
+    ignore: Vec<String>,
+    /// Ignore list file, this will be added to anything specified with
+    /// the --ignore option.  The file should be 1 item (word) per line
+    #[arg(short = 'I', long, default_value = "~/.spel_ignore")]
+    ignore_file: PathBuf,
+    /// Also ignore the words read from stdin, 1 per line (or NUL
+    /// separated), for this run only
+    #[arg(long, default_value_t = false)]
+    ignore_stdin: bool,
+    /// A comma-separated list of finding fingerprints not to report, from
+    /// --format json or sarif, or "@FILE" for the ones in a file, 1 per
+    /// line.  Can be given more than once.
+    #[arg(long, value_name = "IDS")]
+    suppress: Vec<String>,
+    /// When incorrect in a single word check, show the top N possible
+    /// correct spellings
+    #[arg(short, long, default_value = "5")]
+    top: usize,
+    /// Only suggest words within N edits (a letter added, removed or
+    /// changed) of the misspelling, even if that's fewer than --top
+    #[arg(long, value_name = "N")]
+    max_distance: Option<usize>,
+    /// Show the top N suggestions for each misspelling in --file mode,
+    /// --top of them if N isn't given
+    #[arg(long, value_name = "N", num_args = 0..=1, require_equals = true)]
+    suggest: Option<Option<usize>>,
+    /// How suggestions are found in --phrase mode and `spel fix`.  With an
+    /// index, only words within --max-distance (2 by default) edits are
+    /// suggested, which is much quicker for a lot of misspellings
+    #[arg(long, value_enum, default_value_t = SuggestIndex::Scan)]
+    suggest_index: SuggestIndex,
+    /// How suggestions are ranked.  With damerau, --max-distance counts a
+    /// swap of 2 letters next to each other as 1 edit too
+    #[arg(long, value_enum, default_value_t = Metric::Ratio)]
+    metric: Metric,
+    /// Also suggest the words that sound like the misspelling, however far
+    /// off the spelling is, like "physician" for "fizishun"
+    #[arg(long, default_value_t = false)]
+    phonetic: bool,
+    /// Rank the suggestions that only change letters to the ones on the
+    /// keys next to them on this keyboard higher, like "spell" for "spwll"
+    #[arg(long, value_enum, value_name = "LAYOUT")]
+    keyboard: Option<Layout>,
+    /// The output format in --file mode, and for --show-config, where
+    /// json is the only one that differs from the config file syntax.  In
+    /// single word mode and `spel hyphenate`, json gives an object per
+    /// word.  sarif is a single report of the whole run, for code scanning
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+    /// When to highlight the words with color, in the annotate and caret
+    /// formats and the prompts of `spel fix`
+    #[arg(long, value_enum, default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+    /// The units that columns are counted in, for output formats that
+    /// include columns.  This can be a comma-separated list of the
+    /// encodings a client supports, in which case the best one is picked,
+    /// like LSP does.  utf-16 is what LSP clients expect by default
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "utf-16")]
+    position_encoding: Vec<PositionEncoding>,
+    /// The number of the first line in the output, 0 or 1
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(0..=1))]
+    line_base: u64,
+    /// The number of the first column in the output, 0 or 1
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(0..=1))]
+    column_base: u64,
+    /// Show N lines of context before and after each finding in --file
+    /// mode.  The json and sarif formats can't show it
+    #[arg(short = 'C', long, default_value = "0")]
+    context: usize,
+    /// How to group the findings in --file mode.  Only the plain format
+    /// can group them by word
+    #[arg(long, value_enum, default_value_t = GroupBy::File)]
+    group_by: GroupBy,
+    /// In --file mode, report each misspelled word only once per file, with
+    /// the number of times it occurs
+    #[arg(long, default_value_t = false)]
+    unique_per_file: bool,
+    /// In --file mode, only report the first occurrence of each misspelled
+    /// word across all the files
+    #[arg(long, default_value_t = false)]
+    first_only: bool,
+    /// In --file mode, only print the misspelled words, once each and 1 per
+    /// line, e.g. for starting an ignore file
+    #[arg(long, default_value_t = false)]
+    words_only: bool,
+    /// In --file mode, end each finding (or word with --words-only) with a
+    /// NUL instead of a newline, for `xargs -0`
+    #[arg(long, default_value_t = false)]
+    print0: bool,
+    /// In --file mode, list the ignore entries that didn't match anything
+    /// at the end of the run, by where they came from
+    #[arg(long, default_value_t = false)]
+    report_unused_ignores: bool,
+    /// In --file mode, list how many words each ignore entry skipped at
+    /// the end of the run, most first, to spot entries hiding real
+    /// misspellings
+    #[arg(long, default_value_t = false)]
+    ignore_stats: bool,
+    /// When walking directories, only check the files matching one of
+    /// these globs, like "*.md" or "docs/**".  A glob without a "/"
+    /// matches any file or directory name.  Comma-separated, and can be
+    /// given more than once
+    #[arg(long, value_name = "GLOB", value_delimiter = ',')]
+    include: Vec<String>,
+    /// When walking directories, leave out the files and directories
+    /// matching any of these globs, like "vendor/**" or "*.min.js"
+    #[arg(long, value_name = "GLOB", value_delimiter = ',')]
+    exclude: Vec<String>,
+    /// When walking directories, check the files left out by the
+    /// .gitignore and .ignore files too
+    #[arg(long, default_value_t = false)]
+    no_ignore: bool,
+    /// Skip files larger than this many bytes in --file mode
+    #[arg(long, default_value = "10485760")]
+    max_file_size: u64,
+    /// The number of files to check at once in --file mode, 1 per CPU by
+    /// default.  The findings are still output in the order of the files
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+    /// In --file mode, report words that only differ from a dictionary
+    /// word by their diacritics (e.g. "resume" vs. "résumé") as variants
+    /// rather than misspellings
+    #[arg(long, default_value_t = false)]
+    fold_diacritics: bool,
+    /// In --file mode, accept unknown words that can be split into known
+    /// dictionary words, using the compounding rules for the given
+    /// language (de, nl, sv, da, no or generic)
+    #[arg(long, value_name = "LANG", value_parser = parse_compounds)]
+    compounds: Option<CompoundConfig>,
+    /// A bigram frequency file, with "first second count" per line, used
+    /// to rank suggestions by the surrounding words in --phrase mode and
+    /// for --real-word
+    #[arg(long, value_name = "FILE")]
+    ngrams: Option<PathBuf>,
+    /// Append a summary of each run's findings to this file, to follow
+    /// them over time with `spel history show`.  It's JSON lines, an
+    /// object per run, so it can't be a SQLite database
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+    /// Using the --ngrams model, flag correctly spelled words that are
+    /// likely confused with a similar word (their/there, affect/effect)
+    #[arg(long, default_value_t = false, requires = "ngrams")]
+    real_word: bool,
+    /// Filter every file as this type, only checking its prose, instead
+    /// of picking the filter by the file's extension.  "plain" turns
+    /// filtering off
+    #[arg(long, value_enum)]
+    filter: Option<Filter>,
+    /// In files that don't have a filter, like plain text and commit
+    /// messages, skip `inline code` in backticks
+    #[arg(long, default_value_t = false)]
+    skip_code_spans: bool,
+    /// In source files that don't have a filter, only check the comments,
+    /// going by the comment syntax for the file's extension
+    #[arg(long, default_value_t = false)]
+    comments_only: bool,
+    /// Check generated files too.  Files with a "generated by" or "do not
+    /// edit" header in their first few lines are skipped by default.
+    #[arg(long, default_value_t = false)]
+    check_generated: bool,
+    /// Check minified files too.  Files with long lines that are dense with
+    /// symbols or encoded data are skipped by default.
+    #[arg(long, default_value_t = false)]
+    check_minified: bool,
+    /// The config file, for settings that don't have an option, like which
+    /// Markdown elements get checked, and defaults for the ones that do.
+    /// It's fine for it not to exist
+    #[arg(long, default_value = "spel.toml")]
+    config: PathBuf,
+    /// Use the settings in the config file's [profile.NAME] section, on
+    /// top of the ones at the top level
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Print the settings a run would use, from the config file and the
+    /// options together, and exit.  Use --format json for tools
+    #[arg(long, default_value_t = false)]
+    show_config: bool,
+    /// Informational notes to show, in addition to misspellings
+    #[arg(long, value_enum, value_delimiter = ',')]
+    notes: Vec<Note>,
+    /// In single word mode, print nothing, just exit with 0 if the words
+    /// are all spelled correctly or 1 if not
+    #[arg(short, long, default_value_t = false)]
+    quiet: bool,
+    /// Use an alternate dictionary file.  This should be 1 word per line and
+    /// in a utf-8 character set.  Give it more than once to use the words
+    /// from all of them.
+    #[arg(short, long)]
+    dict: Vec<PathBuf>,
+    /// Check against the built in dictionary as well as the --dict file,
+    /// rather than the --dict file alone
+    #[arg(long, default_value_t = false)]
+    with_builtin: bool,
+    /// Your own word list, added to the dictionary for every check and
+    /// edited with `spel add` and `spel remove`
+    #[arg(
+        long,
+        value_name = "FILE",
+        default_value = "~/.local/share/spel/personal.txt"
+    )]
+    personal_dict: PathBuf,
+    /// Don't use the word lists in the repo's .spel directory, words.txt
+    /// for words to add to the dictionary and ignore.txt for words to
+    /// ignore
+    #[arg(long, default_value_t = false)]
+    no_project: bool,
+    /// The words from the repo's .spel/ignore.txt
+    #[arg(skip)]
+    project_ignore: Vec<String>,
+    /// The words read with --ignore-stdin
+    #[arg(skip)]
+    stdin_ignore: Vec<String>,
+    /// The data structure the dictionary is looked up in
+    #[arg(long, value_enum, default_value_t = SetImpl::Hash, hide = true)]
+    set_impl: SetImpl,
+    /// Turn on debug output
+    #[arg(short = 'D', long)]
+    debug: bool,
+    /// A single word or file or a number of files
+    #[arg()]
+    word: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+static LOGGER: GlobalLogger = GlobalLogger;
+
+struct GlobalLogger;
+
+/// This implements the logging to stderr from the `log` crate
+impl log::Log for GlobalLogger {
+    fn enabled(&self, meta: &log::Metadata) -> bool {
+        return meta.level() <= log::max_level();
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let d = chrono::Local::now();
+            eprintln!(
+                "{} - {} - {}:{} {} - {}",
+                d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                record.level(),
+                record.file().unwrap(),
+                record.line().unwrap(),
+                record.target(),
+                record.args(),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Log the error and exit with status 2, the same as for a usage error,
+/// when something the run needs can't be read or written
+trait OrExit<T> {
+    fn or_exit(self, msg: &str) -> T;
+}
+
+impl<T, E: fmt::Display> OrExit<T> for Result<T, E> {
+    fn or_exit(self, msg: &str) -> T {
+        return match self {
+            Ok(value) => value,
+            Err(e) => {
+                error!("{}: {:#}", msg, e);
+                process::exit(2);
+            }
+        };
+    }
+}
+
+/// Validate the language passed to --compounds
+fn parse_compounds(lang: &str) -> Result<CompoundConfig, String> {
+    return CompoundConfig::for_lang(lang)
+        .ok_or_else(|| format!("no compound splitting rules for \"{}\"", lang));
+}
+
+/// Parse a "wrong=right" pair for --fix-word
+fn parse_fix_word(pair: &str) -> Result<(String, String), String> {
+    return match pair.split_once('=') {
+        Some((wrong, right)) if !wrong.trim().is_empty() && !right.trim().is_empty() => {
+            Ok((wrong.trim().to_lowercase(), right.trim().to_string()))
+        }
+        _ => Err(format!("expected WRONG=RIGHT, got \"{}\"", pair)),
+    };
+}
+
+/// The options for finding suggestions
+fn suggest_opts(args: &Args) -> SuggestOpts {
+    return SuggestOpts {
+        top: args.top,
+        max_distance: args.max_distance,
+        index: args.suggest_index,
+        metric: args.metric,
+        phonetic: args.phonetic,
+        keyboard: args.keyboard,
+    };
+}
+
+/// The options for printing the findings
+fn output_opts(args: &Args) -> OutputOpts {
+    return OutputOpts {
+        format: args.format,
+        group_by: args.group_by,
+        unique_per_file: args.unique_per_file,
+        first_only: args.first_only,
+        encoding: PositionEncoding::negotiate(&args.position_encoding),
+        line_base: args.line_base,
+        column_base: args.column_base as usize,
+        color: args.color.enabled(stdout().is_terminal()),
+        context: args.context,
+        words_only: args.words_only,
+        print0: args.print0,
+    };
+}
+
+/// The number of files to check at once, from --jobs or the number of CPUs
+fn jobs(args: &Args) -> usize {
+    return args
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+}
+
+/// The --include and --exclude globs for collecting the files to check
+fn walk_opts(args: &Args) -> WalkOpts {
+    return WalkOpts {
+        include: args.include.clone(),
+        exclude: args.exclude.clone(),
+        no_ignore: args.no_ignore,
+    };
+}
+
+/// Find the files to check in a crate: the README.md and Cargo.toml, and
+/// the Rust sources under src/
+fn crate_files(dir: &Path, walk: &WalkOpts) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let manifest = dir.join("Cargo.toml");
+    if !manifest.exists() {
+        return (vec![], vec![]);
+    }
+
+    let mut docs = vec![manifest];
+    let readme = dir.join("README.md");
+    if readme.exists() {
+        docs.push(readme);
+    }
+
+    let sources = collect_files(&[dir.join("src")], walk)
+        .into_iter()
+        .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+        .collect();
+
+    return (docs, sources);
+}
+
+/// The words to ignore from the options and the project's word lists,
+/// reading the files through `lists`
+fn ignore_list(args: &Args, lists: &mut WordLists) -> HashSet<String> {
+    let mut ret = lists.ignore_list(&args.ignore, &args.ignore_file);
+    ret.extend(args.project_ignore.iter().cloned());
+    ret.extend(args.stdin_ignore.iter().cloned());
+
+    return to_hashset(ret);
+}
+
+/// The words to ignore from each of the places they can come from
+fn ignore_sources(args: &Args, lists: &mut WordLists) -> Vec<(String, Vec<String>)> {
+    return vec![
+        ("--ignore".to_string(), lists.ignore_words(&args.ignore)),
+        (
+            args.ignore_file.display().to_string(),
+            lists.ignore_file(&args.ignore_file),
+        ),
+        (
+            format!("{}/ignore.txt", PROJECT_DIR),
+            args.project_ignore.clone(),
+        ),
+        ("--ignore-stdin".to_string(), args.stdin_ignore.clone()),
+    ];
+}
+
+/// Print the ignore entries that didn't match anything in any of the
+/// groups they're in, to stderr
+fn print_unused_ignores(used: &BTreeMap<(String, String), usize>) {
+    let unused: Vec<&(String, String)> = used
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(entry, _)| entry)
+        .collect();
+    if unused.is_empty() {
+        return;
+    }
+
+    eprintln!("\n{} unused ignore entries:", unused.len());
+    for (source, word) in unused {
+        eprintln!("  {}: {}", source, word);
+    }
+}
+
+/// Print how many words each ignore entry skipped, most first, to stderr
+fn print_ignore_stats(used: &BTreeMap<(String, String), usize>) {
+    let mut stats: Vec<(&(String, String), &usize)> = used.iter().collect();
+    if stats.is_empty() {
+        return;
+    }
+    // Stable, so ties stay in source then word order
+    stats.sort_by(|a, b| b.1.cmp(a.1));
+
+    eprintln!("\nIgnore entry hits:");
+    for ((source, word), count) in stats {
+        eprintln!("  {:>6}  {}: {}", count, source, word);
+    }
+}
+
+/// Read the words for --ignore-stdin, unless something else needs stdin
+fn read_stdin_ignore(args: &Args) -> Vec<String> {
+    let conflict = match &args.command {
+        Some(Command::Suggest { from_file }) if from_file.as_os_str() == "-" => {
+            Some("spel suggest --from-file -")
+        }
+        Some(Command::Fix { auto, fix_word, .. }) if !auto && fix_word.is_empty() => {
+            Some("spel fix without --auto or --fix-word")
+        }
+        None if args.file && args.word.iter().any(|w| w == "-") => Some("spel -f -"),
+        _ => None,
+    };
+    if let Some(other) = conflict {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--ignore-stdin can't be used with {}, which reads stdin too",
+                    other
+                ),
+            )
+            .exit();
+    }
+    let text = io::read_to_string(stdin()).or_exit("Error reading the words to ignore");
+
+    return word_list(&text);
+}
+
+/// Find the repo's word lists, unless they're turned off
+fn project_lists(args: &Args) -> ProjectLists {
+    if args.no_project {
+        return ProjectLists::default();
+    }
+    let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+    return ProjectLists::find(&cwd);
+}
+
+/// A set of files that are checked the same way
+struct FileGroup {
+    files: Vec<PathBuf>,
+    opts: CheckOpts,
+    ign_list: HashSet<String>,
+    /// Where the ignore entries came from, for --report-unused-ignores
+    ignore_sources: Vec<(String, Vec<String>)>,
+    /// The fingerprints of the findings not to report
+    suppress: HashSet<String>,
+    /// Whether findings in these files make the run fail
+    fail: bool,
+}
+
+/// Split the files up by the config file's overrides, with the settings of
+/// the override each one matches on top of the others.  `filter` is used
+/// for all of them if it's given.
+fn file_groups(
+    args: &Args,
+    matches: &ArgMatches,
+    config: &Config,
+    settings: &Settings,
+    files: Vec<PathBuf>,
+    filter: Option<Filter>,
+) -> Vec<FileGroup> {
+    // The files that no override matches come first
+    let mut split = vec![vec![]; config.overrides.len() + 1];
+    for file in files {
+        let i = config
+            .overrides
+            .iter()
+            .position(|o| o.matches(&file, &config.dir));
+        split[i.map_or(0, |i| i + 1)].push(file);
+    }
+
+    // The groups mostly share their word lists, which are only read once
+    let mut lists = WordLists::default();
+    let mut ret = vec![];
+    for (i, files) in split.into_iter().enumerate() {
+        if files.is_empty() {
+            continue;
+        }
+        let settings = match i {
+            0 => settings.clone(),
+            _ => settings.merge(&config.overrides[i - 1].settings),
+        };
+        let mut args = args.clone();
+        apply_settings(&mut args, matches, &settings);
+
+        let mut opts = check_opts(&args, &settings);
+        if filter.is_some() {
+            opts.filter = filter;
+        }
+        ret.push(FileGroup {
+            files,
+            opts,
+            ign_list: ignore_list(&args, &mut lists),
+            ignore_sources: match args.report_unused_ignores || args.ignore_stats {
+                true => ignore_sources(&args, &mut lists),
+                false => vec![],
+            },
+            suppress: to_hashset(lists.ignore_words(&args.suppress)),
+            fail: settings.fail.unwrap_or(true),
+        });
+    }
+
+    return ret;
+}
+
+/// Check the files and print the findings, as in --file mode.  Each group
+/// of files can be checked with different options.  This exits with 1 if
+/// there are findings in any of the groups that fail, or 2 if any of the
+/// files couldn't be read.
+fn check_and_print(
+    args: &Args,
+    groups: &[FileGroup],
+    words: Vec<&str>,
+    ngrams: Option<BigramModel>,
+) {
+    // Convert the word list to hashset for fast lookups
+    let wset = WordSet::new(&words, args.set_impl);
+
+    let mut suggest = args.suggest.map(|top| {
+        let opts = SuggestOpts {
+            top: top.unwrap_or(args.top),
+            ..suggest_opts(args)
+        };
+        SuggestCache::new(&words, opts)
+    });
+    let mut printer = Printer::new(output_opts(args));
+    let mut skipped = vec![];
+    let mut failed = false;
+    let mut summary = RunSummary::new();
+    // How many words each ignore entry, by where it's from, skipped
+    let mut used: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for group in groups {
+        let lookups = Lookups::build(&wset, &group.ign_list, &group.opts, ngrams.clone());
+        let opts = CheckOpts {
+            keep_text: printer.needs_text(),
+            ..group.opts.clone()
+        };
+        skipped.extend(check_files(
+            &group.files,
+            &wset,
+            &group.ign_list,
+            &opts,
+            &lookups,
+            jobs(args),
+            |_, text, mut diags| {
+                diags.retain(|d| !group.suppress.contains(&d.fingerprint));
+                if let Some(suggest) = &mut suggest {
+                    diags.iter_mut().for_each(|d| suggest.add_to(d));
+                }
+                failed |= group.fail && diags.iter().any(|d| d.kind != Kind::Note);
+                summary.add(&diags);
+                printer.add(text, diags);
+            },
+        ));
+        for (source, entries) in &group.ignore_sources {
+            for entry in entries {
+                *used.entry((source.clone(), entry.clone())).or_default() +=
+                    lookups.ignore_count(entry);
+            }
+        }
+    }
+    printer.finish();
+    print_skip_summary(&skipped);
+    if let Some(history) = &args.history {
+        summary.skipped = skipped.len();
+        if let Err(e) = summary.append(history) {
+            warn!("Failed to write to the history: {}", e);
+        }
+    }
+    if args.report_unused_ignores {
+        print_unused_ignores(&used);
+    }
+    if args.ignore_stats {
+        print_ignore_stats(&used);
+    }
+
+    if skipped
+        .iter()
+        .any(|s| matches!(s.reason, SkipReason::OpenFailed(_)))
+    {
+        process::exit(2);
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// The options for checking files, from the args and config file
+fn check_opts(args: &Args, settings: &Settings) -> CheckOpts {
+    return CheckOpts {
+        max_file_size: args.max_file_size,
+        fold_diacritics: args.fold_diacritics,
+        compounds: args.compounds.clone(),
+        real_word: args.real_word,
+        confusable_notes: args.notes.contains(&Note::Confusables),
+        filter: args.filter,
+        markdown: settings.markdown.unwrap_or_default(),
+        skip_code_spans: args.skip_code_spans,
+        comments_only: args.comments_only,
+        check_generated: args.check_generated,
+        check_minified: args.check_minified,
+        keep_text: true,
+    };
+}
+
+/// Use the setting if the option wasn't given on the command line
+fn fill<T>(arg: &mut T, given: bool, setting: Option<T>) {
+    if let (false, Some(value)) = (given, setting) {
+        *arg = value;
+    }
+}
+
+/// Fill in the options that weren't given on the command line from the
+/// config file's settings
+fn apply_settings(args: &mut Args, matches: &ArgMatches, settings: &Settings) {
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let s = settings.clone();
+
+    fill(&mut args.filter, given("filter"), s.filter.map(Some));
+    fill(
+        &mut args.skip_code_spans,
+        given("skip_code_spans"),
+        s.skip_code_spans,
+    );
+    fill(
+        &mut args.comments_only,
+        given("comments_only"),
+        s.comments_only,
+    );
+    fill(
+        &mut args.check_generated,
+        given("check_generated"),
+        s.check_generated,
+    );
+    fill(
+        &mut args.check_minified,
+        given("check_minified"),
+        s.check_minified,
+    );
+    fill(&mut args.dict, given("dict"), s.dict);
+    fill(
+        &mut args.with_builtin,
+        given("with_builtin"),
+        s.with_builtin,
+    );
+    fill(
+        &mut args.personal_dict,
+        given("personal_dict"),
+        s.personal_dict,
+    );
+    fill(&mut args.ignore, given("ignore"), s.ignore);
+    fill(&mut args.ignore_file, given("ignore_file"), s.ignore_file);
+    fill(&mut args.suppress, given("suppress"), s.suppress);
+    fill(&mut args.include, given("include"), s.include);
+    fill(&mut args.exclude, given("exclude"), s.exclude);
+    fill(&mut args.no_ignore, given("no_ignore"), s.no_ignore);
+    fill(&mut args.ngrams, given("ngrams"), s.ngrams.map(Some));
+    fill(&mut args.history, given("history"), s.history.map(Some));
+    fill(&mut args.real_word, given("real_word"), s.real_word);
+    fill(
+        &mut args.fold_diacritics,
+        given("fold_diacritics"),
+        s.fold_diacritics,
+    );
+    // The language was checked when the config was loaded
+    fill(
+        &mut args.compounds,
+        given("compounds"),
+        s.compounds.map(|l| CompoundConfig::for_lang(&l)),
+    );
+    fill(
+        &mut args.max_file_size,
+        given("max_file_size"),
+        s.max_file_size,
+    );
+    fill(&mut args.jobs, given("jobs"), s.jobs.map(Some));
+    fill(&mut args.top, given("top"), s.top);
+    fill(
+        &mut args.max_distance,
+        given("max_distance"),
+        s.max_distance.map(Some),
+    );
+    fill(&mut args.metric, given("metric"), s.metric);
+    fill(&mut args.phonetic, given("phonetic"), s.phonetic);
+    fill(&mut args.keyboard, given("keyboard"), s.keyboard.map(Some));
+    fill(
+        &mut args.suggest,
+        given("suggest"),
+        s.suggest.map(|n| Some(Some(n))),
+    );
+}
+
+/// Put together the config file and the options for --show-config
+fn effective_config(args: &Args, config: &Config, settings: &Settings) -> EffectiveConfig {
+    let value_name = |v: clap::builder::PossibleValue| v.get_name().to_string();
+    let ignore = ignore_words(&args.ignore);
+
+    return EffectiveConfig {
+        config_file: Some(args.config.clone()).filter(|p| p.exists()),
+        profile: args.profile.clone(),
+        project_dir: project_lists(args).dir,
+        ignore,
+        ignore_file: args.ignore_file.clone(),
+        include: args.include.clone(),
+        exclude: args.exclude.clone(),
+        no_ignore: args.no_ignore,
+        suppress: ignore_words(&args.suppress),
+        dict: args.dict.clone(),
+        with_builtin: args.with_builtin,
+        personal_dict: args.personal_dict.clone(),
+        ngrams: args.ngrams.clone(),
+        history: args.history.clone(),
+        filter: args
+            .filter
+            .and_then(|f| f.to_possible_value())
+            .map(value_name),
+        skip_code_spans: args.skip_code_spans,
+        comments_only: args.comments_only,
+        check_generated: args.check_generated,
+        check_minified: args.check_minified,
+        fold_diacritics: args.fold_diacritics,
+        compounds: args.compounds.clone(),
+        real_word: args.real_word,
+        notes: args
+            .notes
+            .iter()
+            .filter_map(|n| n.to_possible_value())
+            .map(value_name)
+            .collect(),
+        max_file_size: args.max_file_size,
+        jobs: jobs(args),
+        top: args.top,
+        max_distance: args.max_distance,
+        metric: args.metric,
+        phonetic: args.phonetic,
+        keyboard: args.keyboard,
+        suggest: args.suggest.map(|n| n.unwrap_or(args.top)),
+        fail: settings.fail.unwrap_or(true),
+        markdown: settings.markdown.unwrap_or_default(),
+        overrides: config.overrides.clone(),
+    };
+}
+
+/// Load the --dict files, merged into one if there's more than one, or
+/// the built in dictionary if there aren't any
+fn load_dicts(args: &Args) -> &'static str {
+    let mut texts = vec![];
+    if args.with_builtin {
+        texts.push(dict_text(BUILTIN_DICT));
+    }
+    for path in &args.dict {
+        // The dict files are kept for the whole run so the words can
+        // borrow from them
+        let fbytes = read_bytes(path).or_exit("Error reading specified dict file");
+        if !fbytes.is_empty() {
+            texts.push(dict_text(fbytes.leak()));
+        }
+    }
+
+    return match texts.len() {
+        0 => dict_text(BUILTIN_DICT),
+        1 => texts[0],
+        _ => merge_dicts(&texts),
+    };
+}
+
+/// Check the words and show suggestions for the misspelled ones, exiting
+/// with an error if there are any
+fn suggest_words(args: &Args, word_list: &[String], dict: &'static str) {
+    // Suggestions for single words only need the words of similar
+    // lengths, so the whole list isn't loaded for them
+    let correct = spell_check_words(
+        word_list,
+        WordBuckets::new(dict),
+        suggest_opts(args),
+        args.format == Format::Json,
+        args.debug,
+    );
+    if !correct {
+        process::exit(1);
+    }
+}
+
+/// Print the distances between the words by each metric
+fn print_distances(args: &Args, word: &str, suggestion: &str) {
+    let d = Distances::new(word, suggestion, &suggest_opts(args));
+    if args.format == Format::Json {
+        println!("{}", serde_json::to_string(&d).unwrap());
+        return;
+    }
+
+    let metric = args.metric.to_possible_value().unwrap();
+    println!("levenshtein   {}", d.levenshtein);
+    println!("damerau       {}", d.damerau);
+    println!("jaro-winkler  {:.4}", d.jaro_winkler);
+    println!("ratio         {:.4}", d.ratio);
+    println!(
+        "score         {:.4} (--metric {})",
+        d.score,
+        metric.get_name()
+    );
+}
+
+/// Print the break points of each word, and whether the dictionary has it
+fn hyphenate_words(args: &Args, words: &[String], dict: &'static str) {
+    let mut buckets = WordBuckets::new(dict);
+    for word in words {
+        let known = buckets.knows(word);
+        let hyphenated = hyphenated(word);
+        match args.format {
+            Format::Json => println!(
+                "{}",
+                serde_json::json!({"word": word, "hyphenated": hyphenated, "known": known})
+            ),
+            _ if known => println!("{}", hyphenated),
+            _ => println!("{} (not in the dictionary)", hyphenated),
+        }
+    }
+}
+
+/// Add the words to the personal dictionary, or remove them from it
+fn edit_personal(args: &Args, words: &[String], add: bool) {
+    let mut personal = PersonalDict::load(&args.personal_dict);
+    let path = args.personal_dict.display();
+    for word in words {
+        let changed = match add {
+            true => personal.add(word),
+            false => personal.remove(word),
+        };
+        match (add, changed) {
+            (true, true) => println!("Added \"{}\" to {}", word, path),
+            (true, false) => println!("\"{}\" is already in {}", word, path),
+            (false, true) => println!("Removed \"{}\" from {}", word, path),
+            (false, false) => println!("\"{}\" isn't in {}", word, path),
+        }
+    }
+    personal
+        .save()
+        .or_exit("Error writing the personal dictionary");
+}
+
+/// Look over the setup for `spel doctor`, exiting with an error if there
+/// are any problems
+fn run_doctor(mut args: Args, matches: &ArgMatches, paths: &[PathBuf]) {
+    let mut doc = Doctor::default();
+    let settings = doc.config(&args.config, args.profile.as_deref());
+    apply_settings(&mut args, matches, &settings);
+    doc.ignore(&args.ignore, &args.ignore_file);
+    if args.dict.is_empty() || args.with_builtin {
+        doc.dictionary(None);
+    }
+    for path in &args.dict {
+        doc.dictionary(Some(path));
+    }
+    doc.personal(&PersonalDict::load(&args.personal_dict));
+    doc.project(&project_lists(&args));
+    doc.ngrams(args.ngrams.as_deref());
+    doc.filters(paths, &walk_opts(&args), &check_opts(&args, &settings));
+    doc.print();
+
+    if !doc.problems.is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Show how the findings of 2 runs compare, exiting with an error if any
+/// are new
+fn compare_results(old: &Path, new: &Path) {
+    let diff = diff_results(
+        &read_results(old).or_exit("Error reading the old results"),
+        &read_results(new).or_exit("Error reading the new results"),
+    );
+    print_results_diff(&diff);
+
+    if !diff.new.is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Print the runs in the history file, or the --history one
+fn show_history(args: &Args, file: Option<&Path>, last: Option<usize>) {
+    let Some(file) = file.or(args.history.as_deref()) else {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "No history file, give one or set --history",
+            )
+            .exit();
+    };
+    let runs = read_history(file).or_exit("Error reading the history");
+    let skip = runs.len().saturating_sub(last.unwrap_or(runs.len()));
+    print!("{}", history_table(&runs[skip..], BAR_WIDTH));
+}
+
+/// Print the new, fixed and persisting findings, each under a heading
+/// with how many there are
+fn print_results_diff(diff: &ResultsDiff) {
+    let sections = [
+        ("New", &diff.new),
+        ("Fixed", &diff.fixed),
+        ("Persisting", &diff.persisting),
+    ];
+    for (i, (title, findings)) in sections.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({}):", title, findings.len());
+        for f in findings {
+            println!("  {}", show_result(f));
+        }
+    }
+}
+
+/// Create a set of CLI args via the `clap` crate, along with the matches,
+/// which tell whether each option was given on the command line
+fn get_args() -> (Args, ArgMatches) {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    return (args, matches);
+}
+
+/// Set the global logger from the `log` crate
+fn setup_logging(args: &Args) {
+    let l = if args.debug {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(l);
+}
+
+/// Run the command with the program's args, exiting with its status
+pub fn run() {
+    let (mut args, matches) = get_args();
+    setup_logging(&args);
+    if let Some(text) = args.text.take() {
+        args.word = vec![text];
+        args.phrase = true;
+    }
+    if let Some(Command::Doctor { paths }) = &args.command {
+        let paths = paths.clone();
+        run_doctor(args, &matches, &paths);
+        return;
+    }
+
+    let config = Config::load(&args.config).or_exit("Error reading the config file");
+    let settings = config
+        .settings(args.profile.as_deref())
+        .or_exit("Error reading the config file");
+    apply_settings(&mut args, &matches, &settings);
+    if let Some(history) = args.history.as_deref().filter(|h| is_database(h)) {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!(
+                    "--history is written as JSON lines, not a database, \
+                    so it can't be \"{}\"",
+                    history.display()
+                ),
+            )
+            .exit();
+    }
+    if args.group_by == GroupBy::Word && args.format != Format::Plain {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--group-by word only works with --format plain",
+            )
+            .exit();
+    }
+    if args.context > 0 && matches!(args.format, Format::Json | Format::Sarif) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--context only works with --format plain, annotate or caret",
+            )
+            .exit();
+    }
+    if args.show_config {
+        let eff = effective_config(&args, &config, &settings);
+        match args.format {
+            Format::Json => println!("{}", eff.to_json()),
+            _ => print!("{}", eff.to_toml()),
+        }
+        return;
+    }
+    if args.ignore_stdin {
+        args.stdin_ignore = read_stdin_ignore(&args);
+    }
+
+    match &args.command {
+        Some(Command::Add { words }) => return edit_personal(&args, words, true),
+        Some(Command::Remove { words }) => return edit_personal(&args, words, false),
+        Some(Command::Distance { word, suggestion }) => {
+            return print_distances(&args, word, suggestion)
+        }
+        Some(Command::DiffResults { old, new }) => return compare_results(old, new),
+        Some(Command::History {
+            history: History::Show { file, last },
+        }) => return show_history(&args, file.as_deref(), *last),
+        _ => (),
+    }
+
+    let personal = PersonalDict::load(&args.personal_dict);
+    let project = project_lists(&args);
+    let dict = project.extend_dict(personal.extend_dict(load_dicts(&args)));
+    args.project_ignore = project.ignore;
+    if args.word.is_empty() && args.command.is_none() {
+        return;
+    }
+
+    if args.command.is_none() && !args.file && !args.phrase && args.quiet {
+        let mut words = WordBuckets::new(dict);
+        if !args.word.iter().all(|w| words.knows(w)) {
+            process::exit(1);
+        }
+        return;
+    }
+    if args.command.is_none() && !args.file && !args.phrase {
+        suggest_words(&args, &args.word, dict);
+        return;
+    }
+    if let Some(Command::Suggest { from_file }) = &args.command {
+        let text = match from_file.as_os_str() == "-" {
+            true => io::read_to_string(stdin()).or_exit("Error reading the word list"),
+            false => {
+                let fbytes = read_bytes(from_file).or_exit("Error reading the word list");
+                String::from_utf8_lossy(&fbytes).into_owned()
+            }
+        };
+        suggest_words(&args, &word_list(&text), dict);
+        return;
+    }
+    if let Some(Command::Hyphenate { words }) = &args.command {
+        hyphenate_words(&args, words, dict);
+        return;
+    }
+    let words = get_words(dict);
+
+    let ngrams = args
+        .ngrams
+        .as_ref()
+        .map(|p| BigramModel::load(p).or_exit("Error reading the ngrams file"));
+
+    let opts = check_opts(&args, &settings);
+
+    if let Some(Command::Report {
+        report: Report::Vocab { examples, paths },
+    }) = &args.command
+    {
+        let wset = WordSet::new(&words, args.set_impl);
+        let ign_list = ignore_list(&args, &mut WordLists::default());
+        let lookups = Lookups::build(&wset, &ign_list, &opts, None);
+        let files = collect_files(paths, &walk_opts(&args));
+
+        let (vocab, skipped) = vocab_report(
+            &files,
+            &wset,
+            &ign_list,
+            &opts,
+            &lookups,
+            jobs(&args),
+            *examples,
+        );
+        print_vocab(&vocab);
+        print_skip_summary(&skipped);
+    } else if let Some(Command::Fix {
+        corrections,
+        session,
+        resume,
+        journal,
+        fix_word,
+        auto,
+        fix_threshold,
+        typos_only,
+        paths,
+    }) = &args.command
+    {
+        let files = collect_files(paths, &walk_opts(&args));
+        if !fix_word.is_empty() {
+            let pairs: HashMap<String, String> = fix_word.iter().cloned().collect();
+            let mut journal = Journal::new(journal, false);
+            let (fixed, changed, skipped) =
+                fix_named_words(&files, &pairs, &mut journal, args.max_file_size);
+            eprintln!("Fixed {} word(s) in {} file(s)", fixed, changed);
+            print_skip_summary(&skipped);
+            return;
+        }
+
+        let wset = WordSet::new(&words, args.set_impl);
+        let ign_list = ignore_list(&args, &mut WordLists::default());
+        let corrections = Corrections::load(corrections).or_exit("Error reading corrections file");
+        if *auto {
+            let mut journal = Journal::new(journal, false);
+            let mut fixer = AutoFixer::new(
+                &words,
+                suggest_opts(&args),
+                corrections,
+                *fix_threshold,
+                *typos_only,
+            );
+            let out_opts = OutputOpts::default();
+            let (mut fixed, mut changed) = (0, 0);
+            let lookups = Lookups::build(&wset, &ign_list, &opts, ngrams);
+            let skipped = check_files(
+                &files,
+                &wset,
+                &ign_list,
+                &opts,
+                &lookups,
+                jobs(&args),
+                |fpath, text, diags| {
+                    let (edits, unfixed) = fixer.fix(text, diags);
+                    for d in &unfixed {
+                        println!("{}", plain(d, &out_opts));
+                    }
+
+                    if edits.is_empty() {
+                        return;
+                    }
+                    write_atomic(fpath, &apply_edits(text, &edits))
+                        .or_exit("Error writing fixed file");
+                    if let Err(e) = journal.append(&journal_entries(fpath, text, &edits)) {
+                        warn!("Failed to write to the journal: {}", e);
+                    }
+                    fixed += edits.len();
+                    changed += 1;
+                },
+            );
+            eprintln!("Fixed {} word(s) in {} file(s)", fixed, changed);
+            print_skip_summary(&skipped);
+            return;
+        }
+
+        let state = if *resume {
+            SessionState::load(session).or_exit("Error loading the session")
+        } else {
+            SessionState::default()
+        };
+        let mut journal = Journal::new(journal, *resume);
+        let out_opts = OutputOpts {
+            color: args.color.enabled(stdout().is_terminal()),
+            ..Default::default()
+        };
+
+        let mut fixer = Fixer::new(
+            &words,
+            suggest_opts(&args),
+            corrections,
+            state,
+            out_opts,
+            stdin().lock(),
+            stdout(),
+        );
+        let mut changed = 0;
+        let lookups = Lookups::build(&wset, &ign_list, &opts, ngrams);
+        let skipped = check_files(
+            &files,
+            &wset,
+            &ign_list,
+            &opts,
+            &lookups,
+            jobs(&args),
+            |fpath, text, diags| {
+                match fixer.fix(fpath, text, diags) {
+                    Ok(Some((fixed, replacements))) => {
+                        write_atomic(fpath, &fixed).or_exit("Error writing fixed file");
+                        if let Err(e) = journal.append(&replacements) {
+                            warn!("Failed to write to the journal: {}", e);
+                        }
+                        changed += 1;
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        error!("Failed to fix \"{}\": {}", fpath.display(), e);
+                        fixer.quit = true;
+                    }
+                }
+
+                // Save as we go, so an interrupted session can be resumed
+                if let Err(e) = fixer.state.save(session) {
+                    warn!("Failed to save the session: {}", e);
+                }
+            },
+        );
+        eprintln!("Fixed {} word(s) in {} file(s)", fixer.fixed, changed);
+        if fixer.quit {
+            eprintln!(
+                "Session saved to \"{}\", continue it with --resume",
+                session.display()
+            );
+        } else {
+            let _ = fs::remove_file(session);
+        }
+        print_skip_summary(&skipped);
+    } else if let Some(Command::GitLog { range }) = &args.command {
+        let wset = WordSet::new(&words, args.set_impl);
+        let ign_list = ignore_list(&args, &mut WordLists::default());
+        let lookups = Lookups::build(&wset, &ign_list, &opts, ngrams);
+
+        let mut printer = Printer::new(output_opts(&args));
+        for (sha, msg) in commit_messages(range).or_exit("Error reading the git log") {
+            let diags = check_file(
+                Path::new(&sha),
+                Cursor::new(&msg),
+                &wset,
+                &ign_list,
+                &opts,
+                &lookups,
+            );
+            printer.add(&msg, diags);
+        }
+        printer.finish();
+    } else if let Some(Command::Rustdoc { paths }) = &args.command {
+        let files: Vec<PathBuf> = collect_files(paths, &walk_opts(&args))
+            .into_iter()
+            .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+            .collect();
+        let groups = file_groups(
+            &args,
+            &matches,
+            &config,
+            &settings,
+            files,
+            Some(Filter::Rustdoc),
+        );
+        check_and_print(&args, &groups, words, ngrams);
+    } else if let Some(Command::Cargo) = &args.command {
+        let (docs, sources) = crate_files(Path::new("."), &walk_opts(&args));
+        if docs.is_empty() {
+            error!("No Cargo.toml found, this needs to be run from a crate's directory");
+            process::exit(2);
+        }
+        let mut groups = file_groups(&args, &matches, &config, &settings, docs, None);
+        groups.extend(file_groups(
+            &args,
+            &matches,
+            &config,
+            &settings,
+            sources,
+            Some(Filter::Rustdoc),
+        ));
+        check_and_print(&args, &groups, words, ngrams);
+    } else if let Some(Command::Bench { paths }) = &args.command {
+        let queries = bench_queries(paths, &walk_opts(&args), &words, args.max_file_size);
+        print_bench(&bench_sets(&words, &queries), queries.len());
+        print_suggest(&bench_suggest(&words, &queries, suggest_opts(&args)));
+        print_ratio(&bench_ratio(&words, &queries));
+    } else if let Some(Command::Revert { journal }) = &args.command {
+        let count = revert(journal).or_exit("Error reading the journal");
+        eprintln!("Reverted {} replacement(s)", count);
+    } else if args.file {
+        let paths: Vec<PathBuf> = args.word.iter().map(PathBuf::from).collect();
+        let files = collect_files(&paths, &walk_opts(&args));
+        let groups = file_groups(&args, &matches, &config, &settings, files, None);
+        check_and_print(&args, &groups, words, ngrams);
+    } else if args.phrase {
+        let ign_list = ignore_list(&args, &mut WordLists::default());
+        spell_check_phrase(
+            &args.word.join(" "),
+            words,
+            &ign_list,
+            suggest_opts(&args),
+            ngrams.as_ref(),
+            &opts,
+            args.set_impl,
+        );
+    }
+}
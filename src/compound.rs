@@ -0,0 +1,141 @@
+use crate::wordset::WordSet;
+use serde::Serialize;
+
+/// Settings for splitting compound words into known dictionary words.  The
+/// languages that build compounds differ in the linking elements they allow
+/// between the parts, e.g. the German "s" in "Arbeitszimmer".
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompoundConfig {
+    /// The shortest dictionary word that counts as a part of a compound
+    pub min_part_len: usize,
+    /// Linking elements that may sit between two parts
+    pub linkers: Vec<String>,
+}
+
+impl CompoundConfig {
+    /// Get the compound settings for a language, by its ISO 639-1 code
+    pub fn for_lang(lang: &str) -> Option<Self> {
+        let linkers: &[&str] = match lang.to_lowercase().as_str() {
+            "de" => &["s", "es", "n", "en", "er", "e"],
+            "nl" => &["s", "en", "e"],
+            "sv" | "da" | "no" => &["s", "e"],
+            "generic" => &[],
+            _ => return None,
+        };
+
+        return Some(Self {
+            min_part_len: 3,
+            linkers: linkers.iter().map(|l| l.to_string()).collect(),
+        });
+    }
+}
+
+/// Try to split the word into 2 or more dictionary words, returning the
+/// parts (linking elements are attached to the part before them) if it can
+/// be done
+pub fn split_compound(word: &str, words: &WordSet, cfg: &CompoundConfig) -> Option<Vec<String>> {
+    // The char boundaries in the word, so we never slice mid-character
+    let bounds: Vec<usize> = word
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([word.len()])
+        .collect();
+    let n = bounds.len() - 1;
+
+    // best[i] is the number of parts and the start of the last part for a
+    // valid segmentation of the first i chars
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+    best[0] = Some((0, 0));
+
+    for end in 1..=n {
+        for start in 0..end {
+            let nparts = match best[start] {
+                Some((nparts, _)) => nparts,
+                None => continue,
+            };
+
+            if end - start < cfg.min_part_len {
+                continue;
+            }
+
+            let part = &word[bounds[start]..bounds[end]];
+            if is_part(part, words, cfg, end == n) {
+                let cand = (nparts + 1, start);
+                // Prefer the fewest parts, which avoids silly splits
+                if best[end].is_none_or(|(b, _)| cand.0 < b) {
+                    best[end] = Some(cand);
+                }
+            }
+        }
+    }
+
+    let (nparts, _) = best[n]?;
+    if nparts < 2 {
+        return None;
+    }
+
+    let mut parts = vec![];
+    let mut end = n;
+    while end > 0 {
+        let (_, start) = best[end].unwrap();
+        parts.push(word[bounds[start]..bounds[end]].to_string());
+        end = start;
+    }
+    parts.reverse();
+
+    return Some(parts);
+}
+
+/// Check whether this is a valid compound part, optionally followed by a
+/// linking element if it's not the last part
+fn is_part(part: &str, words: &WordSet, cfg: &CompoundConfig, last: bool) -> bool {
+    if words.contains(part) {
+        return true;
+    }
+
+    if last {
+        return false;
+    }
+
+    for linker in &cfg.linkers {
+        if let Some(stem) = part.strip_suffix(linker.as_str()) {
+            if stem.chars().count() >= cfg.min_part_len && words.contains(stem) {
+                return true;
+            }
+        }
+    }
+
+    return false;
+}
+
+#[test]
+fn test_split_compound() {
+    let words = WordSet::new(
+        &["arbeit", "zimmer", "haus", "tür", "schlüssel"],
+        Default::default(),
+    );
+    let de = CompoundConfig::for_lang("de").unwrap();
+
+    assert_eq!(
+        split_compound("haustür", &words, &de),
+        Some(vec!["haus".to_string(), "tür".to_string()])
+    );
+    assert_eq!(
+        split_compound("arbeitszimmer", &words, &de),
+        Some(vec!["arbeits".to_string(), "zimmer".to_string()])
+    );
+    assert_eq!(
+        split_compound("haustürschlüssel", &words, &de).map(|p| p.len()),
+        Some(3)
+    );
+
+    // A single word isn't a compound, and neither is junk
+    assert_eq!(split_compound("haus", &words, &de), None);
+    assert_eq!(split_compound("hausxyz", &words, &de), None);
+
+    // No linkers in the generic config
+    let generic = CompoundConfig::for_lang("generic").unwrap();
+    assert_eq!(split_compound("arbeitszimmer", &words, &generic), None);
+
+    assert_eq!(CompoundConfig::for_lang("xx"), None);
+}
@@ -0,0 +1,551 @@
+use crate::compound::CompoundConfig;
+use crate::distance::Metric;
+use crate::filter::{Filter, MarkdownOpts};
+use crate::keyboard::Layout;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::ErrorKind,
+    path::{self, Component, Path, PathBuf},
+};
+
+/// The settings that can be given in the config file, at the top level or
+/// in a profile.  Each one is named after its command line option, and
+/// the option wins if it's given.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub filter: Option<Filter>,
+    pub skip_code_spans: Option<bool>,
+    pub comments_only: Option<bool>,
+    pub check_generated: Option<bool>,
+    pub check_minified: Option<bool>,
+    /// A path or a list of them, like --dict can be given more than once
+    #[serde(deserialize_with = "one_or_many")]
+    pub dict: Option<Vec<PathBuf>>,
+    pub with_builtin: Option<bool>,
+    pub personal_dict: Option<PathBuf>,
+    pub ignore: Option<Vec<String>>,
+    pub ignore_file: Option<PathBuf>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub no_ignore: Option<bool>,
+    /// Fingerprints of findings not to report, or "@FILE"s of them
+    pub suppress: Option<Vec<String>>,
+    pub ngrams: Option<PathBuf>,
+    pub history: Option<PathBuf>,
+    pub real_word: Option<bool>,
+    pub fold_diacritics: Option<bool>,
+    /// The language code for --compounds
+    pub compounds: Option<String>,
+    pub max_file_size: Option<u64>,
+    pub jobs: Option<usize>,
+    pub top: Option<usize>,
+    pub max_distance: Option<usize>,
+    pub metric: Option<Metric>,
+    pub phonetic: Option<bool>,
+    pub keyboard: Option<Layout>,
+    /// How many suggestions to show in --file mode
+    pub suggest: Option<usize>,
+    /// Which Markdown elements get checked
+    pub markdown: Option<MarkdownOpts>,
+    /// Whether findings make the run fail, which they do by default.  This
+    /// one doesn't have an option.
+    pub fail: Option<bool>,
+}
+
+/// Take either a single value or a list of them
+fn one_or_many<'de, D, T>(de: D) -> std::result::Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    return Ok(match OneOrMany::deserialize(de)? {
+        OneOrMany::One(v) => Some(vec![v]),
+        OneOrMany::Many(v) => Some(v),
+    });
+}
+
+impl Settings {
+    /// These settings with any that `over` has replacing them
+    pub fn merge(&self, over: &Settings) -> Settings {
+        let over = over.clone();
+        return Settings {
+            filter: over.filter.or(self.filter),
+            skip_code_spans: over.skip_code_spans.or(self.skip_code_spans),
+            comments_only: over.comments_only.or(self.comments_only),
+            check_generated: over.check_generated.or(self.check_generated),
+            check_minified: over.check_minified.or(self.check_minified),
+            dict: over.dict.or_else(|| self.dict.clone()),
+            with_builtin: over.with_builtin.or(self.with_builtin),
+            personal_dict: over.personal_dict.or_else(|| self.personal_dict.clone()),
+            ignore: over.ignore.or_else(|| self.ignore.clone()),
+            ignore_file: over.ignore_file.or_else(|| self.ignore_file.clone()),
+            include: over.include.or_else(|| self.include.clone()),
+            exclude: over.exclude.or_else(|| self.exclude.clone()),
+            no_ignore: over.no_ignore.or(self.no_ignore),
+            suppress: over.suppress.or_else(|| self.suppress.clone()),
+            ngrams: over.ngrams.or_else(|| self.ngrams.clone()),
+            history: over.history.or_else(|| self.history.clone()),
+            real_word: over.real_word.or(self.real_word),
+            fold_diacritics: over.fold_diacritics.or(self.fold_diacritics),
+            compounds: over.compounds.or_else(|| self.compounds.clone()),
+            max_file_size: over.max_file_size.or(self.max_file_size),
+            jobs: over.jobs.or(self.jobs),
+            top: over.top.or(self.top),
+            max_distance: over.max_distance.or(self.max_distance),
+            metric: over.metric.or(self.metric),
+            phonetic: over.phonetic.or(self.phonetic),
+            keyboard: over.keyboard.or(self.keyboard),
+            suggest: over.suggest.or(self.suggest),
+            markdown: over.markdown.or(self.markdown),
+            fail: over.fail.or(self.fail),
+        };
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(lang) = &self.compounds {
+            if CompoundConfig::for_lang(lang).is_none() {
+                bail!("no compound splitting rules for \"{}\"", lang);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// An `[[override]]` section: settings for the files matching any of its
+/// paths, on top of the others
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Override {
+    /// Glob patterns, like "legacy/**"
+    pub paths: Vec<String>,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+
+impl Override {
+    fn parse(mut table: toml::Table) -> Result<Self> {
+        let paths: Vec<String> = match table.remove("paths") {
+            Some(p) => p.try_into()?,
+            None => bail!("it needs a list of paths"),
+        };
+        let settings: Settings = table.try_into()?;
+        settings.validate()?;
+
+        // These are for the whole run, so they can't differ by file
+        let whole_run = [
+            ("dict", settings.dict.is_some()),
+            ("include", settings.include.is_some()),
+            ("exclude", settings.exclude.is_some()),
+            ("no_ignore", settings.no_ignore.is_some()),
+            ("with_builtin", settings.with_builtin.is_some()),
+            ("personal_dict", settings.personal_dict.is_some()),
+            ("ngrams", settings.ngrams.is_some()),
+            ("history", settings.history.is_some()),
+            ("jobs", settings.jobs.is_some()),
+            ("top", settings.top.is_some()),
+            ("max_distance", settings.max_distance.is_some()),
+            ("metric", settings.metric.is_some()),
+            ("phonetic", settings.phonetic.is_some()),
+            ("keyboard", settings.keyboard.is_some()),
+            ("suggest", settings.suggest.is_some()),
+        ];
+        if let Some((name, _)) = whole_run.iter().find(|(_, set)| *set) {
+            bail!("{} can't be set for some paths only", name);
+        }
+
+        return Ok(Self { paths, settings });
+    }
+
+    /// Check whether the file is matched by any of the paths, which are
+    /// relative to `dir`, the config file's directory
+    pub fn matches(&self, file: &Path, dir: &Path) -> bool {
+        let file = relative_path(file, dir);
+        return self.paths.iter().any(|pat| glob_matches(pat, &file));
+    }
+}
+
+/// The path relative to `dir`, or else to the current directory, however
+/// it was given, for matching globs written relative to them.  A path
+/// that's under neither is left as it is.
+pub fn relative_path(file: &Path, dir: &Path) -> PathBuf {
+    let Ok(abs) = path::absolute(file) else {
+        return file.to_owned();
+    };
+    let dirs = [path::absolute(dir).ok(), env::current_dir().ok()];
+    for dir in dirs.iter().flatten() {
+        if let Ok(rel) = abs.strip_prefix(dir) {
+            return rel.to_owned();
+        }
+    }
+
+    return file.to_owned();
+}
+
+/// Check whether the path matches a glob pattern, like "legacy/**/*.md",
+/// going by its components
+pub fn glob_matches(pat: &str, file: &Path) -> bool {
+    let parts: Vec<String> = file
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(p) => Some(p.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    let parts: Vec<&str> = parts.iter().map(|p| p.as_str()).collect();
+    let pat: Vec<&str> = pat
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+
+    return glob_parts(&pat, &parts);
+}
+
+/// Match path components against a glob pattern's, where `**` matches any
+/// number of components
+fn glob_parts(pat: &[&str], parts: &[&str]) -> bool {
+    return match pat.split_first() {
+        None => parts.is_empty(),
+        Some((&"**", rest)) => (0..=parts.len()).any(|i| glob_parts(rest, &parts[i..])),
+        Some((p, rest)) => parts.split_first().is_some_and(|(part, others)| {
+            let p: Vec<char> = p.chars().collect();
+            let part: Vec<char> = part.chars().collect();
+            glob_name(&p, &part) && glob_parts(rest, others)
+        }),
+    };
+}
+
+/// Match a file name against a glob, where `*` matches any run of chars and
+/// `?` any one
+fn glob_name(pat: &[char], name: &[char]) -> bool {
+    return match pat.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| glob_name(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && glob_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_name(rest, &name[1..]),
+    };
+}
+
+/// Settings from a spel.toml config file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    /// The settings at the top level of the file
+    pub settings: Settings,
+    /// The `[profile.NAME]` sections, picked with --profile
+    pub profiles: BTreeMap<String, Settings>,
+    /// The `[[override]]` sections, in the order they're given
+    pub overrides: Vec<Override>,
+    /// The directory the file is in, which the override paths are
+    /// relative to
+    pub dir: PathBuf,
+}
+
+impl Config {
+    /// Load the config file, if there is one
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut config =
+            Self::parse(&text).with_context(|| format!("in \"{}\"", path.display()))?;
+        config.dir = path.parent().unwrap_or(Path::new("")).to_owned();
+
+        return Ok(config);
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        let profiles = match table.remove("profile") {
+            Some(p) => p.try_into().context("in [profile]")?,
+            None => BTreeMap::new(),
+        };
+        let overrides: Vec<toml::Table> = match table.remove("override") {
+            Some(o) => o.try_into().context("in [[override]]")?,
+            None => vec![],
+        };
+        let config = Self {
+            settings: table.try_into()?,
+            profiles,
+            overrides: (1..)
+                .zip(overrides)
+                .map(|(i, o)| Override::parse(o).with_context(|| format!("in [[override]] {}", i)))
+                .collect::<Result<_>>()?,
+            dir: PathBuf::new(),
+        };
+
+        config.settings.validate()?;
+        for (name, profile) in &config.profiles {
+            profile
+                .validate()
+                .with_context(|| format!("in [profile.{}]", name))?;
+        }
+
+        return Ok(config);
+    }
+
+    /// The settings for a run with the profile, which are the top level
+    /// ones with the profile's on top
+    pub fn settings(&self, profile: Option<&str>) -> Result<Settings> {
+        let Some(name) = profile else {
+            return Ok(self.settings.clone());
+        };
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| anyhow!("there's no [profile.{}]", name))?;
+
+        return Ok(self.settings.merge(profile));
+    }
+}
+
+/// The settings a run ends up with, from the config file and the options
+/// together, for --show-config
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveConfig {
+    /// The config file, if there was one
+    pub config_file: Option<PathBuf>,
+    pub profile: Option<String>,
+    /// The .spel directory the project's word lists are in
+    pub project_dir: Option<PathBuf>,
+    pub ignore: Vec<String>,
+    pub ignore_file: PathBuf,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_ignore: bool,
+    pub suppress: Vec<String>,
+    pub dict: Vec<PathBuf>,
+    pub with_builtin: bool,
+    pub personal_dict: PathBuf,
+    pub ngrams: Option<PathBuf>,
+    pub history: Option<PathBuf>,
+    pub filter: Option<String>,
+    pub skip_code_spans: bool,
+    pub comments_only: bool,
+    pub check_generated: bool,
+    pub check_minified: bool,
+    pub fold_diacritics: bool,
+    pub compounds: Option<CompoundConfig>,
+    pub real_word: bool,
+    pub notes: Vec<String>,
+    pub max_file_size: u64,
+    pub jobs: usize,
+    pub top: usize,
+    pub max_distance: Option<usize>,
+    pub metric: Metric,
+    pub phonetic: bool,
+    pub keyboard: Option<Layout>,
+    pub suggest: Option<usize>,
+    pub fail: bool,
+    pub markdown: MarkdownOpts,
+    /// These are as given in the config file, the options on the command
+    /// line still win over them
+    #[serde(rename = "override")]
+    pub overrides: Vec<Override>,
+}
+
+impl EffectiveConfig {
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string_pretty(self).unwrap();
+    }
+
+    /// The settings in the same syntax as the config file
+    pub fn to_toml(&self) -> String {
+        return toml::to_string(self).unwrap();
+    }
+}
+
+#[test]
+fn test_config_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = &dir.path().join("spel.toml");
+    assert_eq!(Config::load(path).unwrap(), Config::default());
+
+    fs::write(path, "[markdown]\nalt_text = false\nfootnotes = false\n").unwrap();
+    let markdown = Config::load(path).unwrap().settings.markdown.unwrap();
+    assert!(!markdown.alt_text && !markdown.footnotes);
+    assert!(markdown.headings && markdown.tables);
+
+    fs::write(path, "[markdown]\nalt_txt = false\n").unwrap();
+    assert!(Config::load(path).is_err());
+}
+
+#[test]
+fn test_config_profiles() {
+    let config = Config::parse(
+        r#"
+max_file_size = 1000
+filter = "plain"
+
+[profile.docs]
+filter = "markdown"
+ignore = ["spel"]
+
+[profile.docs.markdown]
+tables = false
+
+[profile.code]
+comments_only = true
+compounds = "de"
+"#,
+    )
+    .unwrap();
+
+    let top = config.settings(None).unwrap();
+    assert_eq!(top.filter, Some(Filter::Plain));
+    assert_eq!(top.markdown, None);
+
+    let docs = config.settings(Some("docs")).unwrap();
+    assert_eq!(docs.filter, Some(Filter::Markdown));
+    assert_eq!(docs.max_file_size, Some(1000));
+    assert_eq!(docs.ignore, Some(vec!["spel".to_string()]));
+    assert!(!docs.markdown.unwrap().tables);
+
+    let code = config.settings(Some("code")).unwrap();
+    assert_eq!(code.filter, Some(Filter::Plain));
+    assert_eq!(code.comments_only, Some(true));
+
+    assert!(config.settings(Some("nope")).is_err());
+
+    // The dict can be one file or a list of them
+    let one = Config::parse("dict = \"a.txt\"\n").unwrap();
+    assert_eq!(one.settings.dict, Some(vec![PathBuf::from("a.txt")]));
+    let many = Config::parse("dict = [\"a.txt\", \"b.txt\"]\n").unwrap();
+    assert_eq!(many.settings.dict.unwrap().len(), 2);
+    assert!(Config::parse("dict = 1\n").is_err());
+    assert!(Config::parse("[profile.x]\nfilter = \"nope\"\n").is_err());
+    assert!(Config::parse("[profile.x]\ncompounds = \"xx\"\n").is_err());
+    assert!(Config::parse("[profile.x]\ntop_n = 3\n").is_err());
+    let damerau = Config::parse("metric = \"damerau\"\n").unwrap();
+    assert_eq!(damerau.settings.metric, Some(Metric::Damerau));
+    assert!(Config::parse("metric = \"jaro\"\n").is_err());
+    assert!(Config::parse("[profile]\nx = 1\n").is_err());
+}
+
+#[test]
+fn test_effective_config() {
+    let eff = EffectiveConfig {
+        config_file: None,
+        profile: Some("docs".to_string()),
+        project_dir: None,
+        ignore: vec!["spel".to_string()],
+        ignore_file: PathBuf::from("~/.spel_ignore"),
+        include: vec![],
+        exclude: vec!["vendor/**".to_string()],
+        no_ignore: false,
+        suppress: vec![],
+        dict: vec![],
+        with_builtin: false,
+        personal_dict: PathBuf::from("~/.local/share/spel/personal.txt"),
+        ngrams: None,
+        history: Some(PathBuf::from(".spel_history.jsonl")),
+        filter: Some("markdown".to_string()),
+        skip_code_spans: false,
+        comments_only: true,
+        check_generated: false,
+        check_minified: false,
+        fold_diacritics: false,
+        compounds: CompoundConfig::for_lang("sv"),
+        real_word: false,
+        notes: vec![],
+        max_file_size: 1024,
+        jobs: 4,
+        top: 5,
+        max_distance: None,
+        metric: Metric::Ratio,
+        phonetic: false,
+        keyboard: None,
+        suggest: None,
+        fail: true,
+        markdown: MarkdownOpts {
+            tables: false,
+            ..Default::default()
+        },
+        overrides: vec![Override {
+            paths: vec!["legacy/**".to_string()],
+            settings: Settings {
+                fail: Some(false),
+                ..Default::default()
+            },
+        }],
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&eff.to_json()).unwrap();
+    assert_eq!(json["config_file"], serde_json::Value::Null);
+    assert_eq!(json["profile"], "docs");
+    assert_eq!(json["ignore"][0], "spel");
+    assert_eq!(json["filter"], "markdown");
+    assert_eq!(json["comments_only"], true);
+    assert_eq!(json["compounds"]["linkers"][1], "e");
+    assert_eq!(json["markdown"]["tables"], false);
+    assert_eq!(json["override"][0]["paths"][0], "legacy/**");
+    assert_eq!(json["override"][0]["fail"], false);
+
+    // The markdown section reads back in as a config file
+    let config = Config::parse(
+        &eff.to_toml()
+            .lines()
+            .skip_while(|l| *l != "[markdown]")
+            .take_while(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+    .unwrap();
+    assert_eq!(config.settings.markdown, Some(eff.markdown));
+}
+
+#[test]
+fn test_config_overrides() {
+    let config = Config::parse(
+        r#"
+[[override]]
+paths = ["legacy/**", "*.txt"]
+ignore_file = "legacy/.spel_ignore"
+fail = false
+
+[[override]]
+paths = ["docs/*/draft-?.md"]
+filter = "plain"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.overrides.len(), 2);
+
+    let legacy = &config.overrides[0];
+    assert_eq!(legacy.settings.fail, Some(false));
+    assert!(legacy.matches(Path::new("legacy/a.md"), &config.dir));
+    assert!(legacy.matches(Path::new("./legacy/sub/dir/a.md"), &config.dir));
+    assert!(legacy.matches(Path::new("notes.txt"), &config.dir));
+    assert!(!legacy.matches(Path::new("src/legacy.md"), &config.dir));
+    assert!(!legacy.matches(Path::new("sub/notes.txt"), &config.dir));
+
+    let drafts = &config.overrides[1];
+    assert!(drafts.matches(Path::new("docs/en/draft-1.md"), &config.dir));
+    assert!(!drafts.matches(Path::new("docs/en/draft-10.md"), &config.dir));
+    assert!(!drafts.matches(Path::new("docs/draft-1.md"), &config.dir));
+
+    // Absolute paths, and relative ones from elsewhere, are matched
+    // relative to the config file's directory
+    let dir = tempfile::tempdir().unwrap();
+    assert!(legacy.matches(&dir.path().join("legacy/a.md"), dir.path()));
+    assert!(!legacy.matches(&dir.path().join("src/legacy.md"), dir.path()));
+    let cwd = std::env::current_dir().unwrap();
+    assert!(legacy.matches(&cwd.join("legacy/a.md"), &config.dir));
+
+    assert!(Config::parse("[[override]]\nfail = false\n").is_err());
+    assert!(Config::parse("[[override]]\npaths = [\"a\"]\ndict = \"x\"\n").is_err());
+    assert!(Config::parse("[[override]]\npaths = [\"a\"]\nwith_builtin = true\n").is_err());
+    assert!(Config::parse("[[override]]\npaths = [\"a\"]\nfial = false\n").is_err());
+}
@@ -0,0 +1,193 @@
+use crate::ngram::BigramModel;
+
+/// A set of correctly spelled words that are commonly used in place of
+/// each other, with a short hint on when to use which
+#[derive(Debug)]
+pub struct ConfusionSet {
+    pub words: &'static [&'static str],
+    pub hint: &'static str,
+    /// Words so common that a hint on every one would bury the rest, which
+    /// are only checked by their context, with --real-word
+    pub common: bool,
+}
+
+const fn set(words: &'static [&'static str], hint: &'static str) -> ConfusionSet {
+    return ConfusionSet {
+        words,
+        hint,
+        common: false,
+    };
+}
+
+const fn common_set(words: &'static [&'static str], hint: &'static str) -> ConfusionSet {
+    return ConfusionSet {
+        words,
+        hint,
+        common: true,
+    };
+}
+
+pub const CONFUSION_SETS: &[ConfusionSet] = &[
+    common_set(
+        &["their", "there", "they're"],
+        "their = belonging to them, there = that place, they're = they are",
+    ),
+    common_set(
+        &["your", "you're"],
+        "your = belonging to you, you're = you are",
+    ),
+    common_set(&["its", "it's"], "its = belonging to it, it's = it is"),
+    set(
+        &["whose", "who's"],
+        "whose = belonging to whom, who's = who is",
+    ),
+    common_set(
+        &["to", "too", "two"],
+        "to = direction, too = also/excessively, two = 2",
+    ),
+    common_set(&["then", "than"], "then = time/sequence, than = comparison"),
+    set(
+        &["affect", "effect"],
+        "affect is usually the verb, effect is usually the noun",
+    ),
+    set(
+        &["accept", "except"],
+        "accept = receive, except = excluding",
+    ),
+    set(
+        &["advice", "advise"],
+        "advice is the noun, advise is the verb",
+    ),
+    set(
+        &["lose", "loose"],
+        "lose = misplace/fail to win, loose = not tight",
+    ),
+    set(
+        &["weather", "whether"],
+        "weather = climate, whether = if/introduces alternatives",
+    ),
+    set(
+        &["principal", "principle"],
+        "principal = main/head person, principle = rule/belief",
+    ),
+    set(
+        &["complement", "compliment"],
+        "complement = completes, compliment = praise",
+    ),
+    set(&["peace", "piece"], "peace = calm/no war, piece = a part"),
+    set(
+        &["brake", "break"],
+        "brake = stop a vehicle, break = smash/pause",
+    ),
+    set(
+        &["quite", "quiet"],
+        "quite = fairly/completely, quiet = not loud",
+    ),
+    common_set(
+        &["where", "were"],
+        "where = place, were = past tense of are",
+    ),
+    set(
+        &["passed", "past"],
+        "passed = verb (went by), past = time before now",
+    ),
+    set(
+        &["led", "lead"],
+        "led = past tense of lead, lead = to guide (or the metal)",
+    ),
+    set(
+        &["stationary", "stationery"],
+        "stationary = not moving, stationery = writing materials",
+    ),
+];
+
+/// How strongly the context has to favor the alternative before the word
+/// is flagged
+const FAVOR_FACTOR: u64 = 10;
+
+/// The least number of times the alternative must have been seen in the
+/// context, so we don't flag based on a single stray bigram
+const MIN_EVIDENCE: u64 = 5;
+
+/// Find the confusion set the word belongs to, if any
+pub fn confusion_set(word: &str) -> Option<&'static ConfusionSet> {
+    return CONFUSION_SETS.iter().find(|s| s.words.contains(&word));
+}
+
+/// Get the usage hint for a commonly confused word, if it is one and it
+/// isn't too common to hint at every time
+pub fn confusable_hint(word: &str) -> Option<&'static str> {
+    return confusion_set(word).filter(|s| !s.common).map(|s| s.hint);
+}
+
+fn context_count(model: &BigramModel, prev: Option<&str>, word: &str, next: Option<&str>) -> u64 {
+    let mut ret = 0;
+    if let Some(p) = prev {
+        ret += model.count(p, word);
+    }
+
+    if let Some(n) = next {
+        ret += model.count(word, n);
+    }
+
+    return ret;
+}
+
+/// Check a correctly spelled word against its confusion set, returning the
+/// alternative if the surrounding words strongly favor it
+pub fn real_word_error(
+    model: &BigramModel,
+    prev: Option<&str>,
+    word: &str,
+    next: Option<&str>,
+) -> Option<&'static str> {
+    let set = confusion_set(word)?;
+    let have = context_count(model, prev, word, next);
+
+    return set
+        .words
+        .iter()
+        .filter(|alt| **alt != word)
+        .map(|alt| (*alt, context_count(model, prev, alt, next)))
+        .filter(|(_, count)| *count >= MIN_EVIDENCE && *count >= FAVOR_FACTOR * (have + 1))
+        .max_by_key(|(_, count)| *count)
+        .map(|(alt, _)| alt);
+}
+
+#[test]
+fn test_real_word_error() {
+    let mut model = BigramModel::default();
+    model.add("over", "there", 200);
+    model.add("there", "is", 300);
+    model.add("their", "car", 100);
+    model.add("over", "their", 2);
+
+    assert_eq!(
+        real_word_error(&model, Some("over"), "their", None),
+        Some("there")
+    );
+    assert_eq!(real_word_error(&model, Some("over"), "there", None), None);
+
+    // Context that fits the word it's used as
+    assert_eq!(real_word_error(&model, None, "their", Some("car")), None);
+
+    // Not part of any confusion set
+    assert_eq!(real_word_error(&model, Some("over"), "monkey", None), None);
+}
+
+#[test]
+fn test_confusable_hint() {
+    assert!(confusable_hint("affect").unwrap().contains("verb"));
+    assert_eq!(confusable_hint("effect"), confusable_hint("affect"));
+    assert_eq!(confusable_hint("monkey"), None);
+    // Only --real-word checks the everyday ones
+    assert_eq!(confusable_hint("to"), None);
+    assert_eq!(confusable_hint("their"), None);
+
+    // Every word should only be in one set
+    for set in CONFUSION_SETS {
+        for word in set.words {
+            assert!(std::ptr::eq(confusion_set(word).unwrap(), set));
+        }
+    }
+}
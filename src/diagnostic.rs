@@ -0,0 +1,364 @@
+use clap::ValueEnum;
+use std::{
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// What kind of problem a diagnostic is reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The word isn't in the dictionary
+    Misspelling,
+    /// The word only differs from a dictionary word by its diacritics
+    Variant,
+    /// A correctly spelled word that is likely confused with another
+    Confusion,
+    /// Informational, e.g. a usage hint
+    Note,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Kind::Misspelling => "misspelling",
+            Kind::Variant => "variant",
+            Kind::Confusion => "confusion",
+            Kind::Note => "note",
+        };
+
+        return write!(f, "{}", s);
+    }
+}
+
+/// The units that positions within a line are counted in.  LSP clients
+/// default to utf-16 code units, while most other tools use bytes (utf-8)
+/// or chars (utf-32).
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    #[value(name = "utf-8")]
+    Utf8,
+    #[default]
+    #[value(name = "utf-16")]
+    Utf16,
+    #[value(name = "utf-32")]
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Pick the encoding to use from the ones a client says it supports,
+    /// as in the LSP `general.positionEncodings` capability.  utf-8 is the
+    /// cheapest for us, and utf-16 is the fallback every client must
+    /// support.
+    pub fn negotiate(client: &[PositionEncoding]) -> Self {
+        for enc in [PositionEncoding::Utf8, PositionEncoding::Utf32] {
+            if client.contains(&enc) {
+                return enc;
+            }
+        }
+
+        return PositionEncoding::Utf16;
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Utf16 => "utf-16",
+            PositionEncoding::Utf32 => "utf-32",
+        };
+    }
+}
+
+/// Where a word is in a file, in both bytes and unicode scalar values
+/// (chars), since multibyte characters make them differ.  All ranges are
+/// 0 based and end-exclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offsets from the start of the file
+    pub bytes: Range<usize>,
+    /// Char offsets from the start of the file
+    pub chars: Range<usize>,
+    /// Byte offsets within the line
+    pub line_bytes: Range<usize>,
+    /// Char offsets within the line, i.e. the columns
+    pub line_chars: Range<usize>,
+    /// Utf-16 code unit offsets within the line
+    pub line_utf16: Range<usize>,
+}
+
+impl Span {
+    /// Build the span for a word from its position in the line and where
+    /// the line starts in the file
+    pub fn new(
+        line_bytes: Range<usize>,
+        line_chars: Range<usize>,
+        line_utf16: Range<usize>,
+        line_start_byte: usize,
+        line_start_char: usize,
+    ) -> Self {
+        return Self {
+            bytes: line_start_byte + line_bytes.start..line_start_byte + line_bytes.end,
+            chars: line_start_char + line_chars.start..line_start_char + line_chars.end,
+            line_bytes,
+            line_chars,
+            line_utf16,
+        };
+    }
+
+    /// The offsets within the line, in the units of the given encoding
+    pub fn columns(&self, enc: PositionEncoding) -> Range<usize> {
+        return match enc {
+            PositionEncoding::Utf8 => self.line_bytes.clone(),
+            PositionEncoding::Utf16 => self.line_utf16.clone(),
+            PositionEncoding::Utf32 => self.line_chars.clone(),
+        };
+    }
+}
+
+/// A single finding from checking a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    /// The line number, starting at 1
+    pub line: u64,
+    /// Where the word is in the file
+    pub span: Span,
+    /// The word as it was checked (lowercased)
+    pub word: String,
+    pub kind: Kind,
+    /// Suggested replacements, best first
+    pub suggestions: Vec<String>,
+    /// Extra detail, like a usage hint for a Note
+    pub note: Option<String>,
+    /// What the finding is part of within the file, like a BibTeX entry
+    pub scope: Option<String>,
+    /// The number of occurrences this diagnostic stands for, which is more
+    /// than 1 when repeats are collapsed with --unique-per-file
+    pub count: usize,
+    /// The finding's id, for --suppress, if it's been worked out
+    pub fingerprint: String,
+}
+
+/// An id for a finding that stays the same while the line it's on does,
+/// however the lines around it move.  It's an FNV-1a hash of the path,
+/// the word, the line with its whitespace collapsed and which of the
+/// word's findings on the line it is.
+pub fn fingerprint(path: &Path, word: &str, line: &str, nth: usize) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    let line = line.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    let mut h: u64 = 0xcbf29ce484222325;
+    for part in [path, word, &line, &nth.to_string()] {
+        // Each part ends with a NUL, so they can't run together
+        for b in part.bytes().chain([0]) {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    return format!("{:016x}", h);
+}
+
+impl Diagnostic {
+    pub fn new(path: PathBuf, line: u64, span: Span, word: &str, kind: Kind) -> Self {
+        return Self {
+            path,
+            line,
+            span,
+            word: word.to_string(),
+            kind,
+            suggestions: vec![],
+            note: None,
+            scope: None,
+            count: 1,
+            fingerprint: String::new(),
+        };
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        return self;
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        return self;
+    }
+
+    pub fn with_scope(mut self, scope: Option<&str>) -> Self {
+        self.scope = scope.map(|s| s.to_string());
+        return self;
+    }
+
+    /// Work out the fingerprint from the text of the line the finding is
+    /// on, and which of the word's findings on the line it is
+    pub fn with_fingerprint(mut self, line: &str, nth: usize) -> Self {
+        self.fingerprint = fingerprint(&self.path, &self.word, line, nth);
+        return self;
+    }
+
+    /// The short, parenthetical detail shown after the word, if any
+    pub fn detail(&self) -> Option<String> {
+        let first = self.suggestions.first();
+
+        return match (self.kind, first) {
+            (Kind::Variant, Some(s)) => Some(format!("variant of \"{}\"", s)),
+            (Kind::Confusion, Some(s)) => Some(format!("note: possible confusion with \"{}\"", s)),
+            (Kind::Note, _) => self.note.as_ref().map(|n| format!("note: {}", n)),
+            (Kind::Misspelling, Some(_)) => Some(format!("did you mean {}?", self.alternatives())),
+            _ => None,
+        };
+    }
+
+    /// The suggestions in quotes, as a list like "a", "b" or "c"
+    fn alternatives(&self) -> String {
+        let quoted: Vec<String> = self
+            .suggestions
+            .iter()
+            .map(|s| format!("\"{}\"", s))
+            .collect();
+        return match quoted.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+            _ => quoted.join(""),
+        };
+    }
+}
+
+impl Diagnostic {
+    /// Describe the finding, without the location: the scope, if any,
+    /// and the word, followed by any detail and the number of times it
+    /// occurred
+    pub fn describe(&self) -> String {
+        let mut ret = match &self.scope {
+            Some(scope) => format!("[{}] \"{}\"", scope, self.word),
+            None => format!("\"{}\"", self.word),
+        };
+        if let Some(detail) = self.detail() {
+            ret.push_str(&format!(" ({})", detail));
+        }
+
+        if self.count > 1 {
+            ret.push_str(&format!(" ({} times)", self.count));
+        }
+
+        return ret;
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "{}:{}:{} {}",
+            self.path.display(),
+            self.line,
+            self.span.columns(PositionEncoding::default()).start + 1,
+            self.describe()
+        );
+    }
+}
+
+#[test]
+fn test_span() {
+    let line = "naïve café";
+    let span = Span::new(7..12, 6..10, 6..10, 100, 90);
+    assert_eq!(&line[span.line_bytes.clone()], "café");
+    assert_eq!(span.bytes, 107..112);
+    assert_eq!(span.chars, 96..100);
+    assert_eq!(span.columns(PositionEncoding::Utf8), 7..12);
+    assert_eq!(span.columns(PositionEncoding::Utf32), 6..10);
+}
+
+#[test]
+fn test_negotiate_encoding() {
+    use PositionEncoding::*;
+
+    assert_eq!(PositionEncoding::negotiate(&[Utf16, Utf8]), Utf8);
+    assert_eq!(PositionEncoding::negotiate(&[Utf32, Utf16]), Utf32);
+    assert_eq!(PositionEncoding::negotiate(&[Utf16]), Utf16);
+    assert_eq!(PositionEncoding::negotiate(&[]), Utf16);
+}
+
+#[test]
+fn test_diagnostic_display() {
+    let span = Span::default();
+    let d = Diagnostic::new(
+        PathBuf::from("a.md"),
+        3,
+        span.clone(),
+        "recieve",
+        Kind::Misspelling,
+    );
+    assert_eq!(d.to_string(), "a.md:3:1 \"recieve\"");
+
+    let d = d.with_suggestions(vec!["receive".to_string()]);
+    assert_eq!(
+        d.to_string(),
+        "a.md:3:1 \"recieve\" (did you mean \"receive\"?)"
+    );
+    let d = d.with_suggestions(["receive", "relieve", "recite"].map(String::from).to_vec());
+    assert_eq!(
+        d.to_string(),
+        "a.md:3:1 \"recieve\" (did you mean \"receive\", \"relieve\" or \"recite\"?)"
+    );
+
+    let d = Diagnostic::new(
+        PathBuf::from("a.md"),
+        1,
+        span.clone(),
+        "resume",
+        Kind::Variant,
+    )
+    .with_suggestions(vec!["résumé".to_string()]);
+    assert_eq!(d.to_string(), "a.md:1:1 \"resume\" (variant of \"résumé\")");
+
+    let d = Diagnostic::new(
+        PathBuf::from("a.bib"),
+        4,
+        span.clone(),
+        "studdy",
+        Kind::Misspelling,
+    )
+    .with_scope(Some("smyth2020"));
+    assert_eq!(d.to_string(), "a.bib:4:1 [smyth2020] \"studdy\"");
+
+    let mut d = Diagnostic::new(PathBuf::from("a.md"), 2, span, "affect", Kind::Note)
+        .with_note("affect is usually the verb");
+    d.count = 2;
+    assert_eq!(
+        d.to_string(),
+        "a.md:2:1 \"affect\" (note: affect is usually the verb) (2 times)"
+    );
+}
+
+#[test]
+fn test_fingerprint() {
+    let id = fingerprint(Path::new("docs/a.md"), "teh", "  See teh\tdocs ", 0);
+    assert_eq!(id.len(), 16);
+    // The same however the path is written or the line is spaced
+    assert_eq!(
+        fingerprint(Path::new("./docs/a.md"), "teh", "See teh docs", 0),
+        id
+    );
+    assert_eq!(
+        fingerprint(Path::new("docs\\a.md"), "teh", "See  teh docs", 0),
+        id
+    );
+
+    let others = [
+        fingerprint(Path::new("docs/b.md"), "teh", "See teh docs", 0),
+        fingerprint(Path::new("docs/a.md"), "tehm", "See teh docs", 0),
+        fingerprint(Path::new("docs/a.md"), "teh", "See teh doc", 0),
+        fingerprint(Path::new("docs/a.md"), "teh", "See teh docs", 1),
+    ];
+    assert!(others.iter().all(|o| *o != id));
+
+    let d = Diagnostic::new("a.md".into(), 9, Span::default(), "teh", Kind::Misspelling);
+    assert_eq!(d.fingerprint, "");
+    let d = d.with_fingerprint("teh end", 0);
+    assert_eq!(
+        d.fingerprint,
+        fingerprint(Path::new("a.md"), "teh", "teh end", 0)
+    );
+}
@@ -0,0 +1,584 @@
+use crate::bktree::edit_distance;
+use crate::keyboard::{KeyMap, Layout};
+use crate::util::SuggestOpts;
+use crate::util::{ratio_for, within_distance};
+use clap::ValueEnum;
+use difflib::sequencematcher::SequenceMatcher;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// What an edit costs when working out a distance in half edits, so a swap
+/// can be made to cost less than the others
+const EDIT: usize = 2;
+
+/// The Damerau-Levenshtein distance between 2 words: the number of chars
+/// added, removed or changed, or pairs of chars next to each other swapped,
+/// to get from one to the other.  Like most spell checkers, this is the
+/// optimal string alignment version, where a char isn't edited again after
+/// it's been swapped.
+pub fn damerau_distance(a: &str, b: &str) -> usize {
+    return half_edits(a, b, EDIT, None, &mut vec![]) / EDIT;
+}
+
+/// How similar 2 words are by their Damerau-Levenshtein distance, from 0
+/// to 1, the same way round as the difflib ratio.  A swap only counts as
+/// half an edit here, since it's the most common slip when typing, so "the"
+/// ranks above "ten" for "teh".
+#[cfg(test)]
+pub fn damerau_ratio(a: &str, b: &str) -> f32 {
+    return Metric::Damerau.scorer(a).score(b);
+}
+
+fn half_edits(
+    a: &str,
+    b: &str,
+    swap: usize,
+    keys: Option<&KeyMap>,
+    rows: &mut Vec<usize>,
+) -> usize {
+    // Most words are ascii, where a byte is a char
+    if a.is_ascii() && b.is_ascii() {
+        return osa(a.as_bytes(), b.as_bytes(), swap, keys, rows);
+    }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    return osa(&a, &b, swap, keys, rows);
+}
+
+/// The optimal string alignment distance, in half edits, with a swap
+/// costing `swap`, and changing a char to one on a key next to it only
+/// costing half if there are `keys`.  A swap needs the row before the
+/// previous one as well, so `rows` is made to fit 3 of them.
+fn osa<T: PartialEq + Copy + Into<u32>>(
+    a: &[T],
+    b: &[T],
+    swap: usize,
+    keys: Option<&KeyMap>,
+    rows: &mut Vec<usize>,
+) -> usize {
+    rows.resize(3 * (b.len() + 1), 0);
+    let (before, rest) = rows.split_at_mut(b.len() + 1);
+    let (prev, cur) = rest.split_at_mut(b.len() + 1);
+    let (mut before, mut prev, mut cur) = (before, prev, cur);
+    for (j, p) in prev.iter_mut().enumerate() {
+        *p = j * EDIT;
+    }
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = (i + 1) * EDIT;
+        for (j, cb) in b.iter().enumerate() {
+            let change = match (ca == cb, keys) {
+                (true, _) => prev[j],
+                (false, Some(k)) if k.adjacent((*ca).into(), (*cb).into()) => prev[j] + EDIT / 2,
+                (false, _) => prev[j] + EDIT,
+            };
+            let mut best = change.min(prev[j + 1] + EDIT).min(cur[j] + EDIT);
+            if i > 0 && j > 0 && *ca == b[j - 1] && a[i - 1] == *cb && ca != cb {
+                best = best.min(before[j - 1] + swap);
+            }
+            cur[j + 1] = best;
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    return prev[b.len()];
+}
+
+/// The difflib similarity ratio of a candidate for the word, worked out
+/// the way find_word() does
+pub fn ratio(word: &str, cand: &str) -> f32 {
+    return SequenceMatcher::new(word, cand).ratio();
+}
+
+/// The Jaro-Winkler similarity of 2 words, from 0 to 1: the share of chars
+/// they have in common near the same place, with a bonus for starting the
+/// same way
+pub fn jaro_winkler(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return match a.len() == b.len() {
+            true => 1.0,
+            false => 0.0,
+        };
+    }
+
+    // Chars match if they're this close to each other's place
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut used = vec![false; b.len()];
+    let mut matched = vec![];
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if let Some(j) = (start..end).find(|&j| !used[j] && b[j] == *ca) {
+            used[j] = true;
+            matched.push(*ca);
+        }
+    }
+    if matched.is_empty() {
+        return 0.0;
+    }
+
+    // The matches that are in a different order in b, counted in pairs
+    let in_b = b.iter().zip(&used).filter(|(_, u)| **u).map(|(c, _)| c);
+    let transposed = matched.iter().zip(in_b).filter(|(x, y)| x != y).count() / 2;
+    let m = matched.len() as f32;
+    let jaro = (m / a.len() as f32 + m / b.len() as f32 + (m - transposed as f32) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+
+    return jaro + prefix as f32 * 0.1 * (1.0 - jaro);
+}
+
+/// How suggestions are ranked
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    /// The difflib similarity ratio: how many letters the words have in
+    /// common, in order
+    #[default]
+    Ratio,
+    /// The Levenshtein distance: the fewest letters added, removed or
+    /// changed
+    Levenshtein,
+    /// The Damerau-Levenshtein distance: the fewest letters added, removed,
+    /// changed or swapped with the one next to them.  Swaps rank first
+    Damerau,
+    /// The Jaro-Winkler similarity: the letters in common near the same
+    /// place, favoring words that start the same way
+    JaroWinkler,
+}
+
+impl Metric {
+    /// Make a scorer for the candidates for the word
+    pub fn scorer<'b>(&self, word: &'b str) -> Box<dyn Scorer<'b> + 'b> {
+        return match self {
+            Metric::Ratio => Box::new(RatioScorer::new(word)),
+            // Swapping 2 chars costs the same as changing both
+            Metric::Levenshtein => Box::new(EditScorer::new(word, 2 * EDIT)),
+            Metric::Damerau => Box::new(EditScorer::new(word, EDIT / 2)),
+            Metric::JaroWinkler => Box::new(JaroScorer {
+                word,
+                len: word.chars().count(),
+            }),
+        };
+    }
+
+    /// Check whether the words are within `max` edits of each other.  Only
+    /// damerau counts a swap as 1 edit, the others go by Levenshtein
+    /// distance.
+    pub fn within(&self, a: &str, b: &str, max: usize) -> bool {
+        return match self {
+            Metric::Damerau => damerau_distance(a, b) <= max,
+            _ => within_distance(a, b, max),
+        };
+    }
+}
+
+/// Scores how similar each candidate is to a word, from 0 to 1, the
+/// higher the better
+pub trait Scorer<'b> {
+    fn score(&mut self, cand: &'b str) -> f32;
+
+    /// Check whether the candidate could get a score that `beats` wants
+    /// from a quick look at it, so the full score is only worked out for
+    /// the ones that might
+    fn could_beat(&mut self, _cand: &'b str, _beats: &dyn Fn(f32) -> bool) -> bool {
+        return true;
+    }
+}
+
+/// How long a second sequence has to be for SequenceMatcher to leave its
+/// most common elements out of its index
+const POPULAR_LEN: usize = 200;
+
+/// The difflib ratio, `SequenceMatcher::new(word, cand).ratio()`.  The
+/// matcher indexes its second sequence, the candidate, so it would index
+/// every word in the dictionary.  This indexes the word once instead and
+/// finds the same matching blocks from the other side.
+struct RatioScorer<'b> {
+    word: &'b str,
+    counts: [u16; 256],
+    /// Where each byte is in the word
+    at: Vec<Vec<usize>>,
+    /// The length of the match ending at each byte of the word, for the
+    /// last candidate byte and the one being looked at
+    prev: Vec<usize>,
+    cur: Vec<usize>,
+}
+
+impl<'b> RatioScorer<'b> {
+    fn new(word: &'b str) -> Self {
+        let mut counts = [0_u16; 256];
+        let mut at = vec![vec![]; 256];
+        for (i, b) in word.bytes().enumerate() {
+            counts[b as usize] = counts[b as usize].saturating_add(1);
+            at[b as usize].push(i);
+        }
+
+        return Self {
+            word,
+            counts,
+            at,
+            prev: vec![0; word.len()],
+            cur: vec![0; word.len()],
+        };
+    }
+
+    /// The number of bytes in the matching blocks of the word and the
+    /// candidate: the longest match, then the longest on either side of
+    /// it, and so on
+    fn matches(&mut self, cand: &[u8]) -> usize {
+        let mut ret = 0;
+        let mut queue = vec![(0, self.word.len(), 0, cand.len())];
+        while let Some((alo, ahi, blo, bhi)) = queue.pop() {
+            let (i, j, k) = self.longest_match(cand, alo..ahi, blo..bhi);
+            if k == 0 {
+                continue;
+            }
+
+            ret += k;
+            if alo < i && blo < j {
+                queue.push((alo, i, blo, j));
+            }
+            if i + k < ahi && j + k < bhi {
+                queue.push((i + k, ahi, j + k, bhi));
+            }
+        }
+
+        return ret;
+    }
+
+    /// The longest match between the ranges of the word and the candidate,
+    /// as its start in each and its length.  Of the longest, it's the one
+    /// that starts first in the word, then in the candidate, like difflib.
+    fn longest_match(
+        &mut self,
+        cand: &[u8],
+        word: Range<usize>,
+        range: Range<usize>,
+    ) -> (usize, usize, usize) {
+        let Self { at, prev, cur, .. } = self;
+        let mut best = (word.start, range.start, 0);
+        for j in range.clone() {
+            for &i in &at[cand[j] as usize] {
+                if !word.contains(&i) {
+                    continue;
+                }
+
+                let k = match i {
+                    0 => 1,
+                    _ => prev[i - 1] + 1,
+                };
+                cur[i] = k;
+                let start = (i + 1 - k, j + 1 - k);
+                if k > best.2 || (k == best.2 && start < (best.0, best.1)) {
+                    best = (start.0, start.1, k);
+                }
+            }
+
+            // Only the lengths for the last candidate byte are kept
+            if j > range.start {
+                for &i in &at[cand[j - 1] as usize] {
+                    prev[i] = 0;
+                }
+            }
+            std::mem::swap(prev, cur);
+        }
+        if let Some(j) = range.end.checked_sub(1).filter(|j| range.contains(j)) {
+            for &i in &at[cand[j] as usize] {
+                prev[i] = 0;
+            }
+        }
+
+        return best;
+    }
+}
+
+impl<'b> Scorer<'b> for RatioScorer<'b> {
+    fn score(&mut self, cand: &'b str) -> f32 {
+        // SequenceMatcher leaves the common bytes of a long candidate out
+        if cand.len() >= POPULAR_LEN {
+            return SequenceMatcher::new(self.word, cand).ratio();
+        }
+
+        let total = self.word.len() + cand.len();
+        return ratio_for(self.matches(cand.as_bytes()), total);
+    }
+
+    /// The length alone, or the bytes the words have in common, can rule
+    /// a candidate out
+    fn could_beat(&mut self, cand: &'b str, beats: &dyn Fn(f32) -> bool) -> bool {
+        let total = self.word.len() + cand.len();
+        return beats(ratio_for(self.word.len().min(cand.len()), total))
+            && beats(ratio_for(quick_matches(&self.counts, cand), total));
+    }
+}
+
+/// An upper bound on the number of matching bytes between 2 strings: the
+/// bytes they have in common, regardless of order
+fn quick_matches(counts: &[u16; 256], other: &str) -> usize {
+    let mut avail = *counts;
+    let mut ret = 0;
+    for b in other.bytes() {
+        if avail[b as usize] > 0 {
+            avail[b as usize] -= 1;
+            ret += 1;
+        }
+    }
+
+    return ret;
+}
+
+/// The Levenshtein or Damerau-Levenshtein distance, as a share of the
+/// longer word's length, taken off 1
+struct EditScorer<'b> {
+    word: &'b str,
+    len: usize,
+    /// What a swap costs, in half edits
+    swap: usize,
+    rows: Vec<usize>,
+}
+
+impl<'b> EditScorer<'b> {
+    fn new(word: &'b str, swap: usize) -> Self {
+        return Self {
+            word,
+            len: word.chars().count(),
+            swap,
+            rows: vec![],
+        };
+    }
+}
+
+impl<'b> Scorer<'b> for EditScorer<'b> {
+    fn score(&mut self, cand: &'b str) -> f32 {
+        let len = self.len.max(cand.chars().count());
+        if len == 0 {
+            return 1.0;
+        }
+        let half = half_edits(self.word, cand, self.swap, None, &mut self.rows);
+
+        return 1.0 - half as f32 / (EDIT * len) as f32;
+    }
+
+    /// Every char of the difference in length is an edit
+    fn could_beat(&mut self, cand: &'b str, beats: &dyn Fn(f32) -> bool) -> bool {
+        let cand_len = cand.chars().count();
+        let longest = self.len.max(cand_len);
+        return longest == 0 || beats(1.0 - self.len.abs_diff(cand_len) as f32 / longest as f32);
+    }
+}
+
+struct JaroScorer<'b> {
+    word: &'b str,
+    len: usize,
+}
+
+impl<'b> Scorer<'b> for JaroScorer<'b> {
+    fn score(&mut self, cand: &'b str) -> f32 {
+        return jaro_winkler(self.word, cand);
+    }
+
+    /// At most the shorter word's chars can match, and even then the
+    /// prefix bonus can only make up part of the difference
+    fn could_beat(&mut self, cand: &'b str, beats: &dyn Fn(f32) -> bool) -> bool {
+        let cand_len = cand.chars().count();
+        if self.len == 0 || cand_len == 0 {
+            return true;
+        }
+        let m = self.len.min(cand_len) as f32;
+        let jaro = (m / self.len as f32 + m / cand_len as f32 + 1.0) / 3.0;
+
+        return beats(jaro + 0.4 * (1.0 - jaro));
+    }
+}
+
+/// Another scorer, with a bonus for changing letters to the ones on the
+/// keys next to them, like "spwll" for "spell": how much closer the words
+/// are when each of those changes only counts as half an edit
+pub struct KeyboardScorer<'b> {
+    inner: Box<dyn Scorer<'b> + 'b>,
+    word: &'b str,
+    len: usize,
+    keys: KeyMap,
+    rows: Vec<usize>,
+}
+
+impl<'b> KeyboardScorer<'b> {
+    pub fn new(inner: Box<dyn Scorer<'b> + 'b>, word: &'b str, layout: Layout) -> Self {
+        return Self {
+            inner,
+            word,
+            len: word.chars().count(),
+            keys: KeyMap::new(layout),
+            rows: vec![],
+        };
+    }
+}
+
+impl<'b> Scorer<'b> for KeyboardScorer<'b> {
+    fn score(&mut self, cand: &'b str) -> f32 {
+        let score = self.inner.score(cand);
+        let len = self.len.max(cand.chars().count());
+        if len == 0 {
+            return score;
+        }
+        let plain = half_edits(self.word, cand, 2 * EDIT, None, &mut self.rows);
+        let keyed = half_edits(self.word, cand, 2 * EDIT, Some(&self.keys), &mut self.rows);
+
+        return score + (plain - keyed) as f32 / (EDIT * len) as f32;
+    }
+
+    /// At most every char of the shorter word is changed to a key next to
+    /// it
+    fn could_beat(&mut self, cand: &'b str, beats: &dyn Fn(f32) -> bool) -> bool {
+        let cand_len = cand.chars().count();
+        let longest = self.len.max(cand_len);
+        if longest == 0 {
+            return true;
+        }
+        let bonus = self.len.min(cand_len) as f32 / (EDIT * longest) as f32;
+
+        return self.inner.could_beat(cand, &|score| beats(score + bonus));
+    }
+}
+
+/// How far apart 2 words are by each of the metrics, for `spel distance`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Distances {
+    pub levenshtein: usize,
+    pub damerau: usize,
+    pub jaro_winkler: f32,
+    /// The difflib ratio, with the first word taken as the misspelling
+    pub ratio: f32,
+    /// What suggestions are ranked by with the options
+    pub score: f32,
+}
+
+impl Distances {
+    pub fn new(word: &str, cand: &str, suggest: &SuggestOpts) -> Self {
+        return Self {
+            levenshtein: edit_distance(word, cand),
+            damerau: damerau_distance(word, cand),
+            jaro_winkler: jaro_winkler(word, cand),
+            ratio: ratio(word, cand),
+            score: suggest.scorer(word).score(cand),
+        };
+    }
+}
+
+#[test]
+fn test_damerau_distance() {
+    assert_eq!(damerau_distance("teh", "the"), 1);
+    assert_eq!(damerau_distance("kitten", "sitting"), 3);
+    assert_eq!(damerau_distance("", "abc"), 3);
+    assert_eq!(damerau_distance("recieve", "receive"), 1);
+    assert_eq!(damerau_distance("naïve", "niave"), 2);
+    assert_eq!(damerau_distance("ïa", "aï"), 1);
+    // Swapped chars aren't edited again
+    assert_eq!(damerau_distance("ca", "abc"), 3);
+
+    assert_eq!(damerau_ratio("cat", "cat"), 1.0);
+    assert_eq!(damerau_ratio("", ""), 1.0);
+    assert_eq!(damerau_ratio("abcd", "abdc"), 0.875);
+    assert_eq!(damerau_ratio("abcd", "abce"), 0.75);
+}
+
+#[test]
+fn test_jaro_winkler() {
+    let near = |a, b, want: f32| {
+        let got = jaro_winkler(a, b);
+        assert!((got - want).abs() < 0.001, "{} {} {}", a, b, got);
+    };
+    near("martha", "marhta", 0.961);
+    near("dixon", "dicksonx", 0.813);
+    near("dwayne", "duane", 0.84);
+    near("same", "same", 1.0);
+    near("abc", "xyz", 0.0);
+    near("", "", 1.0);
+    near("", "a", 0.0);
+    near("naïve", "naive", 0.893);
+
+    let damerau = SuggestOpts {
+        metric: Metric::Damerau,
+        ..SuggestOpts::new(5)
+    };
+    let d = Distances::new("teh", "the", &damerau);
+    assert_eq!((d.levenshtein, d.damerau), (2, 1));
+    assert_eq!(d.score, damerau_ratio("teh", "the"));
+    assert_eq!(
+        Distances::new("teh", "the", &SuggestOpts::new(5)).score,
+        d.ratio
+    );
+}
+
+#[test]
+fn test_find_scored() {
+    use crate::util::{by_rank, dedup_variants, dict_text, find_scored, get_words, BUILTIN_DICT};
+
+    let words = ["ten", "tea", "tech", "the", "Ten", "teeth", "xylophone"];
+    let found = |metric: Metric| -> Vec<&str> {
+        find_scored(metric.scorer("teh").as_mut(), &words, 5)
+            .iter()
+            .map(|(_, w)| *w)
+            .collect()
+    };
+    // The swap comes first, and "Ten" is the same suggestion as "ten" but
+    // further off
+    assert_eq!(
+        found(Metric::Damerau),
+        ["the", "tech", "tea", "ten", "teeth"]
+    );
+    assert_eq!(
+        found(Metric::Levenshtein),
+        ["tech", "tea", "ten", "teeth", "the"]
+    );
+    assert_eq!(found(Metric::JaroWinkler)[0], "tech");
+    assert!(find_scored(Metric::Damerau.scorer("teh").as_mut(), &words, 0).is_empty());
+
+    // The quick checks don't change anything, it's the same as scoring the
+    // whole list, with the keyboard's bonus too
+    let words = get_words(dict_text(BUILTIN_DICT));
+    let sample: Vec<&str> = words.iter().step_by(97).copied().collect();
+    for &metric in Metric::value_variants() {
+        for keyboard in [None, Some(Layout::Qwerty)] {
+            let opts = SuggestOpts {
+                metric,
+                keyboard,
+                ..SuggestOpts::new(10)
+            };
+            for word in ["recieve", "hte", "a", "naïve", "acommodation", "spwll"] {
+                let mut scorer = opts.scorer(word);
+                let mut want: Vec<(f32, &str)> =
+                    sample.iter().map(|w| (scorer.score(w), *w)).collect();
+                want.sort_by(by_rank);
+                dedup_variants(&mut want);
+                want.truncate(10);
+                let got = find_scored(opts.scorer(word).as_mut(), &sample, 10);
+                assert_eq!(got, want, "{:?} {:?} {}", metric, keyboard, word);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ratio_scorer() {
+    use crate::util::{dict_text, get_words, BUILTIN_DICT};
+
+    // The same ratio as difflib, which isn't symmetric, for every word in
+    // the dictionary, scored by the one scorer
+    let words = get_words(dict_text(BUILTIN_DICT));
+    let long = "ab".repeat(POPULAR_LEN);
+    for word in ["recieve", "aabbaa", "mississippi", "naïve", "", &long] {
+        let mut scorer = RatioScorer::new(word);
+        for cand in words.iter().step_by(41).chain(&["", "abba", &long]) {
+            let want = SequenceMatcher::new(word, cand).ratio();
+            assert_eq!(scorer.score(cand), want, "{} {}", word, cand);
+        }
+    }
+    assert_ne!(
+        ratio("recieve", "preperceive"),
+        ratio("preperceive", "recieve")
+    );
+}
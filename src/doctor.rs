@@ -0,0 +1,352 @@
+use crate::config::{Config, Settings};
+use crate::ngram::BigramModel;
+use crate::personal::PersonalDict;
+use crate::project::{ProjectLists, PROJECT_DIR};
+use crate::util::{
+    collect_files, dict_text, get_words, ignore_words, parse_path, read_bytes, CheckOpts, WalkOpts,
+    BUILTIN_DICT,
+};
+use crate::wordlist::{split_comment, until};
+use chrono::Local;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+/// What `spel doctor` found out about the setup: a section of lines for
+/// each thing it looked at, and any problems with them
+#[derive(Debug, Default)]
+pub struct Doctor {
+    pub sections: Vec<(String, Vec<String>)>,
+    pub problems: Vec<String>,
+}
+
+impl Doctor {
+    fn section(&mut self, title: &str, lines: Vec<String>) {
+        self.sections.push((title.to_string(), lines));
+    }
+
+    /// Load the config file, noting whether it was there, and get the
+    /// settings for the profile.  The defaults are used if it can't be
+    /// loaded.
+    pub fn config(&mut self, path: &Path, profile: Option<&str>) -> Settings {
+        let (line, config) = match Config::load(path) {
+            Ok(config) if path.exists() => (format!("{} (loaded)", path.display()), config),
+            Ok(config) => (
+                format!("{} (not found, using the defaults)", path.display()),
+                config,
+            ),
+            Err(e) => {
+                self.problems
+                    .push(format!("config file {}: {:#}", path.display(), e));
+                (format!("{} (invalid)", path.display()), Config::default())
+            }
+        };
+        let mut lines = vec![line];
+        if let Some(name) = profile {
+            lines.push(format!("profile {}", name));
+        }
+        for o in &config.overrides {
+            lines.push(format!("override for {}", o.paths.join(", ")));
+        }
+        self.section("Config", lines);
+
+        return match config.settings(profile) {
+            Ok(settings) => settings,
+            Err(e) => {
+                self.problems
+                    .push(format!("config file {}: {:#}", path.display(), e));
+                config.settings
+            }
+        };
+    }
+
+    /// Count the words to ignore from each source, and check the ignore
+    /// file's entries
+    pub fn ignore(&mut self, ignore: &[String], ignore_file: &Path) {
+        let mut lines = vec![];
+        if !ignore.is_empty() {
+            let count = ignore_words(ignore).len();
+            lines.push(format!("--ignore: {} word(s)", count));
+        }
+
+        let real_path = parse_path(ignore_file);
+        let file = match fs::File::open(&real_path) {
+            Ok(f) => f,
+            Err(e) => {
+                lines.push(format!("{}: not loaded ({})", ignore_file.display(), e));
+                self.section("Ignore lists", lines);
+                return;
+            }
+        };
+
+        let today = Local::now().date_naive();
+        let mut count = 0;
+        for (lnum, line) in (1_u64..).zip(BufReader::new(file).lines()) {
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => {
+                    self.problems.push(format!(
+                        "{} line {}: {}, nothing from here on is ignored",
+                        ignore_file.display(),
+                        lnum,
+                        e
+                    ));
+                    break;
+                }
+            };
+
+            let (word, comment) = split_comment(&line);
+            if word.is_empty() || word.starts_with('#') {
+                continue;
+            }
+            match comment.and_then(until) {
+                Some(Ok(date)) if date < today => {
+                    self.problems.push(format!(
+                        "{} line {}: \"{}\" expired on {}, so it isn't ignored any more",
+                        ignore_file.display(),
+                        lnum,
+                        word,
+                        date
+                    ));
+                    continue;
+                }
+                Some(Err(e)) => {
+                    self.problems
+                        .push(format!("{} line {}: {}", ignore_file.display(), lnum, e));
+                }
+                _ => (),
+            }
+            count += 1;
+            // Words are checked lowercase, so these never match
+            if word.chars().any(char::is_uppercase) && !word.contains(char::is_whitespace) {
+                self.problems.push(format!(
+                    "{} line {}: \"{}\" has capitals, so it never matches; use \"{}\"",
+                    ignore_file.display(),
+                    lnum,
+                    word,
+                    word.to_lowercase()
+                ));
+            }
+        }
+        lines.push(format!("{}: {} word(s)", ignore_file.display(), count));
+        self.section("Ignore lists", lines);
+    }
+
+    /// Load the dictionary, checking for entries that can't be right.
+    /// Returns the dictionary text if there is one.
+    pub fn dictionary(&mut self, dict: Option<&Path>) -> Option<&'static str> {
+        let (name, fbytes): (String, &'static [u8]) = match dict {
+            None => ("built in".to_string(), BUILTIN_DICT),
+            Some(path) => match read_bytes(path) {
+                Ok(bytes) => (path.display().to_string(), bytes.leak()),
+                Err(e) => {
+                    self.problems
+                        .push(format!("dictionary {}: {}", path.display(), e));
+                    self.section(
+                        "Dictionary",
+                        vec![format!("{}: not loaded", path.display())],
+                    );
+                    return None;
+                }
+            },
+        };
+
+        if std::str::from_utf8(fbytes).is_err() {
+            self.problems.push(format!(
+                "dictionary {}: not valid utf-8, the bad bytes are replaced",
+                name
+            ));
+        }
+        let text = dict_text(fbytes);
+        let words = get_words(text);
+
+        let mut seen = HashSet::new();
+        let (mut blank, mut dups) = (0, 0);
+        for word in &words {
+            if word.trim().is_empty() {
+                blank += 1;
+            } else if !seen.insert(*word) {
+                dups += 1;
+            }
+        }
+        if blank > 0 {
+            self.problems
+                .push(format!("dictionary {}: {} blank line(s)", name, blank));
+        }
+        if dups > 0 {
+            self.problems
+                .push(format!("dictionary {}: {} duplicate word(s)", name, dups));
+        }
+
+        self.section(
+            "Dictionary",
+            vec![format!("{}: {} word(s)", name, seen.len())],
+        );
+
+        return Some(text);
+    }
+
+    /// Count the words in the personal dictionary
+    pub fn personal(&mut self, personal: &PersonalDict) {
+        let line = match parse_path(&personal.path).exists() {
+            true => format!(
+                "{}: {} word(s)",
+                personal.path.display(),
+                personal.words.len()
+            ),
+            false => format!("{}: not created yet", personal.path.display()),
+        };
+        self.section("Personal dictionary", vec![line]);
+    }
+
+    /// Count the words in the repo's word lists
+    pub fn project(&mut self, lists: &ProjectLists) {
+        let lines = match &lists.dir {
+            Some(dir) => vec![
+                format!(
+                    "{}: {} word(s)",
+                    dir.join("words.txt").display(),
+                    lists.words.len()
+                ),
+                format!(
+                    "{}: {} word(s)",
+                    dir.join("ignore.txt").display(),
+                    lists.ignore.len()
+                ),
+            ],
+            None => vec![format!("no {} directory found", PROJECT_DIR)],
+        };
+        self.section("Project word lists", lines);
+    }
+
+    /// Load the bigram model, if there is one
+    pub fn ngrams(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            return;
+        };
+
+        let line = match BigramModel::load(path) {
+            Ok(_) => format!("{} (loaded)", path.display()),
+            Err(e) => {
+                self.problems
+                    .push(format!("ngrams file {}: {:#}", path.display(), e));
+                format!("{} (not loaded)", path.display())
+            }
+        };
+        self.section("Bigram model", vec![line]);
+    }
+
+    /// Which filter is used for each type of file under the paths
+    pub fn filters(&mut self, paths: &[PathBuf], walk: &WalkOpts, opts: &CheckOpts) {
+        // Files are grouped by their extension, or their name if they
+        // don't have one
+        let mut types: BTreeMap<String, (String, usize)> = BTreeMap::new();
+        for file in collect_files(paths, walk) {
+            let key = match file.extension() {
+                Some(ext) => format!("*.{}", ext.to_string_lossy()),
+                None => file
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into(),
+            };
+            let entry = types
+                .entry(key)
+                .or_insert_with(|| (opts.filter_name(&file), 0));
+            entry.1 += 1;
+        }
+
+        let width = types.keys().map(|k| k.len()).max().unwrap_or(0);
+        let lines = types
+            .into_iter()
+            .map(|(key, (filter, count))| {
+                format!(
+                    "{:<width$}  {} ({} file(s))",
+                    key,
+                    filter,
+                    count,
+                    width = width
+                )
+            })
+            .collect();
+        self.section("Filters", lines);
+    }
+
+    pub fn print(&self) {
+        for (title, lines) in &self.sections {
+            println!("{}:", title);
+            for line in lines {
+                println!("  {}", line);
+            }
+            println!();
+        }
+
+        if self.problems.is_empty() {
+            println!("No problems found");
+            return;
+        }
+        println!("Problems:");
+        for p in &self.problems {
+            println!("  {}", p);
+        }
+    }
+}
+
+#[test]
+fn test_doctor() {
+    let dir = tempfile::tempdir().unwrap();
+    let ign = dir.path().join("ignore");
+    fs::write(
+        &ign,
+        "# Names\nspel\n\nParis\nad Hoc\nold # until=2000-01-01\n",
+    )
+    .unwrap();
+    let dict = dir.path().join("dict");
+    fs::write(&dict, "cat\ndog\n\ncat\n").unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir(&src).unwrap();
+    fs::write(src.join("a.md"), "").unwrap();
+    fs::write(src.join("b.md"), "").unwrap();
+    fs::write(src.join("Makefile"), "").unwrap();
+
+    let mut doc = Doctor::default();
+    let settings = doc.config(&dir.path().join("spel.toml"), None);
+    assert_eq!(settings, Settings::default());
+    doc.ignore(&["a, b,".to_string()], &ign);
+    assert_eq!(doc.dictionary(Some(&dict)), Some("cat\ndog\n\ncat\n"));
+    assert_eq!(doc.dictionary(Some(&dir.path().join("nope"))), None);
+
+    let opts = CheckOpts {
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    doc.filters(&[src], &WalkOpts::default(), &opts);
+
+    let lines: Vec<&str> = doc
+        .sections
+        .iter()
+        .flat_map(|(_, l)| l)
+        .map(|l| l.as_str())
+        .collect();
+    assert!(lines[0].ends_with("(not found, using the defaults)"));
+    assert_eq!(lines[1], "--ignore: 2 word(s)");
+    assert!(lines[2].ends_with(": 3 word(s)"));
+    assert!(lines[3].ends_with(": 2 word(s)"));
+    assert!(lines[4].ends_with(": not loaded"));
+    assert_eq!(
+        &lines[5..],
+        [
+            "*.md      markdown (2 file(s))",
+            "Makefile  shell (1 file(s))"
+        ]
+    );
+
+    assert_eq!(doc.problems.len(), 5, "{:?}", doc.problems);
+    assert!(doc.problems[0].contains("\"Paris\" has capitals"));
+    assert!(doc.problems[1].contains("\"old\" expired on 2000-01-01"));
+    assert!(doc.problems[2].ends_with("1 blank line(s)"));
+    assert!(doc.problems[3].ends_with("1 duplicate word(s)"));
+}
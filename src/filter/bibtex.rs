@@ -0,0 +1,217 @@
+use super::LineFilter;
+
+/// The fields that hold prose
+const CHECKED_FIELDS: &[&str] = &["title", "abstract", "note"];
+
+#[derive(Debug, Default, PartialEq)]
+enum State {
+    /// Between entries
+    #[default]
+    Outside,
+    /// Reading the entry type, after the @
+    Type,
+    /// Reading the citation key
+    Key,
+    /// Reading a field name, or between fields
+    Name,
+    /// After the = of a field
+    BeforeValue,
+    /// Inside a {} or "" delimited value
+    Value,
+    /// A bare value, like a number or a @string macro
+    BareValue,
+}
+
+/// Checks only the title, abstract and note fields of BibTeX entries,
+/// skipping the keys, authors, URLs and everything else, along with any
+/// LaTeX commands in the values
+#[derive(Debug, Default)]
+pub struct BibtexFilter {
+    state: State,
+    key: String,
+    field: String,
+    /// Brace depth, where 1 is directly inside the entry
+    depth: usize,
+    /// The depth the current value closes at
+    value_depth: usize,
+    quoted: bool,
+    /// Inside a \command
+    in_command: bool,
+}
+
+impl BibtexFilter {
+    /// Work out whether to keep a char, updating the state
+    fn keep(&mut self, c: char) -> bool {
+        match self.state {
+            State::Outside => {
+                if c == '@' {
+                    self.state = State::Type;
+                    self.key.clear();
+                }
+            }
+            State::Type => {
+                if c == '{' || c == '(' {
+                    self.depth = 1;
+                    self.state = State::Key;
+                }
+            }
+            State::Key => match c {
+                ',' => {
+                    self.state = State::Name;
+                    self.field.clear();
+                }
+                '}' | ')' => self.end_entry(),
+                c if !c.is_whitespace() => self.key.push(c),
+                _ => (),
+            },
+            State::Name => match c {
+                '=' => self.state = State::BeforeValue,
+                ',' => self.field.clear(),
+                '}' | ')' if self.depth == 1 => self.end_entry(),
+                c if !c.is_whitespace() => self.field.push(c.to_ascii_lowercase()),
+                _ => (),
+            },
+            State::BeforeValue => match c {
+                '{' => {
+                    self.depth += 1;
+                    self.value_depth = self.depth;
+                    self.quoted = false;
+                    self.state = State::Value;
+                }
+                '"' => {
+                    self.value_depth = self.depth;
+                    self.quoted = true;
+                    self.state = State::Value;
+                }
+                c if !c.is_whitespace() => self.state = State::BareValue,
+                _ => (),
+            },
+            State::BareValue => match c {
+                ',' => {
+                    self.state = State::Name;
+                    self.field.clear();
+                }
+                '}' | ')' => self.end_entry(),
+                _ => (),
+            },
+            State::Value => return self.keep_value(c),
+        }
+
+        return false;
+    }
+
+    /// Handle a char inside a value, keeping it if this field is checked
+    fn keep_value(&mut self, c: char) -> bool {
+        if self.in_command {
+            if c.is_alphabetic() {
+                return false;
+            }
+            self.in_command = false;
+        }
+
+        match c {
+            '\\' => {
+                self.in_command = true;
+                return false;
+            }
+            '{' => {
+                self.depth += 1;
+                return false;
+            }
+            '}' => {
+                self.depth = self.depth.saturating_sub(1);
+                if self.depth == 0 {
+                    // A stray } in a quoted value closes the entry
+                    self.end_entry();
+                } else if !self.quoted && self.depth < self.value_depth {
+                    self.end_value();
+                }
+                return false;
+            }
+            '"' if self.quoted && self.depth == self.value_depth => {
+                self.end_value();
+                return false;
+            }
+            _ => return CHECKED_FIELDS.contains(&self.field.as_str()),
+        }
+    }
+
+    fn end_value(&mut self) {
+        self.state = State::Name;
+        self.field.clear();
+    }
+
+    fn end_entry(&mut self) {
+        self.state = State::Outside;
+        self.depth = 0;
+    }
+}
+
+impl LineFilter for BibtexFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let mut ret = String::with_capacity(line.len());
+        for c in line.chars() {
+            if self.keep(c) {
+                ret.push(c);
+            } else {
+                ret.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+
+        return ret;
+    }
+
+    fn scope(&self) -> Option<&str> {
+        return Some(self.key.as_str()).filter(|k| !k.is_empty());
+    }
+}
+
+#[test]
+fn test_bibtex_filter() {
+    use super::Filter;
+
+    let src = "@article{smyth2020,\n\
+        \x20 author = {Smyth, Jonh and Doe, Jane},\n\
+        \x20 title = {A {Studdy} of \\emph{Spelling}},\n\
+        \x20 year = 2020,\n\
+        \x20 url = {https://example.com/paperr},\n\
+        \x20 abstract = \"We examin\n\
+        \x20   typos.\",\n\
+        }\n\
+        @misc{other, note = {Seee also}}";
+    let mut f = Filter::Bibtex.line_filter(&Default::default()).unwrap();
+    let mut kept = vec![];
+    for line in src.lines() {
+        let masked = f.mask(line);
+        assert_eq!(masked.len(), line.len());
+        for word in masked.split_whitespace() {
+            kept.push((f.scope().unwrap().to_string(), word.to_string()));
+        }
+    }
+
+    let expected = [
+        ("smyth2020", "A"),
+        ("smyth2020", "Studdy"),
+        ("smyth2020", "of"),
+        ("smyth2020", "Spelling"),
+        ("smyth2020", "We"),
+        ("smyth2020", "examin"),
+        ("smyth2020", "typos."),
+        ("other", "Seee"),
+        ("other", "also"),
+    ];
+    let expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(k, w)| (k.to_string(), w.to_string()))
+        .collect();
+    assert_eq!(kept, expected);
+
+    // Unbalanced braces in a quoted value end the entry, and the next one
+    // is read as usual
+    let mut f = Filter::Bibtex.line_filter(&Default::default()).unwrap();
+    let masked = f.mask("@misc{a, title = \"x}}\"} @misc{b, note = {Fiine}}");
+    assert_eq!(
+        masked.split_whitespace().collect::<Vec<_>>(),
+        ["x", "Fiine"]
+    );
+}
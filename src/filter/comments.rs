@@ -0,0 +1,234 @@
+use super::{blank, LineFilter};
+use std::path::Path;
+
+/// How comments and strings are written in a language
+#[derive(Debug, Clone, Copy)]
+struct Syntax {
+    /// Markers that start a comment running to the end of the line
+    line: &'static [&'static str],
+    /// The open and close markers of block comments
+    block: Option<(&'static str, &'static str)>,
+    /// The chars that quote strings, which can't hold comments
+    quotes: &'static str,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    quotes: "\"",
+};
+const SCRIPT: Syntax = Syntax {
+    line: &["#"],
+    block: None,
+    quotes: "\"'",
+};
+const CSS: Syntax = Syntax {
+    line: &[],
+    block: Some(("/*", "*/")),
+    quotes: "\"'",
+};
+const JS: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    quotes: "\"'`",
+};
+const SQL: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("/*", "*/")),
+    quotes: "'",
+};
+const LUA: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("--[[", "]]")),
+    quotes: "\"'",
+};
+const HASKELL: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("{-", "-}")),
+    quotes: "\"",
+};
+const LISP: Syntax = Syntax {
+    line: &[";"],
+    block: None,
+    quotes: "\"",
+};
+const INI: Syntax = Syntax {
+    line: &[";", "#"],
+    block: None,
+    quotes: "",
+};
+const PHP: Syntax = Syntax {
+    line: &["//", "#"],
+    block: Some(("/*", "*/")),
+    quotes: "\"'",
+};
+const ERLANG: Syntax = Syntax {
+    line: &["%"],
+    block: None,
+    quotes: "\"",
+};
+
+/// The comment syntax for each file extension, for source files that don't
+/// have a filter of their own
+const SYNTAXES: &[(&[&str], Syntax)] = &[
+    (
+        &[
+            "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "java", "go", "rs", "swift", "kt", "kts",
+            "scala", "cs", "dart", "zig", "proto",
+        ],
+        C_LIKE,
+    ),
+    (&["js", "mjs", "cjs", "jsx", "ts", "tsx"], JS),
+    (&["css", "scss", "less"], CSS),
+    (
+        &[
+            "py", "rb", "pl", "pm", "r", "toml", "cmake", "nix", "tf", "conf", "ps1",
+        ],
+        SCRIPT,
+    ),
+    (&["sql"], SQL),
+    (&["lua"], LUA),
+    (&["hs", "elm"], HASKELL),
+    (
+        &["lisp", "el", "clj", "cljs", "scm", "rkt", "asm", "s"],
+        LISP,
+    ),
+    (&["ini", "cfg"], INI),
+    (&["php"], PHP),
+    (&["erl", "hrl"], ERLANG),
+];
+
+/// Checks just the comments in source files, going by a table of comment
+/// syntax per file extension, for --comments-only.  This is a heuristic:
+/// it knows about strings, but not about things like raw strings or
+/// nested comments.
+#[derive(Debug)]
+pub struct CommentsFilter {
+    syntax: Syntax,
+    /// The marker that closes the block comment we're in
+    in_block: Option<&'static str>,
+}
+
+impl CommentsFilter {
+    /// Start the filter for a file, if its comment syntax is known
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let (_, syntax) = SYNTAXES
+            .iter()
+            .find(|(exts, _)| exts.contains(&ext.as_str()))?;
+
+        return Some(Self {
+            syntax: *syntax,
+            in_block: None,
+        });
+    }
+
+    /// Mask the rest of a block comment, returning the masked text and how
+    /// much of the line it used
+    fn block_text(&mut self, text: &str, close: &'static str) -> (String, usize) {
+        // Leading " * " decorations
+        let body = text.trim_start().trim_start_matches('*');
+        let prefix = text.len() - body.len();
+
+        return match body.find(close) {
+            Some(end) => {
+                self.in_block = None;
+                let end = prefix + end;
+                (
+                    blank(&text[..prefix]) + &text[prefix..end] + &blank(close),
+                    end + close.len(),
+                )
+            }
+            None => (blank(&text[..prefix]) + body, text.len()),
+        };
+    }
+}
+
+impl LineFilter for CommentsFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let mut ret = String::with_capacity(line.len());
+        let mut i = 0;
+        let mut quote = None;
+
+        while i < line.len() {
+            let rest = &line[i..];
+
+            if let Some(close) = self.in_block {
+                let (masked, used) = self.block_text(rest, close);
+                ret.push_str(&masked);
+                i += used;
+                continue;
+            }
+
+            let c = rest.chars().next().unwrap();
+            let len = c.len_utf8();
+            match quote {
+                Some(_) if c == '\\' => {
+                    let escaped = rest[1..].chars().next().map_or(0, |e| e.len_utf8());
+                    ret.push_str(&blank(&rest[..1 + escaped]));
+                    i += 1 + escaped;
+                    continue;
+                }
+                Some(q) if c == q => quote = None,
+                Some(_) => (),
+                None if self.syntax.quotes.contains(c) => quote = Some(c),
+                None => {
+                    // Block comments first, for --[[ vs. --
+                    if let Some((open, close)) = self.syntax.block {
+                        if rest.starts_with(open) {
+                            ret.push_str(&blank(open));
+                            i += open.len();
+                            self.in_block = Some(close);
+                            continue;
+                        }
+                    }
+                    if let Some(marker) = self.syntax.line.iter().find(|m| rest.starts_with(**m)) {
+                        ret.push_str(&blank(marker));
+                        ret.push_str(&rest[marker.len()..]);
+                        return ret;
+                    }
+                }
+            }
+
+            ret.push_str(&blank(&rest[..len]));
+            i += len;
+        }
+
+        return ret;
+    }
+}
+
+#[test]
+fn test_comments_filter() {
+    let words = |fname: &str, src: &str| {
+        let mut f = CommentsFilter::for_path(Path::new(fname)).unwrap();
+        let masked: Vec<String> = src.lines().map(|l| f.mask(l)).collect();
+        let masked = masked.join("\n");
+        assert_eq!(masked.len(), src.len());
+
+        return masked
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+    };
+
+    let src = "/* A blok\n\
+        \x20* comennt */ int x = 1; // trailing remrk\n\
+        char *s = \"not // a coment\\\" here\"; /* inline */\n\
+        int y; /** unclosed";
+    assert_eq!(
+        words("a.c", src),
+        vec!["A", "blok", "comennt", "trailing", "remrk", "inline", "unclosed"]
+    );
+
+    let src = "x = '# not a coment'  # but this is\n\
+        --[[ lua blok ]] -- lien";
+    assert_eq!(words("a.py", src), vec!["but", "this", "is"]);
+    assert_eq!(
+        words("a.lua", "--[[ lua blok ]] -- lien"),
+        vec!["lua", "blok", "lien"]
+    );
+    assert_eq!(words("q.sql", "SELECT 'it--s' -- a nte"), vec!["a", "nte"]);
+    assert!(CommentsFilter::for_path(Path::new("a.txt")).is_none());
+}
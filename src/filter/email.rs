@@ -0,0 +1,144 @@
+use super::{blank, LineFilter};
+
+#[derive(Debug, Default, PartialEq)]
+enum State {
+    /// Nothing seen yet, a message may or may not start with headers
+    #[default]
+    Start,
+    Headers,
+    Body,
+    /// After a "-- " signature separator, up to the next message
+    Signature,
+}
+
+/// Only checks the new text in the body of each message, skipping the
+/// headers, quoted (">") lines, "On ... wrote:" attributions and the
+/// signature.  Works for a single message or an mbox archive.
+#[derive(Debug, Default)]
+pub struct EmailFilter {
+    state: State,
+    prev_blank: bool,
+}
+
+/// Whether the line looks like a "Name: value" header
+fn is_header(line: &str) -> bool {
+    return match line.split_once(':') {
+        Some((name, _)) => {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }
+        None => false,
+    };
+}
+
+/// Whether the line is an mbox "From " postmark, with the sender and an
+/// asctime date, like "From alice@example.com Mon Jan  1 00:00:00 2024".
+/// A line in a body that only starts with "From " isn't one.
+fn is_postmark(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix("From ") else {
+        return false;
+    };
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let [_sender, day, month, date, time, tail @ ..] = fields.as_slice() else {
+        return false;
+    };
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    return ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].contains(day)
+        && [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ]
+        .contains(month)
+        && date.len() <= 2
+        && digits(date)
+        && time.split(':').count() >= 2
+        && time.split(':').all(digits)
+        // Some mailers put a time zone before the year
+        && tail.iter().any(|s| s.len() == 4 && digits(s));
+}
+
+impl LineFilter for EmailFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let prev_blank = std::mem::replace(&mut self.prev_blank, line.trim().is_empty());
+
+        // In an mbox, a postmark after a blank line starts the next message
+        if is_postmark(line) && (self.state != State::Body || prev_blank) {
+            self.state = State::Headers;
+            return blank(line);
+        }
+
+        if self.state == State::Start {
+            self.state = if is_header(line) {
+                State::Headers
+            } else {
+                State::Body
+            };
+        }
+
+        match self.state {
+            State::Headers => {
+                if line.is_empty() {
+                    self.state = State::Body;
+                }
+                return blank(line);
+            }
+            State::Signature => return blank(line),
+            _ => (),
+        }
+
+        if line == "-- " || line == "--" {
+            self.state = State::Signature;
+            return blank(line);
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with('>') || trimmed.ends_with("wrote:") {
+            return blank(line);
+        }
+
+        return line.to_string();
+    }
+}
+
+#[test]
+fn test_email_filter() {
+    use super::{mask_text, Filter};
+
+    let mbox = "From alice@example.com Mon Jan  1 00:00:00 2024\n\
+        From: Alice <alice@example.com>\n\
+        Subject: Re: teh plan\n\
+        \x20 continued headr\n\
+        \n\
+        On Sunday, Bob wrote:\n\
+        > I recieve it\n\
+        Sounds good\n\
+        -- \n\
+        Alice Smyth\n\
+        \n\
+        From bob@example.com Mon Jan  1 00:00:00 2024\n\
+        Subject: helo\n\
+        \n\
+        Thanks";
+    let masked = mask_text(Filter::Email, mbox);
+    let kept: Vec<&str> = masked
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    assert_eq!(kept, vec!["Sounds good", "Thanks"]);
+    assert_eq!(masked.len(), mbox.len());
+
+    // A paragraph starting with "From " in a single message is body text
+    let eml = "Subject: plans\n\nHi\n\nFrom what I hear, it's fine\nthanks";
+    let masked = mask_text(Filter::Email, eml);
+    assert!(masked.contains("From what I hear, it's fine\nthanks"));
+    assert!(is_postmark(
+        "From bob@example.com Tue Feb 13 09:05 +0100 2024"
+    ));
+    assert!(!is_postmark("From bob@example.com on Tuesday"));
+
+    // A draft without headers is all body
+    assert_eq!(
+        mask_text(Filter::Email, "Hi there\n-- \nme"),
+        "Hi there\n   \n  "
+    );
+}
@@ -0,0 +1,130 @@
+/// Named entities and what they decode to.  Curly quotes decode to their
+/// plain versions so that contractions like "don&rsquo;t" stay 1 word.
+const ENTITIES: &[(&str, char)] = &[
+    ("aacute", 'á'),
+    ("acirc", 'â'),
+    ("aelig", 'æ'),
+    ("agrave", 'à'),
+    ("amp", '&'),
+    ("apos", '\''),
+    ("aring", 'å'),
+    ("atilde", 'ã'),
+    ("auml", 'ä'),
+    ("ccedil", 'ç'),
+    ("copy", '©'),
+    ("eacute", 'é'),
+    ("ecirc", 'ê'),
+    ("egrave", 'è'),
+    ("euml", 'ë'),
+    ("gt", '>'),
+    ("hellip", '…'),
+    ("iacute", 'í'),
+    ("icirc", 'î'),
+    ("igrave", 'ì'),
+    ("iuml", 'ï'),
+    ("laquo", '«'),
+    ("ldquo", '"'),
+    ("lsquo", '\''),
+    ("lt", '<'),
+    ("mdash", '—'),
+    ("nbsp", ' '),
+    ("ndash", '–'),
+    ("ntilde", 'ñ'),
+    ("oacute", 'ó'),
+    ("ocirc", 'ô'),
+    ("oelig", 'œ'),
+    ("ograve", 'ò'),
+    ("oslash", 'ø'),
+    ("otilde", 'õ'),
+    ("ouml", 'ö'),
+    ("quot", '"'),
+    ("raquo", '»'),
+    ("rdquo", '"'),
+    ("reg", '®'),
+    ("rsquo", '\''),
+    ("shy", '\0'),
+    ("szlig", 'ß'),
+    ("trade", '™'),
+    ("uacute", 'ú'),
+    ("ucirc", 'û'),
+    ("ugrave", 'ù'),
+    ("uuml", 'ü'),
+    ("yacute", 'ý'),
+    ("yuml", 'ÿ'),
+    ("zwj", '\0'),
+    ("zwnj", '\0'),
+];
+
+/// Decode the char for the name of an entity, between the & and ;
+fn decode(name: &str) -> Option<char> {
+    let code = match name.strip_prefix('#') {
+        Some(num) => match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        },
+        None => {
+            let i = ENTITIES.binary_search_by_key(&name, |(n, _)| n).ok()?;
+            return Some(ENTITIES[i].1);
+        }
+    };
+
+    return match char::from_u32(code)? {
+        '\u{2018}' | '\u{2019}' => Some('\''),
+        '\u{ad}' | '\u{200b}'..='\u{200d}' => Some('\0'),
+        c => Some(c),
+    };
+}
+
+/// Decode HTML entities, like `&amp;`, `&eacute;` and `&#233;`, padding
+/// the decoded char out with NULs so the text keeps its length.  The
+/// padding is dropped from inside words, so "caf&eacute;" is "café".
+pub fn decode_entities(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        ret.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        // Entity names are short, so don't look too far for the ;
+        let end = rest
+            .char_indices()
+            .take(12)
+            .find(|(_, c)| *c == ';')
+            .map(|(i, _)| i);
+        match end.and_then(|end| Some((end, decode(&rest[1..end])?))) {
+            Some((end, c)) => {
+                ret.push(c);
+                ret.extend(std::iter::repeat_n('\0', end + 1 - c.len_utf8()));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                ret.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+
+    return ret;
+}
+
+#[test]
+fn test_decode_entities() {
+    let cases = [
+        ("caf&eacute; &amp; bar", "café\0\0\0\0\0\0 &\0\0\0\0 bar"),
+        ("don&rsquo;t", "don'\0\0\0\0\0\0t"),
+        ("&#233;t&#xE9;", "é\0\0\0\0té\0\0\0\0"),
+        ("wait&hellip;", "wait…\0\0\0\0\0"),
+        ("AT&T &bogus; &", "AT&T &bogus; &"),
+    ];
+    for (text, want) in cases {
+        let got = decode_entities(text);
+        assert_eq!(got.len(), text.len());
+        assert_eq!(got.replace('\0', "|"), want.replace('\0', "|"));
+    }
+
+    for pair in ENTITIES.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{} is out of order", pair[1].0);
+    }
+}
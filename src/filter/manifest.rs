@@ -0,0 +1,232 @@
+use super::{blank, LineFilter};
+
+/// The [package] keys in a Cargo.toml that hold user facing prose
+const CARGO_KEYS: &[&str] = &["description", "keywords", "categories"];
+
+/// The top level keys in a package.json that hold user facing prose
+const NPM_KEYS: &[&str] = &["description"];
+
+#[derive(Debug, Default, PartialEq)]
+enum Continues {
+    #[default]
+    No,
+    /// Inside a """ or ''' string
+    String(&'static str),
+    /// Inside a [ ] array of strings
+    Array,
+}
+
+/// Checks the user facing metadata strings in a package manifest
+/// (Cargo.toml or package.json), like the description and keywords,
+/// skipping everything else
+#[derive(Debug, Default)]
+pub struct ManifestFilter {
+    /// Whether it's a package.json, known from the first line with
+    /// anything on it
+    json: Option<bool>,
+    /// In the [package] table of a Cargo.toml
+    in_package: bool,
+    /// The {} depth at the start of the line, in a package.json
+    depth: usize,
+    /// What a checked value carries on to the next line with
+    continues: Continues,
+}
+
+impl ManifestFilter {
+    /// Keep just the contents of the strings in a value, noting whether a
+    /// multiline string or an array carries on to the next line
+    fn keep_strings(&mut self, value: &str) -> String {
+        let mut ret = String::with_capacity(value.len());
+        let mut rest = value;
+
+        while !rest.is_empty() {
+            if let Continues::String(quote) = self.continues {
+                match find_close(rest, quote) {
+                    Some(end) => {
+                        ret.push_str(&rest[..end]);
+                        ret.push_str(&blank(quote));
+                        rest = &rest[end + quote.len()..];
+                        self.continues = Continues::No;
+                    }
+                    None => {
+                        ret.push_str(rest);
+                        return ret;
+                    }
+                }
+                continue;
+            }
+
+            let c = rest.chars().next().unwrap();
+            let quote = ["\"\"\"", "'''", "\"", "'"]
+                .into_iter()
+                .find(|q| rest.starts_with(q));
+            match (quote, c) {
+                (Some(q), _) => {
+                    ret.push_str(&blank(q));
+                    rest = &rest[q.len()..];
+                    self.continues = Continues::String(q);
+                    // Single quoted strings don't span lines
+                    if q.len() == 1 && find_close(rest, q).is_none() {
+                        ret.push_str(&blank(rest));
+                        self.continues = Continues::No;
+                        return ret;
+                    }
+                }
+                (None, '[') => {
+                    self.continues = Continues::Array;
+                    ret.push(' ');
+                    rest = &rest[1..];
+                }
+                (None, ']') => {
+                    self.continues = Continues::No;
+                    ret.push(' ');
+                    rest = &rest[1..];
+                }
+                (None, c) => {
+                    ret.push_str(&blank(&rest[..c.len_utf8()]));
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        return ret;
+    }
+
+    fn mask_toml(&mut self, line: &str) -> String {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.contains('=') {
+            self.in_package = trimmed == "[package]";
+            return blank(line);
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return blank(line);
+        };
+        if !self.in_package || !CARGO_KEYS.contains(&key.trim()) {
+            return blank(line);
+        }
+
+        let value_start = line.len() - value.len();
+        return blank(&line[..value_start]) + &self.keep_strings(value);
+    }
+
+    fn mask_json(&mut self, line: &str) -> String {
+        let depth = self.depth;
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in line.chars() {
+            match (in_string, escaped, c) {
+                (true, true, _) => escaped = false,
+                (true, false, '\\') => escaped = true,
+                (_, false, '"') => in_string = !in_string,
+                (false, _, '{') => self.depth += 1,
+                (false, _, '}') => self.depth = self.depth.saturating_sub(1),
+                _ => (),
+            }
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            return blank(line);
+        };
+        let key = key.trim().trim_matches('"');
+        if depth != 1 || !NPM_KEYS.contains(&key) {
+            return blank(line);
+        }
+
+        let value_start = line.len() - value.len();
+        let ret = blank(&line[..value_start]) + &self.keep_strings(value);
+        // JSON strings can't span lines
+        self.continues = Continues::No;
+
+        return ret;
+    }
+}
+
+/// Find the end of a string, skipping quotes escaped with a backslash in
+/// basic strings
+fn find_close(text: &str, quote: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote.starts_with('"') {
+            escaped = true;
+        } else if text[i..].starts_with(quote) {
+            return Some(i);
+        }
+    }
+
+    return None;
+}
+
+impl LineFilter for ManifestFilter {
+    fn mask(&mut self, line: &str) -> String {
+        if self.continues != Continues::No {
+            return self.keep_strings(line);
+        }
+
+        if self.json.is_none() && !line.trim().is_empty() {
+            self.json = Some(line.trim_start().starts_with('{'));
+        }
+
+        return match self.json {
+            Some(true) => self.mask_json(line),
+            _ => self.mask_toml(line),
+        };
+    }
+}
+
+#[test]
+fn test_cargo_manifest() {
+    use super::{mask_text, Filter};
+
+    let src = "[package]\n\
+        name = \"spel-chekr\"\n\
+        description = \"A simpel checker\"\n\
+        keywords = [\"spelling\", \"chekr\"]\n\
+        categories = [\n\
+        \x20   \"text-procesing\",\n\
+        ]\n\
+        \n\
+        [dependencies]\n\
+        description = \"not the package\"\n\
+        [package]\n\
+        description = \"\"\"\n\
+        Lnger text\n\
+        here\"\"\"";
+    let masked = mask_text(Filter::Manifest, src);
+    assert_eq!(masked.len(), src.len());
+    let words: Vec<&str> = masked.split_whitespace().collect();
+    assert_eq!(
+        words,
+        vec![
+            "A",
+            "simpel",
+            "checker",
+            "spelling",
+            "chekr",
+            "text-procesing",
+            "Lnger",
+            "text",
+            "here"
+        ]
+    );
+}
+
+#[test]
+fn test_npm_manifest() {
+    use super::{mask_text, Filter};
+
+    let src = "{\n\
+        \x20 \"name\": \"spel-chekr\",\n\
+        \x20 \"description\": \"A \\\"simpel\\\" checker\",\n\
+        \x20 \"repository\": { \"description\": \"nested\" }\n\
+        }";
+    let masked = mask_text(Filter::Manifest, src);
+    assert_eq!(masked.len(), src.len());
+    let words: Vec<&str> = masked
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    assert_eq!(words, vec!["A", "simpel", "checker"]);
+}
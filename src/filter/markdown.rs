@@ -0,0 +1,326 @@
+use super::{blank, decode_entities, mask_code_spans, mask_links, LineFilter};
+use serde::{Deserialize, Serialize};
+
+/// Which Markdown elements get checked, from the `[markdown]` section of the
+/// config file.  Everything is checked by default.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownOpts {
+    /// `# Headings`
+    pub headings: bool,
+    /// The "title" in `[text](url "title")`
+    pub link_titles: bool,
+    /// The alt text of images, the "alt" in `![alt](src)`
+    pub alt_text: bool,
+    /// `| table | rows |`
+    pub tables: bool,
+    /// `> quoted` lines
+    pub blockquotes: bool,
+    /// Footnote definitions, like `[^1]: The note`
+    pub footnotes: bool,
+}
+
+impl Default for MarkdownOpts {
+    fn default() -> Self {
+        return Self {
+            headings: true,
+            link_titles: true,
+            alt_text: true,
+            tables: true,
+            blockquotes: true,
+            footnotes: true,
+        };
+    }
+}
+
+/// Checks the prose in Markdown, skipping code blocks, inline code, HTML
+/// tags and comments, and the targets of links
+#[derive(Debug, Default)]
+pub struct MarkdownFilter {
+    opts: MarkdownOpts,
+    /// The ``` or ~~~ that opened the code block we're in
+    fence: Option<String>,
+    /// Inside an <!-- --> comment
+    in_comment: bool,
+    /// The last line was blank or indented code
+    after_break: bool,
+    /// Inside a footnote definition, which carries on over indented lines
+    in_footnote: bool,
+}
+
+/// Blank out HTML tags, like <br/> or <a href="...">, keeping what's
+/// between them
+fn mask_tags(text: &str) -> String {
+    let mut ret = text.to_string();
+    let mut i = 0;
+
+    while let Some(open) = text[i..].find('<').map(|o| o + i) {
+        let is_tag = text[open + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let close = text[open..].find('>').map(|c| c + open);
+        match close {
+            Some(close) if is_tag => {
+                ret.replace_range(open..=close, &blank(&text[open..=close]));
+                i = close + 1;
+            }
+            _ => i = open + 1,
+        }
+    }
+
+    return ret;
+}
+
+/// Blank out the alt text of images, the "alt" of ![alt](src)
+fn mask_alt_text(text: &str) -> String {
+    let mut ret = text.to_string();
+    let mut i = 0;
+
+    while let Some(open) = text[i..].find("![").map(|o| o + i) {
+        let Some(close) = text[open..].find(']').map(|c| c + open) else {
+            break;
+        };
+        ret.replace_range(open + 2..close, &blank(&text[open + 2..close]));
+        i = close + 1;
+    }
+
+    return ret;
+}
+
+/// Put back the "title"s of links that `mask_links` blanked, from the
+/// text before it was masked
+fn keep_link_titles(masked: &mut String, text: &str) {
+    let mut i = 0;
+
+    while let Some(open) = text[i..].find("](").map(|o| o + i + 2) {
+        let Some(close) = text[open..].find(')').map(|c| c + open) else {
+            break;
+        };
+        i = close;
+
+        let target = &text[open..close];
+        let Some(space) = target.find([' ', '\t']) else {
+            continue;
+        };
+        let title = target[space..].trim();
+        let quoted = ["\"", "'"]
+            .into_iter()
+            .any(|q| title.len() > 1 && title.starts_with(q) && title.ends_with(q));
+        if quoted {
+            let start = open + space + target[space..].find(title).unwrap() + 1;
+            let end = start + title.len() - 2;
+            masked.replace_range(start..end, &text[start..end]);
+        }
+    }
+}
+
+/// The length of the `[^label]:` at the start of a footnote definition
+fn footnote_label(trimmed: &str) -> Option<usize> {
+    if !trimmed.starts_with("[^") {
+        return None;
+    }
+
+    return trimmed.find("]:").map(|end| end + 2);
+}
+
+/// Whether a line is an ATX heading, like "## Usage"
+fn is_heading(trimmed: &str) -> bool {
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+
+    return (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t']));
+}
+
+impl MarkdownFilter {
+    pub fn new(opts: MarkdownOpts) -> Self {
+        return Self {
+            opts,
+            ..Default::default()
+        };
+    }
+
+    /// Blank out the <!-- --> comments on a line, which can span lines
+    fn mask_comments(&mut self, line: &str) -> String {
+        let mut ret = String::with_capacity(line.len());
+        let mut rest = line;
+
+        loop {
+            let marker = match self.in_comment {
+                true => "-->",
+                false => "<!--",
+            };
+            let Some(at) = rest.find(marker) else {
+                break;
+            };
+            let end = at + marker.len();
+            match self.in_comment {
+                true => ret.push_str(&blank(&rest[..end])),
+                false => {
+                    ret.push_str(&rest[..at]);
+                    ret.push_str(&blank(marker));
+                }
+            }
+            self.in_comment = !self.in_comment;
+            rest = &rest[end..];
+        }
+
+        match self.in_comment {
+            true => ret.push_str(&blank(rest)),
+            false => ret.push_str(rest),
+        }
+
+        return ret;
+    }
+
+    /// Mask the inline markup in a line of prose
+    fn mask_inline(&mut self, line: &str) -> String {
+        let mut text = mask_tags(&mask_code_spans(&self.mask_comments(line)));
+        if !self.opts.alt_text {
+            text = mask_alt_text(&text);
+        }
+
+        let mut masked = mask_links(&text, false);
+        if self.opts.link_titles {
+            keep_link_titles(&mut masked, &text);
+        }
+
+        return decode_entities(&masked);
+    }
+}
+
+impl LineFilter for MarkdownFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        let after_break = self.after_break;
+        self.after_break = trimmed.is_empty();
+
+        if let Some(fence) = &self.fence {
+            if trimmed.starts_with(fence.as_str()) {
+                self.fence = None;
+            }
+            return blank(line);
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let c = trimmed.chars().next().unwrap();
+            let len = trimmed.len() - trimmed.trim_start_matches(c).len();
+            self.fence = Some(trimmed[..len].to_string());
+            return blank(line);
+        }
+
+        if let Some(label) = footnote_label(trimmed) {
+            self.in_footnote = true;
+            if !self.opts.footnotes {
+                return blank(line);
+            }
+            let start = line.len() - trimmed.len() + label;
+            return blank(&line[..start]) + &self.mask_inline(&line[start..]);
+        }
+        if self.in_footnote && !trimmed.is_empty() {
+            self.in_footnote = trimmed.len() < line.len();
+            if self.in_footnote && !self.opts.footnotes {
+                return blank(line);
+            }
+        }
+
+        // Indented code blocks, which have to follow a break
+        if after_break && (line.starts_with("    ") || line.starts_with('\t')) && !self.in_footnote
+        {
+            self.after_break = true;
+            return blank(line);
+        }
+
+        // Link reference definitions, like "[docs]: https://..."
+        if trimmed.starts_with('[') && trimmed.contains("]:") {
+            return blank(line);
+        }
+
+        let skip = (trimmed.starts_with('>') && !self.opts.blockquotes)
+            || (is_heading(trimmed) && !self.opts.headings)
+            || (trimmed.starts_with('|') && !self.opts.tables);
+        if skip {
+            return blank(line);
+        }
+
+        return self.mask_inline(line);
+    }
+}
+
+#[cfg(test)]
+fn markdown_words(src: &str, opts: MarkdownOpts) -> Vec<String> {
+    let mut f = MarkdownFilter::new(opts);
+    let masked: Vec<String> = src.lines().map(|l| f.mask(l)).collect();
+    let masked = masked.join("\n");
+    assert_eq!(masked.len(), src.len());
+
+    return masked
+        .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '\0')
+        .map(|w| w.replace('\0', ""))
+        .filter(|w| !w.is_empty())
+        .collect();
+}
+
+#[test]
+fn test_markdown_filter() {
+    let src = "# Ttle\n\
+        \n\
+        Some <b>bld</b> text, see [the dcs](https://exmple.com).\n\
+        Caf&eacute; &amp; `inline_cod` don&rsquo;t\n\
+        <!-- a hiden\n\
+        comment --> after\n\
+        \n\
+        \x20   indented_cod here\n\
+        \n\
+        ~~~rust\n\
+        let x = chek();\n\
+        ~~~\n\
+        [dcs]: https://exmple.com";
+    assert_eq!(
+        markdown_words(src, MarkdownOpts::default()),
+        vec![
+            "Ttle",
+            "Some",
+            "bld",
+            "text",
+            "see",
+            "the",
+            "dcs",
+            "Caf\u{e9}",
+            "don't",
+            "after"
+        ]
+    );
+}
+
+#[test]
+fn test_markdown_elements() {
+    let src = "## Hedding\n\
+        See ![a dgram](x.png) and [this](https://x.io \"a titel\").\n\
+        > Quoed text\n\
+        | Cel | Other |\n\
+        Text[^1]\n\
+        \n\
+        [^1]: The nte\n\
+        \x20   carries on\n\
+        Back agin";
+    assert_eq!(
+        markdown_words(src, MarkdownOpts::default()),
+        vec![
+            "Hedding", "See", "a", "dgram", "and", "this", "a", "titel", "Quoed", "text", "Cel",
+            "Other", "Text", "1", "The", "nte", "carries", "on", "Back", "agin"
+        ]
+    );
+
+    let none = MarkdownOpts {
+        headings: false,
+        link_titles: false,
+        alt_text: false,
+        tables: false,
+        blockquotes: false,
+        footnotes: false,
+    };
+    assert_eq!(
+        markdown_words(src, none),
+        vec!["See", "and", "this", "Text", "1", "Back", "agin"]
+    );
+}
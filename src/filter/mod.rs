@@ -0,0 +1,268 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+mod bibtex;
+mod comments;
+mod email;
+mod entities;
+mod manifest;
+mod markdown;
+mod roff;
+mod rustdoc;
+mod shell;
+mod yaml;
+
+pub use comments::CommentsFilter;
+pub use entities::decode_entities;
+pub use markdown::MarkdownOpts;
+
+/// Picks out the parts of a line that should be checked, a line at a time
+/// so that it can keep track of where it is in the file.
+pub trait LineFilter {
+    /// Return the line (without its newline) with everything that
+    /// shouldn't be checked replaced by spaces.  The result must be the
+    /// same length in bytes, so that positions still line up with the
+    /// original.  NUL chars are filler that is dropped from inside words.
+    fn mask(&mut self, line: &str) -> String;
+
+    /// What the last line masked belongs to, like a BibTeX entry's key,
+    /// to show with the findings on it
+    fn scope(&self) -> Option<&str> {
+        return None;
+    }
+}
+
+/// Skips just the `inline code` in otherwise plain text, for
+/// --skip-code-spans
+#[derive(Debug, Default)]
+pub struct CodeSpanFilter;
+
+impl LineFilter for CodeSpanFilter {
+    fn mask(&mut self, line: &str) -> String {
+        return mask_code_spans(line);
+    }
+}
+
+/// The kinds of files that get filtered down to their prose before being
+/// checked
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Filter {
+    /// Check everything
+    Plain,
+    /// Email messages and mbox archives: only the body text, without the
+    /// headers, quoted lines or signature
+    Email,
+    /// roff/man page sources: only the prose, without the requests,
+    /// macros, escapes or examples
+    Roff,
+    /// BibTeX databases: only the title, abstract and note fields, with
+    /// findings labelled by the entry key
+    Bibtex,
+    /// Shell scripts, Dockerfiles, Makefiles and the like: only the `#`
+    /// comments
+    Shell,
+    /// Rust sources: only the doc comments, without code blocks, inline
+    /// code or intra-doc link targets
+    Rustdoc,
+    /// Package manifests (Cargo.toml and package.json): only the user
+    /// facing metadata, like the description and keywords
+    Manifest,
+    /// Markdown: only the prose, without code blocks, inline code, HTML
+    /// tags or link targets, and with HTML entities decoded
+    Markdown,
+    /// YAML: only the values and comments, without keys, anchors or tags,
+    /// with block scalars checked as prose
+    Yaml,
+}
+
+impl Filter {
+    /// Pick the filter for a file by its name, if it needs one
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        return match (ext.as_deref(), name) {
+            (Some("eml" | "mbox"), _) | (None, "mbox") => Some(Filter::Email),
+            (Some("man"), _) => Some(Filter::Roff),
+            (Some("bib"), _) => Some(Filter::Bibtex),
+            (Some("md" | "markdown" | "mdown" | "mkd"), _) => Some(Filter::Markdown),
+            (Some("yaml" | "yml"), _) => Some(Filter::Yaml),
+            (_, "Cargo.toml" | "package.json") => Some(Filter::Manifest),
+            (Some("sh" | "bash" | "zsh" | "ksh" | "fish" | "mk" | "dockerfile"), _) => {
+                Some(Filter::Shell)
+            }
+            (_, "Makefile" | "makefile" | "GNUmakefile") => Some(Filter::Shell),
+            (_, n) if n.starts_with("Dockerfile") || n.starts_with("Containerfile") => {
+                Some(Filter::Shell)
+            }
+            // Man page sections, like .1 or .3pm
+            (Some(e), _) if is_man_section(e) => Some(Filter::Roff),
+            _ => None,
+        };
+    }
+
+    /// Start filtering a new file
+    pub fn line_filter(&self, markdown: &MarkdownOpts) -> Option<Box<dyn LineFilter>> {
+        return match self {
+            Filter::Plain => None,
+            Filter::Email => Some(Box::new(email::EmailFilter::default())),
+            Filter::Roff => Some(Box::new(roff::RoffFilter::default())),
+            Filter::Bibtex => Some(Box::new(bibtex::BibtexFilter::default())),
+            Filter::Shell => Some(Box::new(shell::ShellFilter::default())),
+            Filter::Rustdoc => Some(Box::new(rustdoc::RustdocFilter::default())),
+            Filter::Manifest => Some(Box::new(manifest::ManifestFilter::default())),
+            Filter::Markdown => Some(Box::new(markdown::MarkdownFilter::new(*markdown))),
+            Filter::Yaml => Some(Box::new(yaml::YamlFilter::default())),
+        };
+    }
+}
+
+/// Whether a file extension is a man page section: 1-9, optionally
+/// followed by a suffix like "pm" or "ssl"
+fn is_man_section(ext: &str) -> bool {
+    let mut chars = ext.chars();
+    return chars.next().is_some_and(|c| ('1'..='9').contains(&c))
+        && chars.all(|c| c.is_ascii_alphabetic());
+}
+
+/// Blank out a whole line
+pub fn blank(line: &str) -> String {
+    return " ".repeat(line.len());
+}
+
+/// Blank out `inline code` spans, including ones with longer runs of
+/// backticks, like ``a ` in code``
+pub fn mask_code_spans(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find('`') {
+        let ticks = rest[open..].len() - rest[open..].trim_start_matches('`').len();
+        let fence = &rest[open..open + ticks];
+        let after = &rest[open + ticks..];
+
+        // An unclosed span is just literal backticks
+        let Some(close) = after.find(fence) else {
+            break;
+        };
+        let end = open + ticks + close + ticks;
+        ret.push_str(&rest[..open]);
+        ret.push_str(&blank(&rest[open..end]));
+        rest = &rest[end..];
+    }
+    ret.push_str(rest);
+
+    return ret;
+}
+
+/// Blank out the targets of Markdown links, keeping the link text: the
+/// url of [text](url) and the label of [text][label].  With `intra_doc`,
+/// shortcut links like [Foo] are rustdoc item paths and are blanked too.
+pub fn mask_links(text: &str, intra_doc: bool) -> String {
+    let mut ret = text.to_string();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while let Some(open) = text[i..].find('[').map(|o| o + i) {
+        let Some(close) = text[open..].find(']').map(|c| c + open) else {
+            break;
+        };
+
+        let target_end = match bytes.get(close + 1) {
+            Some(b'(') => text[close..].find(')').map(|e| e + close + 1),
+            Some(b'[') => text[close + 1..].find(']').map(|e| e + close + 2),
+            _ => None,
+        };
+
+        match target_end {
+            Some(end) => {
+                ret.replace_range(close + 1..end, &blank(&text[close + 1..end]));
+                i = end;
+            }
+            None => {
+                if intra_doc {
+                    ret.replace_range(open..close, &blank(&text[open..close]));
+                }
+                i = close;
+            }
+        }
+    }
+
+    return ret;
+}
+
+/// Run a filter over a whole text, for tests
+#[cfg(test)]
+pub fn mask_text(filter: Filter, text: &str) -> String {
+    let mut f = filter.line_filter(&MarkdownOpts::default()).unwrap();
+    let lines: Vec<String> = text.lines().map(|l| f.mask(l)).collect();
+
+    return lines.join("\n");
+}
+
+#[test]
+fn test_mask_code_spans() {
+    assert_eq!(mask_code_spans("a `b` c"), "a     c");
+    assert_eq!(mask_code_spans("a ``b ` c`` d"), "a           d");
+    assert_eq!(mask_code_spans("a `b c"), "a `b c");
+}
+
+#[test]
+fn test_mask_links() {
+    assert_eq!(
+        mask_links("[text](http://x.io) and", false),
+        "[text]              and"
+    );
+    assert_eq!(
+        mask_links("[text][label] [Foo]", false),
+        "[text]        [Foo]"
+    );
+    assert_eq!(
+        mask_links("see [Foo] or [`Bar`]", true),
+        "see     ] or       ]"
+    );
+}
+
+#[test]
+fn test_for_path() {
+    assert_eq!(Filter::for_path(Path::new("a/b.eml")), Some(Filter::Email));
+    assert_eq!(
+        Filter::for_path(Path::new("list.MBOX")),
+        Some(Filter::Email)
+    );
+    assert_eq!(Filter::for_path(Path::new("mbox")), Some(Filter::Email));
+    assert_eq!(Filter::for_path(Path::new("spel.1")), Some(Filter::Roff));
+    assert_eq!(Filter::for_path(Path::new("Foo.3pm")), Some(Filter::Roff));
+    assert_eq!(Filter::for_path(Path::new("a.10")), None);
+    assert_eq!(
+        Filter::for_path(Path::new("refs.bib")),
+        Some(Filter::Bibtex)
+    );
+    assert_eq!(Filter::for_path(Path::new("x/run.sh")), Some(Filter::Shell));
+    assert_eq!(Filter::for_path(Path::new("Makefile")), Some(Filter::Shell));
+    assert_eq!(
+        Filter::for_path(Path::new("Dockerfile.dev")),
+        Some(Filter::Shell)
+    );
+    assert_eq!(
+        Filter::for_path(Path::new("x/Cargo.toml")),
+        Some(Filter::Manifest)
+    );
+    assert_eq!(
+        Filter::for_path(Path::new("package.json")),
+        Some(Filter::Manifest)
+    );
+    assert_eq!(
+        Filter::for_path(Path::new("README.md")),
+        Some(Filter::Markdown)
+    );
+    assert_eq!(Filter::for_path(Path::new("ci.yml")), Some(Filter::Yaml));
+    assert_eq!(Filter::for_path(Path::new("a.rs")), None);
+    assert_eq!(Filter::for_path(Path::new("a.txt")), None);
+    assert_eq!(Filter::for_path(Path::new("README")), None);
+}
@@ -0,0 +1,133 @@
+use super::{blank, LineFilter};
+
+/// Macros whose arguments are prose, in man(7) and mdoc(7)
+const TEXT_MACROS: &[&str] = &[
+    "SH", "SS", "B", "I", "BI", "BR", "IB", "IR", "RB", "RI", "SM", "SB", "Nd", "Sh", "Ss", "Em",
+    "Sy", "Dq", "Qq", "Sq",
+];
+
+/// Checks the prose in roff/man page sources, skipping requests, macro
+/// names, comments, escapes and no-fill (example) blocks
+#[derive(Debug, Default)]
+pub struct RoffFilter {
+    /// Inside an .EX/.EE or .nf/.fi block
+    in_example: bool,
+}
+
+/// Mask the escapes (`\fB`, `\(em`, `\*[name]`, etc.) and comments in a
+/// line of text
+fn mask_escapes(line: &str) -> String {
+    let mut ret = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+
+        // Work out where the escape ends
+        let rest = &line[i + 1..];
+        let len = match rest.chars().next() {
+            // Comments run to the end of the line
+            Some('"' | '#') => rest.len(),
+            // \fB, \*x, \nx, \sN, or their (xx and [name] forms
+            Some(e @ ('f' | '*' | 'n' | 's' | 'F' | 'm' | 'g' | 'k' | 'Y')) => {
+                let arg = &rest[e.len_utf8()..];
+                let arg = arg.strip_prefix(['+', '-']).map_or(arg, |a| a);
+                let sign = rest.len() - e.len_utf8() - arg.len();
+                e.len_utf8() + sign + escape_arg_len(arg)
+            }
+            // \(xx special chars
+            Some('(') => {
+                1 + rest[1..]
+                    .chars()
+                    .take(2)
+                    .map(|c| c.len_utf8())
+                    .sum::<usize>()
+            }
+            // \[name] special chars
+            Some('[') => rest.find(']').map_or(rest.len(), |e| e + 1),
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+
+        ret.push_str(&blank(&line[i..i + 1 + len]));
+        while chars.peek().is_some_and(|(j, _)| *j < i + 1 + len) {
+            chars.next();
+        }
+    }
+
+    return ret;
+}
+
+/// The length of the argument to an escape: a single char, (xx or [name]
+fn escape_arg_len(arg: &str) -> usize {
+    return match arg.chars().next() {
+        Some('(') => {
+            1 + arg[1..]
+                .chars()
+                .take(2)
+                .map(|c| c.len_utf8())
+                .sum::<usize>()
+        }
+        Some('[') => arg.find(']').map_or(arg.len(), |e| e + 1),
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+}
+
+impl LineFilter for RoffFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let is_request = line.starts_with('.') || line.starts_with('\'');
+        if !is_request {
+            if self.in_example {
+                return blank(line);
+            }
+            return mask_escapes(line);
+        }
+
+        let body = line[1..].trim_start();
+        let name = body.split_whitespace().next().unwrap_or("");
+        match name {
+            "EX" | "nf" => self.in_example = true,
+            "EE" | "fi" => self.in_example = false,
+            _ => (),
+        }
+
+        if self.in_example || !TEXT_MACROS.contains(&name) {
+            return blank(line);
+        }
+
+        // Keep the arguments of the macros that take prose
+        let args_start = line.len() - body.len() + name.len();
+        return blank(&line[..args_start]) + &mask_escapes(&line[args_start..]);
+    }
+}
+
+#[test]
+fn test_roff_filter() {
+    use super::{mask_text, Filter};
+
+    let src = ".\\\" a coment\n\
+        .TH SPEL 1 \"2024\" \"spel 0.2\"\n\
+        .SH NAME\n\
+        spel \\- chek spelling\n\
+        .PP\n\
+        Use \\fBspel\\fR to \\(lqchek\\(rq words.\\\" trailng coment\n\
+        .B \\-\\-file\n\
+        .EX\n\
+        spel \\-f foo.txt\n\
+        .EE\n\
+        Wurks with \\*[product] and \\s-1SMALL\\s0 text.";
+    let masked = mask_text(Filter::Roff, src);
+    let words: Vec<&str> = masked.split_whitespace().collect();
+    assert_eq!(
+        words,
+        vec![
+            "NAME", "spel", "chek", "spelling", "Use", "spel", "to", "chek", "words.", "file",
+            "Wurks", "with", "and", "SMALL", "text."
+        ]
+    );
+    assert_eq!(masked.len(), src.len());
+}
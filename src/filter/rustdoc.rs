@@ -0,0 +1,136 @@
+use super::{blank, decode_entities, mask_code_spans, mask_links, LineFilter};
+
+/// Checks only the doc comments in Rust sources (`///`, `//!`, `/** */`
+/// and `#[doc = "..."]`), skipping code blocks, inline code and the
+/// targets of intra-doc links
+#[derive(Debug, Default)]
+pub struct RustdocFilter {
+    /// Inside a ``` code block
+    in_fence: bool,
+    /// Inside a /** or /*! block comment
+    in_block: bool,
+}
+
+/// Split a line into the doc comment marker and the doc text, if it's a
+/// line doc comment
+fn line_doc(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    for prefix in ["///", "//!"] {
+        if let Some(rest) = trimmed.strip_prefix(prefix) {
+            // //// is an ordinary comment
+            if rest.starts_with('/') {
+                return None;
+            }
+            return Some((indent + prefix.len(), rest));
+        }
+    }
+
+    // #[doc = "..."] and #![doc = "..."] on a single line
+    let attr = trimmed
+        .strip_prefix("#[doc")
+        .or_else(|| trimmed.strip_prefix("#![doc"))?;
+    let open = attr.find('"')?;
+    let close = attr.rfind('"').filter(|c| *c > open)?;
+    let start = line.len() - attr.len() + open + 1;
+
+    return Some((start, &line[start..start + close - open - 1]));
+}
+
+impl RustdocFilter {
+    /// Mask the doc text itself, tracking code fences
+    fn mask_doc(&mut self, text: &str) -> String {
+        if text.trim_start().starts_with("```") {
+            self.in_fence = !self.in_fence;
+            return blank(text);
+        }
+
+        // Link reference definitions, like "[Foo]: crate::Foo"
+        let trimmed = text.trim_start();
+        if self.in_fence || (trimmed.starts_with('[') && trimmed.contains("]:")) {
+            return blank(text);
+        }
+
+        return decode_entities(&mask_links(&mask_code_spans(text), true));
+    }
+}
+
+impl LineFilter for RustdocFilter {
+    fn mask(&mut self, line: &str) -> String {
+        if self.in_block {
+            let (text, rest) = match line.find("*/") {
+                Some(end) => {
+                    self.in_block = false;
+                    line.split_at(end)
+                }
+                None => (line, ""),
+            };
+            // Leading " * " decorations
+            let body = text.trim_start().trim_start_matches('*');
+            let prefix = text.len() - body.len();
+
+            return blank(&text[..prefix]) + &self.mask_doc(body) + &blank(rest);
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("/**") || trimmed.starts_with("/*!") {
+            if trimmed.starts_with("/**/") || trimmed.starts_with("/***") {
+                return blank(line);
+            }
+            let start = line.len() - trimmed.len() + 3;
+            let (text, rest) = match line[start..].find("*/") {
+                Some(end) => line[start..].split_at(end),
+                None => {
+                    self.in_block = true;
+                    (&line[start..], "")
+                }
+            };
+
+            return blank(&line[..start]) + &self.mask_doc(text) + &blank(rest);
+        }
+
+        return match line_doc(line) {
+            Some((start, text)) => {
+                let end = start + text.len();
+                blank(&line[..start]) + &self.mask_doc(text) + &blank(&line[end..])
+            }
+            None => blank(line),
+        };
+    }
+}
+
+#[test]
+fn test_rustdoc_filter() {
+    use super::{mask_text, Filter};
+
+    let src = "//! Crate levl docs\n\
+        use std::fs; // not a doc coment\n\
+        //// not one eithr\n\
+        /// Returns a [`Vec`] of [wurds](crate::Word), see [`Foo::bar`] and\n\
+        /// [the docs][ref].  Uses `inline_cod` too, caf&eacute;.\n\
+        ///\n\
+        /// ```\n\
+        /// let x = spel::chek();\n\
+        /// ```\n\
+        ///\n\
+        /// [ref]: https://example.com/pagee\n\
+        #[doc = \"Atribute docs\"]\n\
+        /**\n\
+        \x20* Blok docs\n\
+        \x20*/\n\
+        fn main() {}";
+    let masked = mask_text(Filter::Rustdoc, src);
+    assert_eq!(masked.len(), src.len());
+    let words: Vec<&str> = masked
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    assert_eq!(
+        words,
+        vec![
+            "Crate", "levl", "docs", "Returns", "a", "of", "wurds", "see", "and", "the", "docs",
+            "Uses", "too", "café", "Atribute", "docs", "Blok", "docs"
+        ]
+    );
+}
@@ -0,0 +1,113 @@
+use super::{blank, LineFilter};
+
+/// Checks just the `#` comments in shell scripts, Dockerfiles, Makefiles
+/// and the like, skipping the code and the bodies of heredocs
+#[derive(Debug, Default)]
+pub struct ShellFilter {
+    /// Heredoc delimiters still to be seen, and whether leading tabs are
+    /// stripped (<<-)
+    heredocs: Vec<(String, bool)>,
+    first_line_done: bool,
+}
+
+/// Parse the delimiter of a heredoc, from just after the <<
+fn heredoc_delim(rest: &str) -> Option<(String, bool)> {
+    let (rest, strip_tabs) = match rest.strip_prefix('-') {
+        Some(r) => (r, true),
+        None => (rest, false),
+    };
+    let rest = rest.trim_start();
+    let rest = rest.trim_start_matches(['\'', '"', '\\']);
+    let delim: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect();
+
+    return Some((delim, strip_tabs)).filter(|(d, _)| !d.is_empty());
+}
+
+impl ShellFilter {
+    /// Find where the comment on a line starts, if there is one, noting
+    /// any heredocs that start on it
+    fn comment_start(&mut self, line: &str) -> Option<usize> {
+        let mut quote = None;
+        let mut prev = ' ';
+        let mut escaped = false;
+
+        for (i, c) in line.char_indices() {
+            if escaped {
+                escaped = false;
+                prev = c;
+                continue;
+            }
+
+            match (quote, c) {
+                (Some('\''), '\'') => quote = None,
+                (Some('\''), _) => (),
+                (_, '\\') => escaped = true,
+                (Some('"'), '"') => quote = None,
+                (Some(_), _) => (),
+                (None, '\'' | '"') => quote = Some(c),
+                (None, '#') if prev.is_whitespace() => return Some(i),
+                (None, '<')
+                    if prev != '<'
+                        && line[i..].starts_with("<<")
+                        && !line[i..].starts_with("<<<") =>
+                {
+                    self.heredocs.extend(heredoc_delim(&line[i + 2..]));
+                }
+                _ => (),
+            }
+            prev = c;
+        }
+
+        return None;
+    }
+}
+
+impl LineFilter for ShellFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let first = !std::mem::replace(&mut self.first_line_done, true);
+        if first && line.starts_with("#!") {
+            return blank(line);
+        }
+
+        if let Some((delim, strip_tabs)) = self.heredocs.first() {
+            let l = if *strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if l == delim {
+                self.heredocs.remove(0);
+            }
+            return blank(line);
+        }
+
+        return match self.comment_start(line) {
+            Some(i) => blank(&line[..i + 1]) + &line[i + 1..],
+            None => blank(line),
+        };
+    }
+}
+
+#[test]
+fn test_shell_filter() {
+    use super::{mask_text, Filter};
+
+    let src = "#!/bin/sh\n\
+        # Instal the thing\n\
+        echo \"not # a coment\" # but this is\n\
+        x=${#arr} 'also # not'\n\
+        cat <<-EOF > out # writting out\n\
+        \t# not a coment either\n\
+        \tEOF\n\
+        RUN make  # buildd it";
+    let masked = mask_text(Filter::Shell, src);
+    assert_eq!(masked.len(), src.len());
+    let words: Vec<&str> = masked.split_whitespace().collect();
+    assert_eq!(
+        words,
+        vec!["Instal", "the", "thing", "but", "this", "is", "writting", "out", "buildd", "it"]
+    );
+}
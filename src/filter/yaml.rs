@@ -0,0 +1,170 @@
+use super::{blank, LineFilter};
+
+/// Checks the values and comments in YAML, skipping keys, anchors, aliases
+/// and tags.  The lines of a folded (>) or literal (|) block scalar are
+/// checked as the prose they are, so that colons and #s in them aren't
+/// taken for keys and comments.
+#[derive(Debug, Default)]
+pub struct YamlFilter {
+    /// The indent of the line that started the block scalar we're in.  The
+    /// block carries on over the lines that are indented further.
+    block: Option<usize>,
+}
+
+/// Find where a key ends, at a ": " or a trailing ":" outside quotes
+fn key_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut prev = ' ';
+
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (None, '#') if prev == ' ' => return None,
+            (None, ':') => {
+                let next = text[i + 1..].chars().next();
+                if next.is_none_or(|n| n == ' ' || n == '\t') {
+                    return Some(i + 1);
+                }
+            }
+            _ => (),
+        }
+        prev = c;
+    }
+
+    return None;
+}
+
+/// Find where the comment in a value starts, outside quotes
+fn comment_start(value: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut prev = ' ';
+
+    for (i, c) in value.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') => (),
+            (Some(q), _) if c == q && prev != '\\' => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') if prev == ' ' => quote = Some(c),
+            (None, '#') if prev == ' ' || prev == '\t' => return Some(i),
+            _ => (),
+        }
+        prev = c;
+    }
+
+    return None;
+}
+
+/// Whether a value starts a block scalar: | or >, with optional chomping
+/// and indentation indicators like |- or >2
+fn is_block_indicator(value: &str) -> bool {
+    let mut chars = value.chars();
+    return matches!(chars.next(), Some('|' | '>'))
+        && chars.all(|c| c == '+' || c == '-' || c.is_ascii_digit());
+}
+
+/// Blank out the anchors (&a), aliases (*a) and tags (!!str) in a value
+fn mask_properties(value: &str) -> String {
+    // They only come before the value itself
+    let mut end = 0;
+    for word in value.split(' ') {
+        let is_property = word.len() > 1 && word.starts_with(['&', '*', '!']);
+        if !word.is_empty() && !is_property {
+            break;
+        }
+        end += word.len() + 1;
+    }
+    let end = end.min(value.len());
+
+    return blank(&value[..end]) + &value[end..];
+}
+
+impl YamlFilter {
+    /// Mask a value, with any comment after it, starting any block scalar
+    fn mask_value(&mut self, value: &str, indent: usize) -> String {
+        let (body, comment) = value.split_at(comment_start(value).unwrap_or(value.len()));
+        // Keep the comment's text, without the #
+        let comment = comment.replacen('#', " ", 1);
+        let body = mask_properties(body);
+
+        if is_block_indicator(body.trim()) {
+            self.block = Some(indent);
+            return blank(&body) + &comment;
+        }
+
+        return body + &comment;
+    }
+}
+
+impl LineFilter for YamlFilter {
+    fn mask(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some(parent) = self.block {
+            if trimmed.is_empty() || indent > parent {
+                return line.to_string();
+            }
+            self.block = None;
+        }
+
+        if trimmed.starts_with('%') || trimmed.starts_with("---") || trimmed.starts_with("...") {
+            return blank(line);
+        }
+
+        // Sequence entries, like "- a" or "- - a"
+        let mut start = indent;
+        while line[start..].starts_with("- ") || &line[start..] == "-" {
+            start += 1;
+            start += line[start..].len() - line[start..].trim_start().len();
+        }
+
+        let rest = &line[start..];
+        let (key, value) = match key_end(rest) {
+            Some(end) => rest.split_at(end),
+            None => ("", rest),
+        };
+
+        return blank(&line[..start]) + &blank(key) + &self.mask_value(value, indent);
+    }
+}
+
+#[test]
+fn test_yaml_filter() {
+    use super::{mask_text, Filter};
+
+    let src = "%YAML 1.2\n\
+        ---\n\
+        # A coment\n\
+        name: Spel chekr\n\
+        base: &base\n\
+        \x20 enabled: true\n\
+        other: *base\n\
+        title: \"Quoted, with a # in it\" # trailing remrk\n\
+        tagged: !!str sumthing\n\
+        folded: >-\n\
+        \x20 Some folded text: with a colon\n\
+        \x20 and a # that's not a coment\n\
+        \n\
+        \x20 after a gap\n\
+        items:\n\
+        \x20 - frist\n\
+        \x20 - key: |2\n\
+        \x20     litteral\n\
+        next_key: done";
+    let masked = mask_text(Filter::Yaml, src);
+    assert_eq!(masked.len(), src.len());
+    let words: Vec<&str> = masked
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .collect();
+    assert_eq!(
+        words,
+        vec![
+            "A", "coment", "Spel", "chekr", "true", "Quoted", "with", "a", "in", "it", "trailing",
+            "remrk", "sumthing", "Some", "folded", "text", "with", "a", "colon", "and", "a",
+            "that's", "not", "a", "coment", "after", "a", "gap", "frist", "litteral", "done"
+        ]
+    );
+}
@@ -0,0 +1,749 @@
+use crate::diagnostic::{Diagnostic, Kind};
+use crate::journal::{Journal, Replacement};
+use crate::output::{annotate, OutputOpts};
+use crate::typos::common_typo;
+use crate::util::{
+    load_file, match_case, parse_path, tokenize_spans, write_atomic, SkippedFile, SuggestOpts,
+    Suggester,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    io::{BufRead, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Misspellings that should always be corrected to the same word, stored
+/// as "wrong=right" lines in a file
+#[derive(Debug, Default)]
+pub struct Corrections {
+    path: PathBuf,
+    map: BTreeMap<String, String>,
+}
+
+impl Corrections {
+    /// Load the corrections file.  A missing file is just an empty map
+    pub fn load(fpath: &Path) -> Result<Self> {
+        let path = parse_path(fpath);
+        let mut map = BTreeMap::new();
+
+        if path.exists() {
+            for line in fs::read_to_string(&path)?.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                match line.split_once('=') {
+                    Some((wrong, right)) => {
+                        map.insert(wrong.trim().to_lowercase(), right.trim().to_string());
+                    }
+                    None => warn!("Ignoring bad corrections line: {}", line),
+                }
+            }
+        }
+
+        return Ok(Self { path, map });
+    }
+
+    pub fn get(&self, word: &str) -> Option<&str> {
+        return self.map.get(word).map(|s| s.as_str());
+    }
+
+    /// Add a correction and write the whole map back out
+    pub fn save(&mut self, wrong: &str, right: &str) -> Result<()> {
+        self.map.insert(wrong.to_string(), right.to_string());
+
+        let mut content = String::new();
+        for (wrong, right) in &self.map {
+            content.push_str(&format!("{}={}\n", wrong, right));
+        }
+
+        fs::write(&self.path, content)?;
+
+        return Ok(());
+    }
+}
+
+/// The progress and decisions of a fix session, saved so that a long
+/// review can be picked up again with --resume
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Files that have been completely reviewed
+    pub done: BTreeSet<PathBuf>,
+    /// Byte offsets of the occurrences that were skipped in files that
+    /// were only partly reviewed, in the text as it was written out
+    pub skipped: BTreeMap<PathBuf, BTreeSet<usize>>,
+    /// Word -> replacement for the rest of the session
+    pub replace_all: BTreeMap<String, String>,
+    /// Words to leave alone for the rest of the session
+    pub ignored: BTreeSet<String>,
+}
+
+impl SessionState {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("no session to resume in \"{}\"", path.display()))?;
+
+        return Ok(serde_json::from_str(&content)?);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        return Ok(());
+    }
+}
+
+/// What to do with a single finding, as entered at the prompt
+#[derive(Debug, PartialEq)]
+enum Action {
+    /// Replace just this occurrence
+    Replace(String),
+    /// Replace this and every later occurrence in this session
+    ReplaceAll(String),
+    /// Replace this and save it to the corrections map
+    Always(String),
+    /// Leave every occurrence of the word alone for this session
+    Ignore,
+    Skip,
+    Quit,
+}
+
+const HELP: &str = "  [N] use suggestion N, [r WORD] replace with WORD, \
+    [a N|WORD] replace all this session, [c N|WORD] always correct, \
+    [i] ignore this session, [s] skip, [q] quit";
+
+/// Parse a command from the prompt.  The replacement for "a" and "c" can
+/// be either a suggestion number or a word
+fn parse_action(input: &str, suggestions: &[String]) -> Option<Action> {
+    let input = input.trim();
+    let pick = |arg: &str| -> Option<String> {
+        let arg = arg.trim();
+        return match arg.parse::<usize>() {
+            Ok(n) => suggestions.get(n.checked_sub(1)?).cloned(),
+            Err(_) if !arg.is_empty() => Some(arg.to_string()),
+            Err(_) => None,
+        };
+    };
+
+    if input.chars().all(|c| c.is_ascii_digit()) && !input.is_empty() {
+        return pick(input).map(Action::Replace);
+    }
+
+    let (cmd, arg) = input.split_at(input.chars().next().map_or(0, |c| c.len_utf8()));
+
+    return match cmd {
+        "" | "s" => Some(Action::Skip),
+        "i" => Some(Action::Ignore),
+        "q" => Some(Action::Quit),
+        "r" => Some(arg.trim())
+            .filter(|w| !w.is_empty())
+            .map(|w| Action::Replace(w.to_string())),
+        "a" => pick(arg).map(Action::ReplaceAll),
+        "c" => pick(arg).map(Action::Always),
+        _ => None,
+    };
+}
+
+/// Walks through the findings in each file, prompting for what to do with
+/// each one and remembering the choices that apply to the whole session
+pub struct Fixer<'a, R: BufRead, W: Write> {
+    /// Finds the suggestions to offer
+    suggester: Suggester<'a>,
+    corrections: Corrections,
+    pub state: SessionState,
+    /// Set once the user quits, after which nothing more is prompted for
+    pub quit: bool,
+    /// The number of replacements made so far
+    pub fixed: usize,
+    out_opts: OutputOpts,
+    input: R,
+    output: W,
+}
+
+impl<'a, R: BufRead, W: Write> Fixer<'a, R, W> {
+    pub fn new(
+        words: &'a [&'a str],
+        suggest: SuggestOpts,
+        corrections: Corrections,
+        state: SessionState,
+        out_opts: OutputOpts,
+        input: R,
+        output: W,
+    ) -> Self {
+        return Self {
+            suggester: Suggester::new(words, suggest),
+            corrections,
+            state,
+            quit: false,
+            fixed: 0,
+            out_opts,
+            input,
+            output,
+        };
+    }
+
+    /// The replacement to use for a word without asking, if any
+    fn remembered(&self, word: &str) -> Option<String> {
+        return self
+            .state
+            .replace_all
+            .get(word)
+            .cloned()
+            .or_else(|| self.corrections.get(word).map(|s| s.to_string()));
+    }
+
+    /// Ask what to do with a finding, until we get a valid answer
+    fn prompt(&mut self, text: &str, d: &Diagnostic) -> Result<Action> {
+        let suggestions = if d.suggestions.is_empty() {
+            self.suggester
+                .find(&d.word)
+                .into_iter()
+                .map(|(_, w)| w.to_string())
+                .collect()
+        } else {
+            d.suggestions.clone()
+        };
+
+        writeln!(self.output, "{}", annotate(d, text, &self.out_opts))?;
+        let list: Vec<String> = suggestions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| format!("{}) {}", i + 1, s))
+            .collect();
+        writeln!(self.output, "  {}", list.join("  "))?;
+
+        loop {
+            write!(self.output, "> ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                // Treat EOF like quitting
+                return Ok(Action::Quit);
+            }
+
+            match parse_action(&line, &suggestions) {
+                Some(action) => return Ok(action),
+                None => writeln!(self.output, "{}", HELP)?,
+            }
+        }
+    }
+
+    /// Go through the findings for a file, returning the fixed text and
+    /// the replacements made, if any.  Files already done in this session
+    /// are skipped.
+    pub fn fix(
+        &mut self,
+        path: &Path,
+        text: &str,
+        findings: Vec<Diagnostic>,
+    ) -> Result<Option<(String, Vec<Replacement>)>> {
+        if self.quit || self.state.done.contains(path) {
+            return Ok(None);
+        }
+
+        let prev_skipped = self.state.skipped.remove(path).unwrap_or_default();
+        let mut skipped = BTreeSet::new();
+        let mut edits: Vec<(Range<usize>, String)> = vec![];
+        // How far the text has shifted from the replacements so far
+        let mut delta = 0_isize;
+
+        for d in findings {
+            let start = d.span.bytes.start;
+            if d.kind == Kind::Note || self.state.ignored.contains(&d.word) {
+                continue;
+            }
+
+            if prev_skipped.contains(&start) {
+                skipped.insert(start.saturating_add_signed(delta));
+                continue;
+            }
+
+            let repl = match self.remembered(&d.word) {
+                Some(repl) => repl,
+                None => match self.prompt(text, &d)? {
+                    Action::Replace(repl) => repl,
+                    Action::ReplaceAll(repl) => {
+                        self.state.replace_all.insert(d.word.clone(), repl.clone());
+                        repl
+                    }
+                    Action::Always(repl) => {
+                        self.corrections.save(&d.word, &repl)?;
+                        repl
+                    }
+                    Action::Ignore => {
+                        self.state.ignored.insert(d.word.clone());
+                        continue;
+                    }
+                    Action::Skip => {
+                        skipped.insert(start.saturating_add_signed(delta));
+                        continue;
+                    }
+                    Action::Quit => {
+                        self.quit = true;
+                        break;
+                    }
+                },
+            };
+
+            let repl = match_case(&text[d.span.bytes.clone()], &repl);
+            delta += repl.len() as isize - d.span.bytes.len() as isize;
+            edits.push((d.span.bytes.clone(), repl));
+        }
+
+        if self.quit {
+            // Remember what was skipped so it isn't asked about again when
+            // the session is resumed
+            if !skipped.is_empty() {
+                self.state.skipped.insert(path.to_owned(), skipped);
+            }
+        } else {
+            self.state.done.insert(path.to_owned());
+        }
+
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        self.fixed += edits.len();
+
+        return Ok(Some((
+            apply_edits(text, &edits),
+            journal_entries(path, text, &edits),
+        )));
+    }
+}
+
+/// Apply the replacements, which must be in order and not overlap
+pub fn apply_edits(text: &str, edits: &[(Range<usize>, String)]) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, repl) in edits {
+        ret.push_str(&text[last..range.start]);
+        ret.push_str(repl);
+        last = range.end;
+    }
+    ret.push_str(&text[last..]);
+
+    return ret;
+}
+
+/// Fixes findings without prompting, but only when it's confident in the
+/// replacement, leaving the rest to be reported
+pub struct AutoFixer<'a> {
+    suggester: Suggester<'a>,
+    corrections: Corrections,
+    /// Only apply a suggestion with a similarity score above this
+    threshold: f32,
+    /// Only apply corrections from the common typo table or the
+    /// corrections map
+    typos_only: bool,
+    /// The replacement picked for each word so far, if any
+    cache: HashMap<String, Option<String>>,
+}
+
+impl<'a> AutoFixer<'a> {
+    pub fn new(
+        words: &'a [&'a str],
+        suggest: SuggestOpts,
+        corrections: Corrections,
+        threshold: f32,
+        typos_only: bool,
+    ) -> Self {
+        return Self {
+            suggester: Suggester::new(words, suggest),
+            corrections,
+            threshold,
+            typos_only,
+            cache: HashMap::new(),
+        };
+    }
+
+    /// Pick the replacement for a finding, if we're sure enough of it
+    fn replacement(&mut self, d: &Diagnostic) -> Option<String> {
+        if let Some(repl) = self
+            .corrections
+            .get(&d.word)
+            .or_else(|| common_typo(&d.word))
+        {
+            return Some(repl.to_string());
+        }
+
+        if self.typos_only {
+            return None;
+        }
+
+        return match d.kind {
+            // These are exact matches once the accents are folded or the
+            // word is transliterated
+            Kind::Variant | Kind::Misspelling if !d.suggestions.is_empty() => {
+                d.suggestions.first().cloned()
+            }
+            Kind::Misspelling => {
+                let suggester = &self.suggester;
+                let threshold = self.threshold;
+                self.cache
+                    .entry(d.word.clone())
+                    .or_insert_with(|| {
+                        suggester
+                            .find_n(&d.word, 1)
+                            .first()
+                            .filter(|(score, _)| *score > threshold)
+                            .map(|(_, w)| w.to_string())
+                    })
+                    .clone()
+            }
+            _ => None,
+        };
+    }
+
+    /// Work out the edits for a file, returning them along with the
+    /// findings that were left alone
+    pub fn fix(
+        &mut self,
+        text: &str,
+        findings: Vec<Diagnostic>,
+    ) -> (Vec<(Range<usize>, String)>, Vec<Diagnostic>) {
+        let mut edits = vec![];
+        let mut unfixed = vec![];
+        for d in findings {
+            if d.kind == Kind::Note {
+                continue;
+            }
+
+            match self.replacement(&d) {
+                Some(repl) => {
+                    let repl = match_case(&text[d.span.bytes.clone()], &repl);
+                    edits.push((d.span.bytes.clone(), repl));
+                }
+                None => unfixed.push(d),
+            }
+        }
+
+        return (edits, unfixed);
+    }
+}
+
+/// The journal entries for the edits to a file, with the offsets of where
+/// each replacement ends up in the new text
+pub fn journal_entries(
+    path: &Path,
+    text: &str,
+    edits: &[(Range<usize>, String)],
+) -> Vec<Replacement> {
+    let mut ret = vec![];
+    let mut delta = 0_isize;
+    for (range, repl) in edits {
+        ret.push(Replacement {
+            path: path.to_owned(),
+            start: range.start.saturating_add_signed(delta),
+            before: text[range.clone()].to_string(),
+            after: repl.clone(),
+        });
+        delta += repl.len() as isize - range.len() as isize;
+    }
+
+    return ret;
+}
+
+/// Find the named misspellings in the text, as whole words, along with
+/// the replacement for each, which follows the case of the original
+pub fn fix_words(text: &str, pairs: &HashMap<String, String>) -> Vec<(Range<usize>, String)> {
+    let mut ret = vec![];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        for (range, word) in tokenize_spans(line) {
+            if let Some(repl) = pairs.get(&word) {
+                let orig = &line[range.clone()];
+                ret.push((
+                    offset + range.start..offset + range.end,
+                    match_case(orig, repl),
+                ));
+            }
+        }
+        offset += line.len();
+    }
+
+    return ret;
+}
+
+/// Replace just the given misspellings across the files, without asking.
+/// Returns the number of words fixed, the number of files changed and the
+/// files that were skipped.
+pub fn fix_named_words(
+    files: &[PathBuf],
+    pairs: &HashMap<String, String>,
+    journal: &mut Journal,
+    max_size: u64,
+) -> (usize, usize, Vec<SkippedFile>) {
+    let mut fixed = 0;
+    let mut changed = 0;
+    let mut skipped = vec![];
+
+    for fpath in files {
+        let text = match load_file(fpath, max_size) {
+            Ok(text) => text,
+            Err(reason) => {
+                skipped.push(SkippedFile {
+                    path: fpath.to_owned(),
+                    reason,
+                });
+                continue;
+            }
+        };
+
+        let edits = fix_words(&text, pairs);
+        if edits.is_empty() {
+            continue;
+        }
+
+        if let Err(e) = write_atomic(fpath, &apply_edits(&text, &edits)) {
+            error!("Failed to write \"{}\": {}", fpath.display(), e);
+            continue;
+        }
+        if let Err(e) = journal.append(&journal_entries(fpath, &text, &edits)) {
+            warn!("Failed to write to the journal: {}", e);
+        }
+
+        fixed += edits.len();
+        changed += 1;
+    }
+
+    return (fixed, changed, skipped);
+}
+
+#[test]
+fn test_fix_words() {
+    let pairs = HashMap::from([
+        ("recieve".to_string(), "receive".to_string()),
+        ("teh".to_string(), "the".to_string()),
+    ]);
+    let text = "Recieve teh mail.\nTEH recieved, RECIEVE, tehran\n";
+
+    let edits = fix_words(text, &pairs);
+    assert_eq!(
+        apply_edits(text, &edits),
+        "Receive the mail.\nTHE recieved, RECEIVE, tehran\n"
+    );
+
+    let journal = journal_entries(Path::new("a.txt"), text, &edits);
+    assert_eq!(journal[2].start, 18);
+    assert_eq!(journal[2].before, "TEH");
+}
+
+#[test]
+fn test_auto_fixer() {
+    use crate::diagnostic::Span;
+
+    let text = "Recieve a lettr from the cafe\n";
+    let finding = |word: &str, kind: Kind| -> Diagnostic {
+        let start = text.to_lowercase().find(word).unwrap();
+        let span = Span::new(start..start + word.len(), 0..0, 0..0, 0, 0);
+        return Diagnostic::new(PathBuf::from("a.txt"), 1, span, word, kind);
+    };
+    let findings = vec![
+        finding("recieve", Kind::Misspelling),
+        finding("lettr", Kind::Misspelling),
+        finding("cafe", Kind::Variant).with_suggestions(vec!["café".to_string()]),
+    ];
+    let words = vec!["letter", "latter"];
+
+    // "lettr" vs. "letter" scores 0.909
+    let mut fixer = AutoFixer::new(
+        &words,
+        SuggestOpts::new(1),
+        Corrections::default(),
+        0.9,
+        false,
+    );
+    let (edits, unfixed) = fixer.fix(text, findings.clone());
+    assert_eq!(
+        apply_edits(text, &edits),
+        "Receive a letter from the café\n"
+    );
+    assert!(unfixed.is_empty());
+
+    let mut fixer = AutoFixer::new(
+        &words,
+        SuggestOpts::new(1),
+        Corrections::default(),
+        0.95,
+        false,
+    );
+    let (edits, unfixed) = fixer.fix(text, findings.clone());
+    assert_eq!(apply_edits(text, &edits), "Receive a lettr from the café\n");
+    assert_eq!(unfixed[0].word, "lettr");
+
+    // Only the common typo table
+    let mut fixer = AutoFixer::new(
+        &words,
+        SuggestOpts::new(1),
+        Corrections::default(),
+        0.5,
+        true,
+    );
+    let (edits, unfixed) = fixer.fix(text, findings);
+    assert_eq!(apply_edits(text, &edits), "Receive a lettr from the cafe\n");
+    assert_eq!(unfixed.len(), 2);
+}
+
+#[test]
+fn test_parse_action() {
+    let sugg = vec!["receive".to_string(), "relieve".to_string()];
+
+    assert_eq!(
+        parse_action("1\n", &sugg),
+        Some(Action::Replace("receive".to_string()))
+    );
+    assert_eq!(parse_action("3", &sugg), None);
+    assert_eq!(parse_action("0", &sugg), None);
+    assert_eq!(
+        parse_action("r deceive", &sugg),
+        Some(Action::Replace("deceive".to_string()))
+    );
+    assert_eq!(
+        parse_action("a2", &sugg),
+        Some(Action::ReplaceAll("relieve".to_string()))
+    );
+    assert_eq!(
+        parse_action("c receive", &sugg),
+        Some(Action::Always("receive".to_string()))
+    );
+    assert_eq!(parse_action("a", &sugg), None);
+    assert_eq!(parse_action("\n", &sugg), Some(Action::Skip));
+    assert_eq!(parse_action("i", &sugg), Some(Action::Ignore));
+    assert_eq!(parse_action("q", &sugg), Some(Action::Quit));
+    assert_eq!(parse_action("x", &sugg), None);
+}
+
+#[test]
+fn test_fixer() {
+    use crate::diagnostic::Span;
+    use std::io::Cursor;
+
+    let text = "Recieve teh recieve, teh end.  Recieve\n";
+    let find = |word: &str| -> Vec<Diagnostic> {
+        return text
+            .match_indices(word)
+            .map(|(i, _)| {
+                let span = Span::new(i..i + word.len(), 0..0, 0..0, 0, 0);
+                Diagnostic::new(
+                    PathBuf::from("a.txt"),
+                    1,
+                    span,
+                    &word.to_lowercase(),
+                    Kind::Misspelling,
+                )
+                .with_suggestions(vec!["receive".to_string(), "the".to_string()])
+            })
+            .collect();
+    };
+    let mut findings = find("Recieve");
+    findings.extend(find("recieve"));
+    findings.extend(find("teh"));
+    findings.sort_by_key(|d| d.span.bytes.start);
+
+    let dir = tempfile::tempdir().unwrap();
+    let corr_path = dir.path().join("corrections");
+    let corrections = Corrections::load(&corr_path).unwrap();
+    let words = vec![];
+    let input = Cursor::new("a1\nc 2\n");
+    let mut out = vec![];
+    let mut fixer = Fixer::new(
+        &words,
+        SuggestOpts::new(5),
+        corrections,
+        SessionState::default(),
+        OutputOpts::default(),
+        input,
+        &mut out,
+    );
+
+    let (fixed, journal) = fixer
+        .fix(Path::new("a.txt"), text, findings)
+        .unwrap()
+        .unwrap();
+    assert_eq!(fixed, "Receive the receive, the end.  Receive\n");
+    assert_eq!(journal[1].start, 8);
+    assert_eq!(journal[1].before, "teh");
+    assert_eq!(journal[1].after, "the");
+    assert_eq!(&fixed[journal[4].start..], "Receive\n");
+    assert_eq!(fixer.fixed, 5);
+    assert!(!fixer.quit);
+    assert!(fixer.state.done.contains(Path::new("a.txt")));
+    assert_eq!(fs::read_to_string(&corr_path).unwrap(), "teh=the\n");
+
+    // The saved correction is applied without asking in a later session
+    let corrections = Corrections::load(&corr_path).unwrap();
+    assert_eq!(corrections.get("teh"), Some("the"));
+}
+
+#[test]
+fn test_fixer_resume() {
+    use crate::diagnostic::Span;
+    use std::io::Cursor;
+
+    let path = Path::new("a.txt");
+    let find = |text: &str| -> Vec<Diagnostic> {
+        let mut ret = vec![];
+        for word in ["teh", "recieve"] {
+            for (i, _) in text.match_indices(word) {
+                let span = Span::new(i..i + word.len(), 0..0, 0..0, 0, 0);
+                ret.push(
+                    Diagnostic::new(path.to_owned(), 1, span, word, Kind::Misspelling)
+                        .with_suggestions(vec!["the".to_string()]),
+                );
+            }
+        }
+        ret.sort_by_key(|d| d.span.bytes.start);
+
+        return ret;
+    };
+    let words = vec![];
+    let mut out = vec![];
+
+    // Fix the first, skip the second and quit at the third
+    let text = "teh recieve teh\n";
+    let mut fixer = Fixer::new(
+        &words,
+        SuggestOpts::new(5),
+        Corrections::default(),
+        SessionState::default(),
+        OutputOpts::default(),
+        Cursor::new("1\ns\nq\n"),
+        &mut out,
+    );
+    let (text, _) = fixer.fix(path, text, find(text)).unwrap().unwrap();
+    assert_eq!(text, "the recieve teh\n");
+    assert!(fixer.quit);
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = dir.path().join("session.json");
+    fixer.state.save(&session).unwrap();
+    let state = SessionState::load(&session).unwrap();
+    assert!(state.done.is_empty());
+    assert_eq!(state.skipped[path], BTreeSet::from([4]));
+
+    // Resuming doesn't ask about the skipped word again
+    let mut fixer = Fixer::new(
+        &words,
+        SuggestOpts::new(5),
+        Corrections::default(),
+        state,
+        OutputOpts::default(),
+        Cursor::new("1\n"),
+        &mut out,
+    );
+    let (text, _) = fixer.fix(path, &text, find(&text)).unwrap().unwrap();
+    assert_eq!(text, "the recieve the\n");
+    assert!(!fixer.quit);
+    assert!(fixer.state.skipped.is_empty());
+
+    // And a finished file isn't looked at again
+    assert_eq!(fixer.fix(path, &text, find(&text)).unwrap(), None);
+}
@@ -0,0 +1,50 @@
+use anyhow::{bail, Result};
+use std::process::Command;
+
+/// Separates the commits in the `git log` output
+const RECORD_SEP: char = '\x1e';
+
+/// Get the (abbreviated sha, message) of each commit in the revision
+/// range, e.g. "v1.0..HEAD", newest first
+pub fn commit_messages(range: &str) -> Result<Vec<(String, String)>> {
+    let output = Command::new("git")
+        .args(["log", "--format=%h%x00%B%x1e", range, "--"])
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    return Ok(parse_log(&String::from_utf8_lossy(&output.stdout)));
+}
+
+/// Split the output of `git log --format=%h%x00%B%x1e`
+fn parse_log(log: &str) -> Vec<(String, String)> {
+    let mut ret = vec![];
+    for record in log.split(RECORD_SEP) {
+        let record = record.trim_start_matches('\n');
+        if let Some((sha, msg)) = record.split_once('\0') {
+            ret.push((sha.to_string(), msg.trim_end().to_string()));
+        }
+    }
+
+    return ret;
+}
+
+#[test]
+fn test_parse_log() {
+    let log = "abc1234\0Fix teh bug\n\nLonger body\n\x1e\ndef5678\0Subject only\n\x1e\n";
+    assert_eq!(
+        parse_log(log),
+        vec![
+            (
+                "abc1234".to_string(),
+                "Fix teh bug\n\nLonger body".to_string()
+            ),
+            ("def5678".to_string(), "Subject only".to_string()),
+        ]
+    );
+}
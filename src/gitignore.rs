@@ -0,0 +1,223 @@
+use crate::config::glob_matches;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The files in a directory whose rules are followed when walking it, in
+/// the order they're read, so the rules in `.ignore` win
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// A line of an ignore file
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    /// The directory of the ignore file, which anchored globs start from,
+    /// or the one being walked if the file is above it
+    dir: PathBuf,
+    /// The path down from the ignore file's directory to `dir`, if it's
+    /// above it
+    prefix: PathBuf,
+    glob: String,
+    /// A `!` rule, which picks back up what an earlier rule left out
+    negate: bool,
+    /// A glob ending in `/`, which only matches directories
+    dir_only: bool,
+    /// A glob with a `/` in it, which matches the path from `dir`.  The
+    /// others match any file or directory name under `dir`.
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(dir: &Path, prefix: &Path, line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, glob) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if glob.is_empty() {
+            return None;
+        }
+
+        return Some(Self {
+            dir: dir.to_owned(),
+            prefix: prefix.to_owned(),
+            glob: glob.to_string(),
+            negate,
+            dir_only,
+            anchored: glob.contains('/'),
+        });
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(rel) = path.strip_prefix(&self.dir) else {
+            return false;
+        };
+        if self.anchored {
+            return glob_matches(&self.glob, &self.prefix.join(rel));
+        }
+
+        return rel
+            .file_name()
+            .is_some_and(|name| glob_matches(&self.glob, Path::new(name)));
+    }
+}
+
+/// The rules of the `.gitignore` and `.ignore` files in the directory being
+/// walked and the ones above it, up to the root of its git repository,
+/// like git and ripgrep follow.  The last rule that matches a path decides
+/// whether it's left out.
+#[derive(Debug, Default, Clone)]
+pub struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    /// The rules of the ignore files in the directories above `dir`, from
+    /// the root of the git repository it's in, to start walking it with.
+    /// There are none if it isn't in one.
+    pub fn above(dir: &Path) -> Self {
+        let mut ret = Self::default();
+        let Ok(abs) = fs::canonicalize(dir) else {
+            return ret;
+        };
+        let Some(root) = abs.ancestors().position(|a| a.join(".git").exists()) else {
+            return ret;
+        };
+
+        let above: Vec<&Path> = abs.ancestors().skip(1).take(root).collect();
+        for parent in above.into_iter().rev() {
+            let prefix = abs.strip_prefix(parent).unwrap_or(Path::new(""));
+            ret.read(parent, dir, prefix);
+        }
+
+        return ret;
+    }
+
+    /// Add the rules of the ignore files in the directory, returning how
+    /// many rules there were before, to `truncate` to when leaving it
+    pub fn enter(&mut self, dir: &Path) -> usize {
+        let ret = self.rules.len();
+        self.read(dir, dir, Path::new(""));
+
+        return ret;
+    }
+
+    /// Add the rules of the ignore files in `from`, which is `prefix`
+    /// above `dir`
+    fn read(&mut self, from: &Path, dir: &Path, prefix: &Path) {
+        for name in IGNORE_FILES {
+            let path = from.join(name);
+            match fs::read_to_string(&path) {
+                Ok(text) => self.add(dir, prefix, &text),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => warn!("Failed to read \"{}\": {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Add the rules in the text of an ignore file in the directory that's
+    /// `prefix` above `dir`
+    fn add(&mut self, dir: &Path, prefix: &Path, text: &str) {
+        self.rules
+            .extend(text.lines().filter_map(|l| Rule::parse(dir, prefix, l)));
+    }
+
+    /// Drop the rules added after the first `len`
+    pub fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    /// Check whether the path is left out by the rules
+    pub fn ignored(&self, path: &Path, is_dir: bool) -> bool {
+        return self
+            .rules
+            .iter()
+            .rev()
+            .find(|r| r.matches(path, is_dir))
+            .is_some_and(|r| !r.negate);
+    }
+}
+
+#[test]
+fn test_gitignore() {
+    let mut rules = Gitignore::default();
+    rules.add(
+        Path::new("repo"),
+        Path::new(""),
+        "\
+# Build output
+target/
+*.log
+!keep.log
+/TODO.md
+docs/generated/**
+\\#notes
+
+",
+    );
+    let ignored = |path: &str, is_dir: bool| rules.ignored(Path::new(path), is_dir);
+    assert!(ignored("repo/target", true));
+    assert!(ignored("repo/sub/target", true));
+    assert!(!ignored("repo/target", false));
+    assert!(ignored("repo/a.log", false));
+    assert!(ignored("repo/sub/b.log", false));
+    assert!(!ignored("repo/keep.log", false));
+    assert!(ignored("repo/TODO.md", false));
+    assert!(!ignored("repo/sub/TODO.md", false));
+    assert!(ignored("repo/docs/generated/api.md", false));
+    assert!(!ignored("repo/docs/guide.md", false));
+    assert!(ignored("repo/#notes", false));
+    assert!(!ignored("other/a.log", false));
+
+    // The rules of a subdirectory come after, so they win there
+    let len = rules.rules.len();
+    rules.add(Path::new("repo/sub"), Path::new(""), "!*.log\nTODO.md\n");
+    let ignored = |path: &str| rules.ignored(Path::new(path), false);
+    assert!(!ignored("repo/sub/b.log"));
+    assert!(ignored("repo/sub/TODO.md"));
+    assert!(ignored("repo/a.log"));
+    rules.truncate(len);
+    assert!(rules.ignored(Path::new("repo/sub/b.log"), false));
+}
+
+#[test]
+fn test_gitignore_above() {
+    use std::fs::{create_dir_all, write};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    create_dir_all(repo.join(".git")).unwrap();
+    create_dir_all(repo.join("docs/generated")).unwrap();
+    write(tmp.path().join(".gitignore"), "*.md\n").unwrap();
+    write(
+        repo.join(".gitignore"),
+        "*.log\ndocs/generated/\n/TODO.md\n",
+    )
+    .unwrap();
+    write(repo.join("docs/.ignore"), "draft.txt\n").unwrap();
+
+    // Walking a subdirectory, the rules above it down from the repository's
+    // root are followed, but not the ones outside of it
+    let docs = repo.join("docs");
+    let rules = Gitignore::above(&docs);
+    assert!(rules.ignored(&docs.join("a.log"), false));
+    assert!(rules.ignored(&docs.join("generated"), true));
+    assert!(!rules.ignored(&docs.join("sub/generated"), true));
+    assert!(!rules.ignored(&docs.join("TODO.md"), false));
+    assert!(!rules.ignored(&docs.join("guide.md"), false));
+    // The directory's own ones are left to `enter`
+    assert!(!rules.ignored(&docs.join("draft.txt"), false));
+
+    assert!(Gitignore::above(&repo).rules.is_empty());
+    assert!(Gitignore::above(tmp.path()).rules.is_empty());
+}
@@ -0,0 +1,227 @@
+use crate::diagnostic::{Diagnostic, Kind};
+use crate::wordset::fold_case;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// How long the bar of the run with the most findings is
+pub const BAR_WIDTH: usize = 40;
+
+/// The extensions of database files, which a history file can't be, as
+/// it's JSON lines
+const DB_EXTENSIONS: [&str; 4] = ["sqlite", "sqlite3", "db", "db3"];
+
+/// Whether the path looks like it's meant for a database rather than the
+/// JSON lines a history is written as
+pub fn is_database(path: &Path) -> bool {
+    return path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| DB_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+}
+
+/// How many findings a run had, appended to the history file given with
+/// `--history` so they can be followed over time with `spel history show`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunSummary {
+    /// When the run finished, in RFC 3339
+    pub time: String,
+    /// The files that were checked, not counting the skipped ones
+    pub files: usize,
+    pub skipped: usize,
+    pub misspellings: usize,
+    pub variants: usize,
+    pub confusions: usize,
+    pub notes: usize,
+    /// The different words that were flagged, ignoring case
+    pub words: usize,
+    #[serde(skip)]
+    seen: HashSet<String>,
+}
+
+impl RunSummary {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Count a checked file's findings
+    pub fn add(&mut self, diags: &[Diagnostic]) {
+        self.files += 1;
+        for d in diags {
+            match d.kind {
+                Kind::Misspelling => self.misspellings += 1,
+                Kind::Variant => self.variants += 1,
+                Kind::Confusion => self.confusions += 1,
+                Kind::Note => {
+                    self.notes += 1;
+                    continue;
+                }
+            }
+            if self.seen.insert(fold_case(&d.word).into_owned()) {
+                self.words += 1;
+            }
+        }
+    }
+
+    /// The findings, not counting the notes
+    pub fn findings(&self) -> usize {
+        return self.misspellings + self.variants + self.confusions;
+    }
+
+    /// Stamp the summary with the time and add it to the end of the file
+    pub fn append(&mut self, history: &Path) -> Result<()> {
+        self.time = Local::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+        let mut f = OpenOptions::new().create(true).append(true).open(history)?;
+        writeln!(f, "{}", serde_json::to_string(self)?)?;
+
+        return Ok(());
+    }
+}
+
+/// Read all the runs in a history file, oldest first
+pub fn read_history(history: &Path) -> Result<Vec<RunSummary>> {
+    let mut ret = vec![];
+    for (i, line) in fs::read_to_string(history)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(line) {
+            Ok(r) => ret.push(r),
+            Err(e) => bail!("{}:{}: bad history entry: {}", history.display(), i + 1, e),
+        }
+    }
+
+    return Ok(ret);
+}
+
+/// A line per run, with its counts and a bar as long as its findings
+/// relative to the run with the most, followed by how they've changed
+/// since the first run
+pub fn history_table(runs: &[RunSummary], width: usize) -> String {
+    let Some((first, last)) = runs.first().zip(runs.last()) else {
+        return "No runs yet\n".to_string();
+    };
+    let most = runs.iter().map(|r| r.findings()).max().unwrap_or(0).max(1);
+
+    let mut ret = format!(
+        "{:<16}  {:>6}  {:>8}  {:>6}\n",
+        "Run", "Files", "Findings", "Words"
+    );
+    for run in runs {
+        let time = match DateTime::parse_from_rfc3339(&run.time) {
+            Ok(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+            Err(_) => run.time.clone(),
+        };
+        let bar = "#".repeat((run.findings() * width).div_ceil(most));
+        ret += &format!(
+            "{:<16}  {:>6}  {:>8}  {:>6}  {}\n",
+            time,
+            run.files,
+            run.findings(),
+            run.words,
+            bar
+        );
+    }
+
+    let change = last.findings() as i64 - first.findings() as i64;
+    if runs.len() > 1 {
+        ret += &match change {
+            0 => format!("\nNo change since the first of {} runs\n", runs.len()),
+            c if first.findings() == 0 => format!("\n{:+} findings since the first run\n", c),
+            c => format!(
+                "\n{:+} findings ({:+.0}%) since the first of {} runs\n",
+                c,
+                c as f64 * 100.0 / first.findings() as f64,
+                runs.len()
+            ),
+        };
+    }
+
+    return ret;
+}
+
+#[test]
+fn test_run_summary() {
+    use crate::diagnostic::Span;
+    let diag =
+        |word: &str, kind: Kind| Diagnostic::new("a.md".into(), 1, Span::default(), word, kind);
+
+    let mut run = RunSummary::new();
+    run.add(&[
+        diag("teh", Kind::Misspelling),
+        diag("Teh", Kind::Misspelling),
+        diag("cafe", Kind::Variant),
+        diag("affect", Kind::Note),
+    ]);
+    run.add(&[]);
+    assert_eq!(
+        (run.files, run.misspellings, run.variants, run.notes),
+        (2, 2, 1, 1)
+    );
+    assert_eq!((run.findings(), run.words), (3, 2));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.jsonl");
+    run.append(&path).unwrap();
+    RunSummary::new().append(&path).unwrap();
+    let runs = read_history(&path).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].findings(), 3);
+    assert!(DateTime::parse_from_rfc3339(&runs[1].time).is_ok());
+
+    fs::write(&path, "{\"files\":\"many\"}\n").unwrap();
+    let err = read_history(&path).unwrap_err().to_string();
+    assert!(
+        err.contains("history.jsonl:1: bad history entry"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_is_database() {
+    assert!(is_database(Path::new("runs.sqlite")));
+    assert!(is_database(Path::new("a/history.DB")));
+    assert!(!is_database(Path::new(".spel_history.jsonl")));
+    assert!(!is_database(Path::new("history")));
+}
+
+#[test]
+fn test_history_table() {
+    let run = |time: &str, misspellings: usize| RunSummary {
+        time: time.to_string(),
+        files: 10,
+        misspellings,
+        words: misspellings / 2,
+        ..Default::default()
+    };
+
+    assert_eq!(history_table(&[], 10), "No runs yet\n");
+    let runs = [
+        run("2026-10-01T09:30:00+02:00", 40),
+        run("2026-10-08T09:30:00+02:00", 21),
+        run("someday", 30),
+    ];
+    assert_eq!(
+        history_table(&runs, 10),
+        "\
+Run                Files  Findings   Words
+2026-10-01 09:30      10        40      20  ##########
+2026-10-08 09:30      10        21      10  ######
+someday               10        30      15  ########
+
+-10 findings (-25%) since the first of 3 runs
+"
+    );
+    assert!(history_table(&runs[..1], 10).ends_with("20  ##########\n"));
+    assert!(history_table(&[run("a", 0), run("b", 3)], 10)
+        .ends_with("\n+3 findings since the first run\n"));
+}
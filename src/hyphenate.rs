@@ -0,0 +1,61 @@
+use hypher::Lang;
+
+/// Split the word into the pieces it can be broken between at the end of a
+/// line, using the Knuth–Liang (TeX) patterns for English.  A word that
+/// already has hyphens can be broken at them, and each part is split on
+/// its own.
+pub fn hyphenate(word: &str) -> Vec<&str> {
+    let mut ret = vec![];
+    let mut start = 0;
+    for part in word.split_inclusive('-') {
+        let bare = part.trim_end_matches('-');
+        // The patterns are only for letters
+        let mut ends: Vec<usize> = match bare.chars().all(char::is_alphabetic) {
+            true => hypher::hyphenate(bare, Lang::English)
+                .scan(start, |end, piece| {
+                    *end += piece.len();
+                    Some(*end)
+                })
+                .collect(),
+            false => vec![],
+        };
+        // Keep the hyphen with the piece before it
+        ends.pop();
+        ends.push(start + part.len());
+
+        for end in ends {
+            ret.push(&word[start..end]);
+            start = end;
+        }
+    }
+
+    return ret;
+}
+
+/// The word with a hyphen at each place it can be broken, like "hy-phen-ation"
+pub fn hyphenated(word: &str) -> String {
+    let pieces = hyphenate(word);
+    let mut ret = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        ret.push_str(piece);
+        if i + 1 < pieces.len() && !piece.ends_with('-') {
+            ret.push('-');
+        }
+    }
+
+    return ret;
+}
+
+#[test]
+fn test_hyphenate() {
+    assert_eq!(hyphenate("hyphenation"), ["hy", "phen", "ation"]);
+    assert_eq!(hyphenated("typesetting"), "type-set-ting");
+    assert_eq!(hyphenated("Dictionary"), "Dic-tio-nary");
+    // Too short to break
+    assert_eq!(hyphenate("cat"), ["cat"]);
+    assert_eq!(hyphenate(""), Vec::<&str>::new());
+    // The hyphens already there are break points
+    assert_eq!(hyphenate("well-known"), ["well-", "known"]);
+    assert_eq!(hyphenated("self-contained"), "self-con-tained");
+    assert_eq!(hyphenated("x86-64"), "x86-64");
+}
@@ -0,0 +1,164 @@
+use crate::util::write_atomic;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// A replacement made in a file by `spel fix`, recorded in the undo
+/// journal so that it can be reverted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replacement {
+    pub path: PathBuf,
+    /// Byte offset of the replacement in the file as it was written
+    pub start: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// The undo journal of a `spel fix` run, 1 JSON object per line.  A new
+/// run starts it over, but only once it has a replacement to write, so a
+/// run that doesn't change anything leaves the last one's to revert.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    /// Whether what's in the file is from an earlier run, and gets
+    /// replaced by the first write
+    stale: bool,
+}
+
+impl Journal {
+    /// The journal for a new run, or one that adds to the file, for a
+    /// resumed session
+    pub fn new(path: &Path, resume: bool) -> Self {
+        return Self {
+            path: path.to_owned(),
+            stale: !resume,
+        };
+    }
+
+    /// Add replacements to the end of the journal
+    pub fn append(&mut self, replacements: &[Replacement]) -> Result<()> {
+        if replacements.is_empty() {
+            return Ok(());
+        }
+
+        let mut f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(!self.stale)
+            .truncate(self.stale)
+            .open(&self.path)?;
+        self.stale = false;
+
+        for r in replacements {
+            writeln!(f, "{}", serde_json::to_string(r)?)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Read all the replacements in a journal, in the order they were made
+pub fn read_journal(journal: &Path) -> Result<Vec<Replacement>> {
+    let mut ret = vec![];
+    for (i, line) in fs::read_to_string(journal)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(line) {
+            Ok(r) => ret.push(r),
+            Err(e) => bail!("{}:{}: bad journal entry: {}", journal.display(), i + 1, e),
+        }
+    }
+
+    return Ok(ret);
+}
+
+/// Undo the replacements in a file's text, last first.  This fails if
+/// the text doesn't have what was written where it was written, i.e. the
+/// file has changed since.
+pub fn undo(text: &str, replacements: &[&Replacement]) -> Result<String> {
+    let mut ret = text.to_string();
+    for r in replacements.iter().rev() {
+        let end = r.start + r.after.len();
+        if ret.get(r.start..end) != Some(r.after.as_str()) {
+            bail!(
+                "expected \"{}\" at byte {}, the file has changed since it was fixed",
+                r.after,
+                r.start
+            );
+        }
+
+        ret.replace_range(r.start..end, &r.before);
+    }
+
+    return Ok(ret);
+}
+
+/// Revert every replacement in the journal.  Files that have changed since
+/// are left alone and reported.  Returns the number of replacements that
+/// were reverted.
+pub fn revert(journal: &Path) -> Result<usize> {
+    let replacements = read_journal(journal)?;
+
+    // Group them by file, keeping the order they were made in
+    let mut paths: Vec<&Path> = vec![];
+    for r in &replacements {
+        if !paths.contains(&r.path.as_path()) {
+            paths.push(&r.path);
+        }
+    }
+
+    let mut count = 0;
+    for path in paths {
+        let for_file: Vec<&Replacement> = replacements.iter().filter(|r| r.path == path).collect();
+        let reverted = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| undo(&text, &for_file));
+
+        match reverted.and_then(|text| write_atomic(path, &text)) {
+            Ok(()) => count += for_file.len(),
+            Err(e) => error!("Not reverting \"{}\": {}", path.display(), e),
+        }
+    }
+
+    return Ok(count);
+}
+
+#[test]
+fn test_journal_revert() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    let journal = dir.path().join("journal.jsonl");
+
+    // Two runs over the same file, the second after the first
+    fs::write(&path, "The receive the end\n").unwrap();
+    let rep = |start: usize, before: &str, after: &str| Replacement {
+        path: path.clone(),
+        start,
+        before: before.to_string(),
+        after: after.to_string(),
+    };
+    // An earlier run's journal is kept until the new run writes to it
+    fs::write(&journal, "not json\n").unwrap();
+    let mut run = Journal::new(&journal, false);
+    run.append(&[]).unwrap();
+    assert!(read_journal(&journal).is_err());
+    run.append(&[rep(0, "Teh", "The"), rep(4, "recieve", "receive")])
+        .unwrap();
+    Journal::new(&journal, true)
+        .append(&[rep(12, "teh", "the")])
+        .unwrap();
+    assert_eq!(read_journal(&journal).unwrap().len(), 3);
+
+    assert_eq!(revert(&journal).unwrap(), 3);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "Teh recieve teh end\n");
+
+    // Reverting again doesn't match what's in the file anymore
+    assert_eq!(revert(&journal).unwrap(), 0);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "Teh recieve teh end\n");
+}
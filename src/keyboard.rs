@@ -0,0 +1,94 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// A keyboard layout, for telling which keys are next to each other
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+impl Layout {
+    /// The rows of keys, top to bottom, without shift
+    fn rows(&self) -> [&'static str; 4] {
+        return match self {
+            Layout::Qwerty => ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+            Layout::Dvorak => ["1234567890[]", "',.pyfgcrl/=", "aoeuidhtns-", ";qjkxbmwvz"],
+            Layout::Colemak => ["1234567890-=", "qwfpgjluy;[]", "arstdhneio'", "zxcvbkm,./"],
+        };
+    }
+}
+
+/// Which ascii keys are next to each other on a layout, on the same row
+/// or the rows above and below.  Each row sits half a key to the right of
+/// the one above, so a key touches the one above it and the one up to the
+/// right.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    adjacent: Box<[u128; 128]>,
+}
+
+impl KeyMap {
+    pub fn new(layout: Layout) -> Self {
+        let rows: Vec<Vec<u8>> = layout.rows().iter().map(|r| r.bytes().collect()).collect();
+        let mut adjacent = Box::new([0_u128; 128]);
+        let mut link = |a: u8, b: u8| {
+            adjacent[a as usize] |= 1 << b;
+            adjacent[b as usize] |= 1 << a;
+        };
+        for (r, row) in rows.iter().enumerate() {
+            for (c, &key) in row.iter().enumerate() {
+                if let Some(&right) = row.get(c + 1) {
+                    link(key, right);
+                }
+                if let Some(below) = rows.get(r + 1) {
+                    for d in [c.checked_sub(1), Some(c)].into_iter().flatten() {
+                        if let Some(&under) = below.get(d) {
+                            link(key, under);
+                        }
+                    }
+                }
+            }
+        }
+
+        return Self { adjacent };
+    }
+
+    /// Check whether the 2 chars are on keys next to each other
+    pub fn adjacent(&self, a: u32, b: u32) -> bool {
+        if a >= 128 || b >= 128 {
+            return false;
+        }
+        // Capitals are on the same keys
+        let (a, b) = (
+            (a as u8).to_ascii_lowercase(),
+            (b as u8).to_ascii_lowercase(),
+        );
+
+        return self.adjacent[a as usize] & (1 << b) != 0;
+    }
+}
+
+#[test]
+fn test_key_map() {
+    let qwerty = KeyMap::new(Layout::Qwerty);
+    let adj = |a: char, b: char| qwerty.adjacent(a as u32, b as u32);
+    assert!(adj('w', 'e'));
+    assert!(adj('e', 'w'));
+    assert!(adj('s', 'w'));
+    assert!(adj('s', 'e'));
+    assert!(adj('s', 'z'));
+    assert!(adj('s', 'x'));
+    assert!(adj('S', 'w'));
+    assert!(!adj('s', 'c'));
+    assert!(!adj('s', 'q'));
+    assert!(!adj('s', 's'));
+    assert!(!adj('q', 'p'));
+    assert!(!adj('é', 'e'));
+
+    let dvorak = KeyMap::new(Layout::Dvorak);
+    assert!(dvorak.adjacent('o' as u32, 'e' as u32));
+    assert!(!dvorak.adjacent('w' as u32, 'e' as u32));
+}
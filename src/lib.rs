@@ -0,0 +1,61 @@
+//! The spelling checker behind the `spel` command, for checking words and
+//! text from other programs.  [`SpellChecker`] is the place to start:
+//!
+//! ```
+//! use spel::SpellChecker;
+//!
+//! let checker = SpellChecker::builtin();
+//! assert!(checker.is_correct("spelling"));
+//! assert!(!checker.is_correct("speling"));
+//! assert!(checker.suggest("speling", 5).contains(&"spelling"));
+//! ```
+#![allow(clippy::needless_return)]
+#[macro_use]
+extern crate log;
+
+mod bench;
+mod bktree;
+mod buckets;
+pub mod checker;
+mod cli;
+mod compound;
+mod config;
+mod confusables;
+pub mod diagnostic;
+mod distance;
+mod doctor;
+mod filter;
+mod fix;
+mod git;
+mod gitignore;
+mod history;
+mod hyphenate;
+mod journal;
+mod keyboard;
+mod modeline;
+mod ngram;
+mod normalize;
+mod output;
+mod personal;
+mod phonetic;
+mod phrase;
+mod project;
+mod report;
+mod results;
+mod symspell;
+mod typos;
+mod util;
+mod wordlist;
+mod wordset;
+
+pub use crate::checker::SpellChecker;
+pub use crate::compound::CompoundConfig;
+pub use crate::diagnostic::{Diagnostic, Kind};
+pub use crate::distance::Metric;
+pub use crate::filter::{Filter, MarkdownOpts};
+pub use crate::keyboard::Layout;
+pub use crate::util::{CheckOpts, SuggestIndex, SuggestOpts};
+
+/// The `spel` command, for src/main.rs.  It isn't part of the library.
+#[doc(hidden)]
+pub use crate::cli::run;
@@ -0,0 +1,190 @@
+use crate::diagnostic::Diagnostic;
+use crate::filter::Filter;
+use crate::util::CheckOpts;
+use crate::wordset::fold_case;
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+/// Options a file sets for itself, like a vim modeline: `spel:` followed
+/// by the options on the first or last line, usually in a comment, like
+/// `# spel: filter=shell ignore=kubectl,minikube`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Modeline {
+    /// The line it's on, whose words aren't checked
+    pub line: u64,
+    pub filter: Option<Filter>,
+    pub skip_code_spans: Option<bool>,
+    pub comments_only: Option<bool>,
+    pub real_word: Option<bool>,
+    /// Words to ignore in this file only, case folded
+    pub ignore: HashSet<String>,
+    /// What was wrong with the options that were left out, like unknown
+    /// ones, to warn about
+    pub problems: Vec<String>,
+}
+
+/// The options of a modeline, if the line is one.  Only comment markers
+/// can come before the `spel:`, so prose that happens to mention spel
+/// isn't taken for one.
+fn options(line: &str) -> Option<&str> {
+    let start = line.find("spel:")?;
+    if line[..start].contains(char::is_alphanumeric) {
+        return None;
+    }
+    let rest = &line[start + "spel:".len()..];
+    let end = ["-->", "*/"]
+        .iter()
+        .filter_map(|close| rest.find(close))
+        .min()
+        .unwrap_or(rest.len());
+
+    return Some(&rest[..end]);
+}
+
+impl Modeline {
+    /// Find the modeline on the first or last line of the text, if there
+    /// is one
+    pub fn find(text: &str) -> Option<Self> {
+        let count = text.lines().count();
+        let ends = [text.lines().next(), text.lines().next_back()];
+        for (i, line) in [0, count.saturating_sub(1)].into_iter().zip(ends) {
+            if let Some(opts) = line.and_then(options) {
+                return Some(Self {
+                    line: i as u64 + 1,
+                    ..Self::parse(opts)
+                });
+            }
+        }
+
+        return None;
+    }
+
+    /// Parse the options, which are split by whitespace.  Switches can be
+    /// given on their own or as `name=true` or `name=false`.  Options that
+    /// are unknown or have a bad value are left out, and noted in
+    /// `problems`, so the rest still apply.
+    pub fn parse(options: &str) -> Self {
+        let mut ret = Self::default();
+        for opt in options.split_whitespace() {
+            if let Err(e) = ret.set(opt) {
+                ret.problems.push(e.to_string());
+            }
+        }
+
+        return ret;
+    }
+
+    /// Set one option, given as `name` or `name=value`
+    fn set(&mut self, opt: &str) -> Result<()> {
+        let (name, value) = match opt.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (opt, None),
+        };
+        let switch = || {
+            return match value {
+                None | Some("true") => Ok(true),
+                Some("false") => Ok(false),
+                Some(v) => Err(anyhow!("{} is true or false, not \"{}\"", name, v)),
+            };
+        };
+
+        match name {
+            "filter" => {
+                let value = value.unwrap_or_default();
+                self.filter = Some(
+                    Filter::from_str(value, true)
+                        .map_err(|_| anyhow!("there's no \"{}\" filter", value))?,
+                );
+            }
+            "ignore" => self.ignore.extend(
+                value
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|w| !w.is_empty())
+                    .map(|w| fold_case(w).into_owned()),
+            ),
+            "skip-code-spans" => self.skip_code_spans = Some(switch()?),
+            "comments-only" => self.comments_only = Some(switch()?),
+            "real-word" => self.real_word = Some(switch()?),
+            _ => bail!("unknown option \"{}\"", name),
+        }
+
+        return Ok(());
+    }
+
+    /// The options with the modeline's on top
+    pub fn apply(&self, opts: &CheckOpts) -> CheckOpts {
+        let mut ret = opts.clone();
+        ret.filter = self.filter.or(ret.filter);
+        ret.skip_code_spans = self.skip_code_spans.unwrap_or(ret.skip_code_spans);
+        ret.comments_only = self.comments_only.unwrap_or(ret.comments_only);
+        ret.real_word = self.real_word.unwrap_or(ret.real_word);
+
+        return ret;
+    }
+
+    /// Drop the findings on the modeline itself and the ones for the words
+    /// it ignores
+    pub fn retain(&self, diags: &mut Vec<Diagnostic>) {
+        diags.retain(|d| d.line != self.line && !self.ignore.contains(&*fold_case(&d.word)));
+    }
+}
+
+#[test]
+fn test_modeline() {
+    let m = Modeline::parse(" filter=shell ignore=Kubectl,minikube, comments-only ");
+    assert_eq!(m.filter, Some(Filter::Shell));
+    assert_eq!(m.comments_only, Some(true));
+    assert_eq!(m.skip_code_spans, None);
+    assert!(m.ignore.contains("kubectl") && m.ignore.contains("minikube"));
+    assert_eq!(m.ignore.len(), 2);
+    assert!(m.problems.is_empty());
+    assert_eq!(Modeline::parse("real-word=false").real_word, Some(false));
+
+    // Bad options are left out, and the rest still apply
+    let bad = Modeline::parse("filter=nope real-word=maybe min-length=4 skip-code-spans");
+    assert_eq!(bad.filter, None);
+    assert_eq!(bad.real_word, None);
+    assert_eq!(bad.skip_code_spans, Some(true));
+    assert_eq!(
+        bad.problems,
+        [
+            "there's no \"nope\" filter",
+            "real-word is true or false, not \"maybe\"",
+            "unknown option \"min-length\"",
+        ]
+    );
+
+    let opts = CheckOpts {
+        skip_code_spans: true,
+        ..Default::default()
+    };
+    let applied = m.apply(&opts);
+    assert_eq!(applied.filter, Some(Filter::Shell));
+    assert!(applied.comments_only && applied.skip_code_spans);
+
+    let found = |text: &str| Modeline::find(text).map(|m| m.line);
+    assert_eq!(found("<!-- spel: ignore=foo -->\nText\n"), Some(1));
+    assert_eq!(found("Text\nmore\n// spel: real-word\n"), Some(3));
+    assert_eq!(found("/* spel: comments-only */"), Some(1));
+    assert_eq!(found("# spel: bogus\n"), Some(1));
+    assert_eq!(found("Run spel: it checks\nspelling\n"), None);
+    assert_eq!(found("a\n# spel: ignore=x\nb\n"), None);
+    assert_eq!(found(""), None);
+
+    use crate::diagnostic::{Kind, Span};
+    let diag = |line: u64, word: &str| {
+        return Diagnostic::new(
+            "a.sh".into(),
+            line,
+            Span::default(),
+            word,
+            Kind::Misspelling,
+        );
+    };
+    let m = Modeline::find("echo hi\n# spel: ignore=kubectl\n").unwrap();
+    let mut diags = vec![diag(1, "KUBECTL"), diag(1, "teh"), diag(2, "spel")];
+    m.retain(&mut diags);
+    assert_eq!(diags, [diag(1, "teh")]);
+}
@@ -0,0 +1,157 @@
+use crate::util::{parse_path, read_lines};
+use anyhow::{bail, Result};
+use std::{collections::HashMap, path::Path};
+
+/// How much the surrounding words count for compared to the similarity
+/// ratio when reranking suggestions
+const CONTEXT_WEIGHT: f32 = 0.1;
+
+/// A simple bigram frequency model, used to rank suggestions by the words
+/// around them
+#[derive(Debug, Default, Clone)]
+pub struct BigramModel {
+    counts: HashMap<String, HashMap<String, u64>>,
+}
+
+impl BigramModel {
+    /// Load a model file, which is 1 bigram per line in the form of
+    /// `first second count`.  Blank lines and lines starting with # are
+    /// skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut ret = Self::default();
+
+        for (lnum, line) in (1_u64..).zip(read_lines(&parse_path(path))?) {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let count = match parts.as_slice() {
+                [_, _, count] => count.parse::<u64>().ok(),
+                _ => None,
+            };
+
+            match count {
+                Some(count) => ret.add(parts[0], parts[1], count),
+                None => bail!(
+                    "Invalid bigram on line {} of {}: {}",
+                    lnum,
+                    path.display(),
+                    line
+                ),
+            }
+        }
+
+        return Ok(ret);
+    }
+
+    pub fn add(&mut self, first: &str, second: &str, count: u64) {
+        *self
+            .counts
+            .entry(first.to_lowercase())
+            .or_default()
+            .entry(second.to_lowercase())
+            .or_default() += count;
+    }
+
+    pub fn count(&self, first: &str, second: &str) -> u64 {
+        return self
+            .counts
+            .get(first)
+            .and_then(|c| c.get(second))
+            .copied()
+            .unwrap_or(0);
+    }
+
+    /// How often the word starts a bigram, as a measure of how common it is
+    pub fn frequency(&self, word: &str) -> u64 {
+        return self.counts.get(word).map_or(0, |c| c.values().sum());
+    }
+
+    /// Score how well the candidate fits between the previous and next
+    /// words
+    pub fn context_score(&self, prev: Option<&str>, cand: &str, next: Option<&str>) -> f32 {
+        let mut count = 0;
+        if let Some(p) = prev {
+            count += self.count(p, cand);
+        }
+
+        if let Some(n) = next {
+            count += self.count(cand, n);
+        }
+
+        return (1.0 + count as f32).ln();
+    }
+
+    /// Rerank the (ratio, word) suggestions using the surrounding words,
+    /// best first
+    pub fn rerank<'a>(
+        &self,
+        matches: &[(f32, &'a str)],
+        prev: Option<&str>,
+        next: Option<&str>,
+    ) -> Vec<(f32, &'a str)> {
+        let mut ret: Vec<(f32, &str)> = matches
+            .iter()
+            .map(|&(ratio, w)| {
+                (
+                    ratio + CONTEXT_WEIGHT * self.context_score(prev, w, next),
+                    w,
+                )
+            })
+            .collect();
+        // Ties go to the more common word, then alphabetical order
+        ret.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then_with(|| self.frequency(b.1).cmp(&self.frequency(a.1)))
+                .then_with(|| a.1.cmp(b.1))
+        });
+
+        return ret;
+    }
+}
+
+#[test]
+fn test_bigram_rerank() {
+    let mut model = BigramModel::default();
+    model.add("of", "cake", 500);
+    model.add("Piece", "of", 1000);
+
+    assert_eq!(model.count("piece", "of"), 1000);
+    assert_eq!(model.count("of", "calk"), 0);
+
+    let matches = vec![(0.86, "calk"), (0.86, "cake"), (0.5, "cat")];
+    let res = model.rerank(&matches, Some("of"), None);
+    assert_eq!(res[0].1, "cake");
+    assert_eq!(res[1].1, "calk");
+
+    // Without context, a tie goes to the more common word, then the one
+    // that comes first alphabetically
+    assert_eq!(model.frequency("of"), 500);
+    let matches = vec![(0.5, "cot"), (0.5, "of"), (0.5, "cat")];
+    let res = model.rerank(&matches, None, None);
+    assert_eq!(
+        res.iter().map(|r| r.1).collect::<Vec<_>>(),
+        ["of", "cat", "cot"]
+    );
+}
+
+#[test]
+fn test_bigram_load() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let fname = dir.path().join("bigrams.txt");
+    let mut f = std::fs::File::create(&fname).unwrap();
+    f.write_all(b"# a comment\npiece of 10\n\nof cake 5\n")
+        .unwrap();
+
+    let model = BigramModel::load(&fname).unwrap();
+    assert_eq!(model.count("of", "cake"), 5);
+
+    let mut f = std::fs::File::create(&fname).unwrap();
+    f.write_all(b"piece of ten\n").unwrap();
+    assert!(BigramModel::load(&fname).is_err());
+}
@@ -0,0 +1,123 @@
+use crate::wordset::WordSet;
+use std::collections::HashMap;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Strip the diacritics from a word, so "résumé" becomes "resume"
+pub fn fold_diacritics(word: &str) -> String {
+    return word.nfd().filter(|c| !is_combining_mark(*c)).collect();
+}
+
+/// Transliterate a word to plain ascii where we know how, so "straße"
+/// becomes "strasse" and "encyclopædia" becomes "encyclopaedia".  Anything
+/// that can't be transliterated is left as is.
+pub fn transliterate(word: &str) -> String {
+    let mut ret = String::with_capacity(word.len());
+
+    for c in word.chars() {
+        if c.is_ascii() {
+            ret.push(c);
+            continue;
+        }
+
+        let repl = match c {
+            'ß' => "ss",
+            'æ' => "ae",
+            'Æ' => "AE",
+            'œ' => "oe",
+            'Œ' => "OE",
+            'ø' => "o",
+            'Ø' => "O",
+            'ð' | 'đ' => "d",
+            'Ð' | 'Đ' => "D",
+            'þ' => "th",
+            'Þ' => "TH",
+            'ł' => "l",
+            'Ł' => "L",
+            'ı' => "i",
+            'ĳ' => "ij",
+            'ﬀ' => "ff",
+            'ﬁ' => "fi",
+            'ﬂ' => "fl",
+            '’' | '‘' => "'",
+            '‐' | '‑' | '–' => "-",
+            _ => "",
+        };
+
+        if !repl.is_empty() {
+            ret.push_str(repl);
+        } else {
+            // Fall back to stripping any diacritics from the char
+            ret.extend(c.to_string().nfd().filter(|c| !is_combining_mark(*c)));
+        }
+    }
+
+    return ret;
+}
+
+/// Build a map of folded form -> original for the dictionary words that
+/// actually have diacritics.  Plain words don't need an entry since the
+/// folded form of a token can be looked up in the dictionary directly.
+pub fn build_folded(words: &WordSet) -> HashMap<String, String> {
+    let mut ret = HashMap::new();
+
+    for word in words.iter() {
+        if word.is_ascii() {
+            continue;
+        }
+
+        let folded = fold_diacritics(&word);
+        if folded != word {
+            ret.entry(folded).or_insert_with(|| word.to_string());
+        }
+    }
+
+    return ret;
+}
+
+/// If the word matches a dictionary word when ignoring diacritics, return
+/// the dictionary word it's a variant of
+pub fn find_variant(
+    word: &str,
+    words: &WordSet,
+    folded: &HashMap<String, String>,
+) -> Option<String> {
+    let key = fold_diacritics(word);
+    if key != word && words.contains(key.as_str()) {
+        return Some(key);
+    }
+
+    return folded.get(&key).cloned();
+}
+
+#[test]
+fn test_fold_diacritics() {
+    assert_eq!(fold_diacritics("résumé"), "resume");
+    assert_eq!(fold_diacritics("naïve"), "naive");
+    assert_eq!(fold_diacritics("plain"), "plain");
+}
+
+#[test]
+fn test_transliterate() {
+    assert_eq!(transliterate("straße"), "strasse");
+    assert_eq!(transliterate("encyclopædia"), "encyclopaedia");
+    assert_eq!(transliterate("café"), "cafe");
+    assert_eq!(transliterate("don’t"), "don't");
+    assert_eq!(transliterate("plain"), "plain");
+}
+
+#[test]
+fn test_find_variant() {
+    let words = WordSet::new(&["résumé", "naive"], Default::default());
+    let folded = build_folded(&words);
+
+    assert_eq!(
+        find_variant("resume", &words, &folded),
+        Some("résumé".to_string())
+    );
+    assert_eq!(
+        find_variant("naïve", &words, &folded),
+        Some("naive".to_string())
+    );
+    assert_eq!(find_variant("naive", &words, &folded), None);
+    assert_eq!(find_variant("monkey", &words, &folded), None);
+}
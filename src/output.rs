@@ -0,0 +1,798 @@
+use crate::diagnostic::{Diagnostic, Kind, PositionEncoding};
+use clap::ValueEnum;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How the findings in --file mode are grouped in the output
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum GroupBy {
+    /// Stream the findings file by file, in the order they're found
+    #[default]
+    File,
+    /// Group all the occurrences of each misspelled word together
+    Word,
+}
+
+/// The output format for the diagnostics in --file mode
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// file:line:col "word", which editors can jump to
+    #[default]
+    Plain,
+    /// A JSON object per line, for tools
+    Json,
+    /// The whole source line, with the word highlighted
+    Annotate,
+    /// A SARIF 2.1.0 report of the whole run, for code scanning
+    Sarif,
+    /// The whole source line with a caret under the word, like a
+    /// compiler's errors
+    Caret,
+}
+
+/// When to color the output
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorWhen {
+    /// When writing to a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorWhen {
+    /// Check whether to use color, given whether the output is a terminal
+    pub fn enabled(&self, terminal: bool) -> bool {
+        return match self {
+            ColorWhen::Auto => terminal,
+            ColorWhen::Always => true,
+            ColorWhen::Never => false,
+        };
+    }
+}
+
+/// The options for how the diagnostics are output
+#[derive(Debug, Clone)]
+pub struct OutputOpts {
+    pub format: Format,
+    pub group_by: GroupBy,
+    /// Collapse repeats of a word within a file into a single finding
+    pub unique_per_file: bool,
+    /// Only report the first occurrence of each word in the whole run
+    pub first_only: bool,
+    /// The units that columns are counted in
+    pub encoding: PositionEncoding,
+    /// The number of the first line, 0 or 1
+    pub line_base: u64,
+    /// The number of the first column, 0 or 1
+    pub column_base: usize,
+    /// Highlight with color instead of brackets
+    pub color: bool,
+    /// The number of lines of context to show before and after each
+    /// finding
+    pub context: usize,
+    /// Only print the misspelled words, once each
+    pub words_only: bool,
+    /// End each line of output with a NUL instead of a newline
+    pub print0: bool,
+}
+
+impl OutputOpts {
+    /// The line number of the diagnostic, using the configured base
+    pub fn line(&self, d: &Diagnostic) -> u64 {
+        return d.line - 1 + self.line_base;
+    }
+
+    /// The column range of the diagnostic, using the configured encoding
+    /// and base
+    pub fn columns(&self, d: &Diagnostic) -> std::ops::Range<usize> {
+        let cols = d.span.columns(self.encoding);
+        return cols.start + self.column_base..cols.end + self.column_base;
+    }
+}
+
+impl Default for OutputOpts {
+    fn default() -> Self {
+        return Self {
+            format: Format::default(),
+            group_by: GroupBy::default(),
+            unique_per_file: false,
+            first_only: false,
+            encoding: PositionEncoding::default(),
+            line_base: 1,
+            column_base: 1,
+            color: false,
+            context: 0,
+            words_only: false,
+            print0: false,
+        };
+    }
+}
+
+/// Renders the diagnostics from a --file run
+#[derive(Debug, Default)]
+pub struct Printer {
+    pub opts: OutputOpts,
+    /// The words reported so far, for --first-only and --words-only
+    seen: HashSet<String>,
+    /// Diagnostics held back until the end of the run, when grouping by word
+    pending: Vec<Diagnostic>,
+    /// The lines of context for each of the pending diagnostics, as their
+    /// files aren't around at the end of the run
+    pending_context: Vec<Vec<String>>,
+}
+
+impl Printer {
+    pub fn new(opts: OutputOpts) -> Self {
+        return Self {
+            opts,
+            ..Default::default()
+        };
+    }
+
+    /// Add the findings for a single file, along with the file contents
+    pub fn add(&mut self, text: &str, mut findings: Vec<Diagnostic>) {
+        if self.opts.words_only {
+            for word in new_words(&findings, &mut self.seen) {
+                self.emit(&word);
+            }
+            return;
+        }
+
+        if self.opts.unique_per_file {
+            findings = unique_per_file(findings);
+        }
+
+        if self.opts.first_only {
+            findings.retain(|f| self.seen.insert(f.word.clone()));
+        }
+
+        // Only split up the file if we need to show context
+        let lines: Vec<&str> =
+            if self.opts.context > 0 && !matches!(self.opts.format, Format::Json | Format::Sarif) {
+                text.lines().collect()
+            } else {
+                vec![]
+            };
+
+        match (self.opts.format, self.opts.group_by) {
+            (Format::Json, _) => {
+                for f in findings {
+                    self.emit(&to_json(&f, &self.opts).to_string());
+                }
+            }
+            (Format::Annotate, _) => {
+                for f in findings {
+                    self.emit(&annotate(&f, text, &self.opts));
+                    self.print_context(&f, &lines);
+                }
+            }
+            (Format::Caret, _) => {
+                for f in findings {
+                    self.emit(&caret(&f, text, &self.opts));
+                    self.print_context(&f, &lines);
+                }
+            }
+            (Format::Plain, GroupBy::File) => {
+                for f in findings {
+                    self.emit(&plain(&f, &self.opts));
+                    self.print_context(&f, &lines);
+                }
+            }
+            (Format::Plain, GroupBy::Word) => {
+                for f in findings {
+                    self.pending_context.push(match self.opts.context {
+                        0 => vec![],
+                        _ => context(&f, &lines, &self.opts),
+                    });
+                    self.pending.push(f);
+                }
+            }
+            (Format::Sarif, _) => self.pending.extend(findings),
+        }
+    }
+
+    /// Whether the findings are shown with the text of their file, so it
+    /// has to be kept for `add()`
+    pub fn needs_text(&self) -> bool {
+        return !self.opts.words_only
+            && match self.opts.format {
+                Format::Annotate | Format::Caret => true,
+                Format::Plain => self.opts.context > 0,
+                Format::Json | Format::Sarif => false,
+            };
+    }
+
+    /// Print a line of output, ended the way the options say
+    fn emit(&self, line: &str) {
+        match self.opts.print0 {
+            true => print!("{}\0", line),
+            false => println!("{}", line),
+        }
+    }
+
+    fn print_context(&self, d: &Diagnostic, lines: &[&str]) {
+        if self.opts.context == 0 {
+            return;
+        }
+
+        for line in context(d, lines, &self.opts) {
+            self.emit(&line);
+        }
+        self.emit("--");
+    }
+
+    /// Output anything that was held back for the end of the run.  A
+    /// SARIF report is always output, even if it's empty.
+    pub fn finish(&mut self) {
+        if self.opts.format == Format::Sarif && !self.opts.words_only {
+            let report = serde_json::to_string_pretty(&to_sarif(&self.pending)).unwrap();
+            self.emit(&report);
+        } else {
+            for line in group_by_word(&self.pending, &self.pending_context, &self.opts) {
+                self.emit(&line);
+            }
+        }
+        self.pending.clear();
+        self.pending_context.clear();
+    }
+}
+
+/// The misspelled words that haven't been seen yet, in the order they're
+/// found, for --words-only
+pub fn new_words(findings: &[Diagnostic], seen: &mut HashSet<String>) -> Vec<String> {
+    return findings
+        .iter()
+        .filter(|f| f.kind == Kind::Misspelling && seen.insert(f.word.clone()))
+        .map(|f| f.word.clone())
+        .collect();
+}
+
+/// Render a diagnostic as file:line:col "word"
+pub fn plain(d: &Diagnostic, opts: &OutputOpts) -> String {
+    return format!(
+        "{}:{}:{} {}",
+        d.path.display(),
+        opts.line(d),
+        opts.columns(d).start,
+        d.describe()
+    );
+}
+
+/// Get the full line that the diagnostic is on, from the file text
+pub fn line_of<'a>(d: &Diagnostic, text: &'a str) -> &'a str {
+    let start = d.span.bytes.start - d.span.line_bytes.start;
+    let rest = &text[start..];
+    let end = rest.find('\n').unwrap_or(rest.len());
+
+    return rest[..end].trim_end_matches('\r');
+}
+
+/// Render a diagnostic as file:line: followed by the whole line, with the
+/// word wrapped in color or brackets
+pub fn annotate(d: &Diagnostic, text: &str, opts: &OutputOpts) -> String {
+    let line = line_of(d, text);
+    let bytes = &d.span.line_bytes;
+    let (open, close) = if opts.color {
+        ("\x1b[1;31m", "\x1b[0m")
+    } else {
+        ("[", "]")
+    };
+
+    let mut ret = format!(
+        "{}:{}: {}{}{}{}{}",
+        d.path.display(),
+        opts.line(d),
+        &line[..bytes.start],
+        open,
+        &line[bytes.clone()],
+        close,
+        &line[bytes.end..],
+    );
+
+    if let Some(detail) = d.detail() {
+        ret.push_str(&format!("  ({})", detail));
+    }
+
+    return ret;
+}
+
+/// Render a diagnostic the way a compiler shows an error: the kind and the
+/// word, where it is, and the line with carets under the word, followed by
+/// a blank line
+pub fn caret(d: &Diagnostic, text: &str, opts: &OutputOpts) -> String {
+    let line = line_of(d, text);
+    let bytes = &d.span.line_bytes;
+    let paint = |code: &str, s: &str| match opts.color {
+        true => format!("\x1b[{}m{}\x1b[0m", code, s),
+        false => s.to_string(),
+    };
+    let (red, blue) = ("1;31", "1;34");
+    let num = opts.line(d).to_string();
+    let gutter = paint(blue, &format!("{} |", " ".repeat(num.len())));
+    // Tabs are kept so the carets line up under the word however wide
+    // they're shown
+    let indent: String = line[..bytes.start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let carets = "^".repeat(line[bytes.clone()].chars().count());
+
+    let mut ret = format!(
+        "{}: \"{}\"\n{}{} {}:{}:{}\n{}\n{} {}{}{}\n{} {}{}",
+        paint(red, &d.kind.to_string()),
+        d.word,
+        " ".repeat(num.len()),
+        paint(blue, "-->"),
+        d.path.display(),
+        num,
+        opts.columns(d).start,
+        gutter,
+        paint(blue, &format!("{} |", num)),
+        &line[..bytes.start],
+        paint(red, &line[bytes.clone()]),
+        &line[bytes.end..],
+        gutter,
+        indent,
+        paint(red, &carets),
+    );
+    if let Some(detail) = d.detail() {
+        ret.push_str(&format!(" {}", detail));
+    }
+    ret.push('\n');
+
+    return ret;
+}
+
+/// Render the lines around the diagnostic, grep -C style, with the line
+/// the finding is on marked with a >
+pub fn context(d: &Diagnostic, lines: &[&str], opts: &OutputOpts) -> Vec<String> {
+    let idx = (d.line - 1) as usize;
+    let first = idx.saturating_sub(opts.context);
+    let last = (idx + opts.context).min(lines.len().saturating_sub(1));
+    let width = (last as u64 + opts.line_base).to_string().len();
+
+    let mut ret = vec![];
+    for (i, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let marker = if i == idx { '>' } else { ' ' };
+        ret.push(format!(
+            "{} {:>width$} | {}",
+            marker,
+            i as u64 + opts.line_base,
+            line,
+            width = width,
+        ));
+    }
+
+    return ret;
+}
+
+/// Render a diagnostic as a JSON object, with the columns counted in the
+/// units of the configured encoding
+pub fn to_json(d: &Diagnostic, opts: &OutputOpts) -> serde_json::Value {
+    let cols = opts.columns(d);
+
+    return json!({
+        "path": d.path.to_string_lossy(),
+        "line": opts.line(d),
+        "column": cols.start,
+        "end_column": cols.end,
+        "position_encoding": opts.encoding.as_str(),
+        "byte_offset": [d.span.bytes.start, d.span.bytes.end],
+        "char_offset": [d.span.chars.start, d.span.chars.end],
+        "word": d.word,
+        "kind": d.kind.to_string(),
+        "suggestions": d.suggestions,
+        "note": d.note,
+        "scope": d.scope,
+        "count": d.count,
+        "fingerprint": d.fingerprint,
+    });
+}
+
+/// The SARIF level and description of each kind of finding, which are the
+/// rules of the report
+const SARIF_RULES: [(Kind, &str, &str); 4] = [
+    (
+        Kind::Misspelling,
+        "warning",
+        "The word isn't in the dictionary",
+    ),
+    (
+        Kind::Variant,
+        "warning",
+        "The word only differs from a dictionary word by its accents",
+    ),
+    (
+        Kind::Confusion,
+        "warning",
+        "The word is spelled right, but is often confused with another",
+    ),
+    (Kind::Note, "note", "A note on the word's usage"),
+];
+
+/// Render the findings of a run as a SARIF 2.1.0 log.  SARIF lines and
+/// columns start at 1, and the columns are counted in utf-16 code units,
+/// whatever the options say.
+pub fn to_sarif(findings: &[Diagnostic]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = SARIF_RULES
+        .iter()
+        .map(|(kind, level, desc)| {
+            json!({
+                "id": kind.to_string(),
+                "shortDescription": {"text": desc},
+                "defaultConfiguration": {"level": level},
+            })
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|d| {
+            let rule = SARIF_RULES.iter().position(|r| r.0 == d.kind).unwrap();
+            // Relative to where spel was run, which is usually the root of
+            // the repo in CI
+            let path = d.path.to_string_lossy().replace('\\', "/");
+            let uri = match (path.starts_with('/'), d.path.is_absolute()) {
+                (true, _) => format!("file://{}", path),
+                (false, true) => format!("file:///{}", path),
+                _ => path.strip_prefix("./").unwrap_or(&path).to_string(),
+            };
+            json!({
+                "ruleId": d.kind.to_string(),
+                "ruleIndex": rule,
+                "level": SARIF_RULES[rule].1,
+                "message": {"text": format!("{}: {}", d.kind, d.describe())},
+                "partialFingerprints": {"spel/v1": d.fingerprint},
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {"uri": uri},
+                        "region": {
+                            "startLine": d.line,
+                            "startColumn": d.span.line_utf16.start + 1,
+                            "endColumn": d.span.line_utf16.end + 1,
+                            "byteOffset": d.span.bytes.start,
+                            "byteLength": d.span.bytes.len(),
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    return json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "spel",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "utf16CodeUnits",
+            "results": results,
+        }],
+    });
+}
+
+/// Collapse the findings for a single file so each word is only reported
+/// once, at its first occurrence, with the number of times it was found
+pub fn unique_per_file(findings: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut ret: Vec<Diagnostic> = vec![];
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for f in findings {
+        match seen.get(&f.word) {
+            Some(&i) => ret[i].count += f.count,
+            None => {
+                seen.insert(f.word.clone(), ret.len());
+                ret.push(f);
+            }
+        }
+    }
+
+    return ret;
+}
+
+/// Render the findings as a header for each word, in alphabetical order,
+/// followed by the locations it was found at, each with its lines of
+/// `context` if there are any
+pub fn group_by_word(
+    findings: &[Diagnostic],
+    context: &[Vec<String>],
+    opts: &OutputOpts,
+) -> Vec<String> {
+    let mut by_word: BTreeMap<&str, Vec<(&Diagnostic, &[String])>> = BTreeMap::new();
+    for (i, f) in findings.iter().enumerate() {
+        let lines = context.get(i).map_or(&[][..], |c| &c[..]);
+        by_word.entry(&f.word).or_default().push((f, lines));
+    }
+
+    let mut ret = vec![];
+    for (word, found) in by_word {
+        let total: usize = found.iter().map(|f| f.0.count).sum();
+        ret.push(format!("\"{}\" ({})", word, total));
+        for (f, lines) in found {
+            let mut loc = format!(
+                "    {}:{}:{}",
+                f.path.display(),
+                opts.line(f),
+                opts.columns(f).start
+            );
+            if let Some(scope) = &f.scope {
+                loc.push_str(&format!(" [{}]", scope));
+            }
+            if let Some(detail) = f.detail() {
+                loc.push_str(&format!(" ({})", detail));
+            }
+            if f.count > 1 {
+                loc.push_str(&format!(" ({} times)", f.count));
+            }
+            ret.push(loc);
+            if !lines.is_empty() {
+                ret.extend(lines.iter().map(|l| format!("    {}", l)));
+                ret.push("    --".to_string());
+            }
+        }
+    }
+
+    return ret;
+}
+
+#[cfg(test)]
+fn finding(path: &str, line: u64, word: &str) -> Diagnostic {
+    use crate::diagnostic::{Kind, Span};
+
+    let chars = word.chars().count();
+    let span = Span::new(0..word.len(), 0..chars, 0..chars, 0, 0);
+    return Diagnostic::new(path.into(), line, span, word, Kind::Misspelling);
+}
+
+#[test]
+fn test_group_by_word() {
+    let findings = vec![
+        finding("a.md", 3, "recieve"),
+        finding("a.md", 5, "teh"),
+        finding("b.md", 1, "recieve"),
+    ];
+
+    assert_eq!(
+        group_by_word(&findings, &[], &OutputOpts::default()),
+        vec![
+            "\"recieve\" (2)",
+            "    a.md:3:1",
+            "    b.md:1:1",
+            "\"teh\" (1)",
+            "    a.md:5:1",
+        ]
+    );
+
+    // The context of each finding is kept until the end of the run
+    let mut printer = Printer::new(OutputOpts {
+        group_by: GroupBy::Word,
+        context: 1,
+        ..Default::default()
+    });
+    assert!(printer.needs_text());
+    printer.add("one\ntwo\nrecieve\n", vec![finding("a.md", 3, "recieve")]);
+    printer.add("teh\n", vec![finding("b.md", 1, "teh")]);
+    assert_eq!(
+        group_by_word(&printer.pending, &printer.pending_context, &printer.opts),
+        vec![
+            "\"recieve\" (1)",
+            "    a.md:3:1",
+            "      2 | two",
+            "    > 3 | recieve",
+            "    --",
+            "\"teh\" (1)",
+            "    b.md:1:1",
+            "    > 1 | teh",
+            "    --",
+        ]
+    );
+}
+
+#[test]
+fn test_unique_per_file() {
+    let findings = vec![
+        finding("a.md", 3, "recieve"),
+        finding("a.md", 5, "teh"),
+        finding("a.md", 9, "recieve"),
+        finding("a.md", 12, "recieve"),
+    ];
+
+    let res: Vec<String> = unique_per_file(findings)
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+    assert_eq!(
+        res,
+        vec!["a.md:3:1 \"recieve\" (3 times)", "a.md:5:1 \"teh\""]
+    );
+}
+
+#[test]
+fn test_first_only() {
+    let mut printer = Printer::new(OutputOpts {
+        group_by: GroupBy::Word,
+        first_only: true,
+        ..Default::default()
+    });
+    printer.add(
+        "",
+        vec![finding("a.md", 3, "recieve"), finding("a.md", 5, "teh")],
+    );
+    printer.add(
+        "",
+        vec![finding("b.md", 1, "teh"), finding("b.md", 2, "wrold")],
+    );
+
+    let words: Vec<&str> = printer.pending.iter().map(|f| f.word.as_str()).collect();
+    assert_eq!(words, vec!["recieve", "teh", "wrold"]);
+}
+
+#[test]
+fn test_new_words() {
+    let mut seen = HashSet::new();
+    let mut note = finding("a.md", 4, "effect");
+    note.kind = Kind::Note;
+    let findings = vec![
+        finding("a.md", 3, "teh"),
+        note,
+        finding("a.md", 5, "recieve"),
+        finding("a.md", 9, "teh"),
+    ];
+    assert_eq!(new_words(&findings, &mut seen), vec!["teh", "recieve"]);
+
+    let findings = vec![finding("b.md", 1, "recieve"), finding("b.md", 2, "wrold")];
+    assert_eq!(new_words(&findings, &mut seen), vec!["wrold"]);
+}
+
+#[test]
+fn test_to_json() {
+    use crate::diagnostic::{Kind, Span};
+
+    // "ünïcöde 𝒳xyz" puts the word after a 4 byte, 2 utf-16 unit, char
+    let span = Span::new(13..16, 8..11, 9..12, 100, 90);
+    let d = Diagnostic::new("a.md".into(), 2, span, "xyz", Kind::Misspelling);
+
+    let mut opts = OutputOpts::default();
+    let j = to_json(&d, &opts);
+    assert_eq!(j["column"], 10);
+    assert_eq!(j["end_column"], 13);
+    assert_eq!(j["position_encoding"], "utf-16");
+    assert_eq!(j["byte_offset"], json!([113, 116]));
+    assert_eq!(j["kind"], "misspelling");
+    assert_eq!(j["fingerprint"], "");
+
+    opts.encoding = PositionEncoding::Utf8;
+    let j = to_json(&d, &opts);
+    assert_eq!(j["column"], 14);
+    assert_eq!(plain(&d, &opts), "a.md:2:14 \"xyz\"");
+
+    opts.line_base = 0;
+    opts.column_base = 0;
+    let j = to_json(&d, &opts);
+    assert_eq!(j["line"], 1);
+    assert_eq!(j["column"], 13);
+    assert_eq!(plain(&d, &opts), "a.md:1:13 \"xyz\"");
+}
+
+#[test]
+fn test_to_sarif() {
+    use crate::diagnostic::Span;
+
+    let span = Span::new(13..16, 8..11, 9..12, 100, 90);
+    let d = Diagnostic::new(".\\docs\\a.md".into(), 2, span, "xyz", Kind::Misspelling)
+        .with_suggestions(vec!["xyzzy".to_string()])
+        .with_fingerprint("ünïcöde 𝒳xyz", 0);
+    let id = d.fingerprint.clone();
+    let note = Diagnostic::new("/src/b.md".into(), 1, Span::default(), "its", Kind::Note);
+    let sarif = to_sarif(&[d, note]);
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "spel");
+    assert_eq!(run["tool"]["driver"]["rules"][3]["id"], "note");
+
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "misspelling");
+    assert_eq!(result["level"], "warning");
+    assert_eq!(result["partialFingerprints"]["spel/v1"], id);
+    assert_eq!(
+        result["message"]["text"],
+        "misspelling: \"xyz\" (did you mean \"xyzzy\"?)"
+    );
+    let loc = &result["locations"][0]["physicalLocation"];
+    assert_eq!(loc["artifactLocation"]["uri"], "docs/a.md");
+    assert_eq!(loc["region"]["startLine"], 2);
+    assert_eq!(loc["region"]["startColumn"], 10);
+    assert_eq!(loc["region"]["endColumn"], 13);
+    assert_eq!(loc["region"]["byteOffset"], 113);
+    assert_eq!(loc["region"]["byteLength"], 3);
+    assert_eq!(run["results"][1]["ruleIndex"], 3);
+    assert_eq!(run["results"][1]["level"], "note");
+    let loc = &run["results"][1]["locations"][0]["physicalLocation"];
+    assert_eq!(loc["artifactLocation"]["uri"], "file:///src/b.md");
+
+    assert_eq!(to_sarif(&[])["runs"][0]["results"], json!([]));
+}
+
+#[test]
+fn test_annotate() {
+    use crate::diagnostic::{Kind, Span};
+
+    let text = "first line\r\nwe recieve it\nlast";
+    let span = Span::new(3..10, 3..10, 3..10, 12, 12);
+    let d = Diagnostic::new("a.md".into(), 2, span, "recieve", Kind::Misspelling);
+
+    assert_eq!(line_of(&d, text), "we recieve it");
+
+    let mut opts = OutputOpts::default();
+    assert_eq!(annotate(&d, text, &opts), "a.md:2: we [recieve] it");
+
+    opts.color = true;
+    assert_eq!(
+        annotate(&d, text, &opts),
+        "a.md:2: we \x1b[1;31mrecieve\x1b[0m it"
+    );
+}
+
+#[test]
+fn test_caret() {
+    use crate::diagnostic::{Kind, Span};
+
+    let text = "first line\r\n\twe recieve it\nlast";
+    let span = Span::new(4..11, 4..11, 4..11, 12, 12);
+    let d = Diagnostic::new("a.md".into(), 2, span, "recieve", Kind::Misspelling)
+        .with_suggestions(vec!["receive".to_string()]);
+
+    let mut opts = OutputOpts::default();
+    assert_eq!(
+        caret(&d, text, &opts),
+        "\
+misspelling: \"recieve\"
+ --> a.md:2:5
+  |
+2 | \twe recieve it
+  | \t   ^^^^^^^ did you mean \"receive\"?
+"
+    );
+
+    opts.color = true;
+    let colored = caret(&d, text, &opts);
+    assert!(colored.starts_with("\x1b[1;31mmisspelling\x1b[0m: \"recieve\""));
+    assert!(colored.contains("\twe \x1b[1;31mrecieve\x1b[0m it"));
+    assert!(colored.contains("\x1b[1;31m^^^^^^^\x1b[0m"));
+
+    assert!(ColorWhen::Auto.enabled(true));
+    assert!(!ColorWhen::Auto.enabled(false));
+    assert!(ColorWhen::Always.enabled(false));
+    assert!(!ColorWhen::Never.enabled(true));
+}
+
+#[test]
+fn test_context() {
+    let lines = vec!["one", "two", "three", "four", "five"];
+    let mut opts = OutputOpts {
+        context: 1,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        context(&finding("a.md", 3, "three"), &lines, &opts),
+        vec!["  2 | two", "> 3 | three", "  4 | four"]
+    );
+
+    // Clamped at the start and end of the file
+    opts.context = 2;
+    assert_eq!(
+        context(&finding("a.md", 1, "one"), &lines, &opts),
+        vec!["> 1 | one", "  2 | two", "  3 | three"]
+    );
+    assert_eq!(
+        context(&finding("a.md", 5, "five"), &lines, &opts),
+        vec!["  3 | three", "  4 | four", "> 5 | five"]
+    );
+}
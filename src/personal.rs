@@ -0,0 +1,108 @@
+use crate::util::{extend_dict, get_ignore_file_contents, parse_path, write_atomic};
+use crate::wordlist::WordList;
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The user's own word list, kept across runs and edited with `spel add`
+/// and `spel remove`.  Its words are added to the dictionary for every
+/// check, like the repo's .spel/words.txt.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PersonalDict {
+    pub path: PathBuf,
+    pub words: Vec<String>,
+}
+
+impl PersonalDict {
+    /// Load the list, which is empty if the file doesn't exist yet
+    pub fn load(path: &Path) -> Self {
+        return Self {
+            path: path.to_owned(),
+            words: get_ignore_file_contents(path),
+        };
+    }
+
+    /// Add the word, returning false if it's already there
+    pub fn add(&mut self, word: &str) -> bool {
+        let word = word.trim();
+        if word.is_empty() || self.words.iter().any(|w| w == word) {
+            return false;
+        }
+        self.words.push(word.to_string());
+
+        return true;
+    }
+
+    /// Remove the word, returning false if it wasn't there
+    pub fn remove(&mut self, word: &str) -> bool {
+        let before = self.words.len();
+        self.words.retain(|w| w != word.trim());
+
+        return self.words.len() != before;
+    }
+
+    /// Write the list back out, sorted and without duplicates, creating
+    /// the directory it goes in the first time.  The file's comments and
+    /// sections are kept, with new words going at the end.
+    pub fn save(&self) -> Result<()> {
+        let path = parse_path(&self.path);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut list = WordList::load(&path)?;
+        list.retain(|w| self.words.iter().any(|word| word == w));
+        for word in &self.words {
+            list.add(word);
+        }
+
+        return write_atomic(&path, &list.text());
+    }
+
+    /// Add the words the dictionary doesn't have yet to the end of it
+    pub fn extend_dict(&self, dict: &'static str) -> &'static str {
+        return extend_dict(dict, &self.words);
+    }
+}
+
+#[test]
+fn test_personal_dict() {
+    use crate::util::get_words;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("share/spel/personal.txt");
+    let mut personal = PersonalDict::load(&path);
+    assert!(personal.words.is_empty());
+
+    assert!(personal.add("tokio"));
+    assert!(personal.add(" serde\n"));
+    assert!(!personal.add("tokio"));
+    assert!(!personal.add(""));
+    personal.save().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "serde\ntokio\n");
+
+    // A file edited by hand is tidied up the next time it's saved
+    fs::write(&path, "tokio\r\n\nserde\ntokio\n").unwrap();
+    let mut personal = PersonalDict::load(&path);
+    assert_eq!(personal.words, ["tokio", "serde", "tokio"]);
+    assert!(personal.remove("tokio"));
+    assert!(!personal.remove("tokio"));
+    personal.save().unwrap();
+    assert_eq!(PersonalDict::load(&path).words, ["serde"]);
+
+    let dict = personal.extend_dict("cat\nserdes\n");
+    assert_eq!(get_words(dict), ["cat", "serdes", "serde"]);
+
+    // Comments and sections are kept
+    fs::write(&path, "# Crates\nserde\n\n# Tools\nkubectl\n").unwrap();
+    let mut personal = PersonalDict::load(&path);
+    assert_eq!(personal.words, ["serde", "kubectl"]);
+    personal.add("cargo");
+    personal.remove("serde");
+    personal.save().unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Crates\n\n# Tools\ncargo\nkubectl\n"
+    );
+}
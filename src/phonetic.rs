@@ -0,0 +1,204 @@
+use crate::normalize::transliterate;
+use crate::util::{by_rank, dedup_variants, SuggestOpts};
+use std::{borrow::Cow, collections::HashMap};
+
+/// The least a word that sounds like the misspelling scores, however it's
+/// spelled.  Between them, they're ordered by their spelling.
+const SOUND_SCORE: f32 = 0.75;
+
+/// The Metaphone key for how a word sounds in English, like "FSXN" for
+/// both "physician" and "fizishun".  Words spelled the way they sound
+/// often have the same key as the word that was meant, however far apart
+/// the spellings are.
+pub fn metaphone(word: &str) -> String {
+    let plain = match word.is_ascii() {
+        true => Cow::Borrowed(word),
+        false => Cow::Owned(transliterate(word)),
+    };
+    let mut w: Vec<u8> = plain
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    // Doubled letters sound like 1, except for the C in "accept"
+    w.dedup_by(|b, a| a == b && *a != b'C');
+    if [b"KN", b"GN", b"PN", b"AE", b"WR"]
+        .iter()
+        .any(|p| w.starts_with(*p))
+    {
+        w.remove(0);
+    }
+    if w.starts_with(b"WH") {
+        w.remove(1);
+    }
+
+    let vowel = |b: Option<&u8>| matches!(b, Some(b'A' | b'E' | b'I' | b'O' | b'U'));
+    let soft = |b: Option<&u8>| matches!(b, Some(b'I' | b'E' | b'Y'));
+    let mut ret = String::new();
+    for (i, &c) in w.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| w[p]);
+        let next = w.get(i + 1);
+        let after = w.get(i + 2);
+        let key: &str = match c {
+            b'A' | b'E' | b'I' | b'O' | b'U' if i == 0 => std::str::from_utf8(&w[..1]).unwrap(),
+            b'A' | b'E' | b'I' | b'O' | b'U' => "",
+            // The silent B in "thumb"
+            b'B' if prev == Some(b'M') && i + 1 == w.len() => "",
+            b'C' if next == Some(&b'I') && after == Some(&b'A') => "X",
+            b'C' if next == Some(&b'H') && prev == Some(b'S') => "K",
+            b'C' if next == Some(&b'H') => "X",
+            b'C' if soft(next) => "S",
+            b'C' => "K",
+            b'D' if next == Some(&b'G') && soft(after) => "J",
+            b'D' => "T",
+            // The silent G in "night" and "sign"
+            b'G' if next == Some(&b'H') && after.is_some() && !vowel(after) => "",
+            b'G' if next == Some(&b'N') && (i + 2 == w.len() || w[i + 2..] == *b"NED") => "",
+            // The D of "edge" already sounds like J
+            b'G' if soft(next) && prev == Some(b'D') => "",
+            b'G' if soft(next) && prev != Some(b'G') => "J",
+            b'G' => "K",
+            // The H of a pair like "ch" or "ph" is part of its sound
+            b'H' if matches!(prev, Some(b'C' | b'S' | b'P' | b'T' | b'G')) => "",
+            b'H' if vowel(prev.as_ref()) && !vowel(next) => "",
+            b'K' if prev == Some(b'C') => "",
+            b'P' if next == Some(&b'H') => "F",
+            b'Q' => "K",
+            b'S' if next == Some(&b'H') => "X",
+            b'S' if next == Some(&b'I') && matches!(after, Some(b'O' | b'A')) => "X",
+            b'T' if next == Some(&b'I') && matches!(after, Some(b'O' | b'A')) => "X",
+            b'T' if next == Some(&b'H') => "0",
+            b'T' if next == Some(&b'C') && after == Some(&b'H') => "",
+            b'V' => "F",
+            b'W' | b'Y' if !vowel(next) => "",
+            b'X' if i == 0 => "S",
+            b'X' => "KS",
+            b'Z' => "S",
+            _ => std::str::from_utf8(&w[i..=i]).unwrap(),
+        };
+        ret.push_str(key);
+    }
+
+    return ret;
+}
+
+/// The words of a list by their Metaphone key, to look up the ones that
+/// sound like a misspelling
+#[derive(Debug, Default)]
+pub struct PhoneticIndex<'a> {
+    keys: HashMap<String, Vec<&'a str>>,
+}
+
+impl<'a> PhoneticIndex<'a> {
+    pub fn new(words: &[&'a str]) -> Self {
+        let mut keys: HashMap<String, Vec<&'a str>> = HashMap::new();
+        for word in words {
+            let key = metaphone(word);
+            if !key.is_empty() {
+                keys.entry(key).or_default().push(word);
+            }
+        }
+
+        return Self { keys };
+    }
+
+    /// Only index the words that sound like one of the misspellings, which
+    /// is quicker when there are just a few of them
+    pub fn for_misspellings(words: &[&'a str], misspellings: &[&str]) -> Self {
+        let mut keys: HashMap<String, Vec<&'a str>> = misspellings
+            .iter()
+            .map(|m| (metaphone(m), vec![]))
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+        for word in words {
+            if let Some(alikes) = keys.get_mut(&metaphone(word)) {
+                alikes.push(word);
+            }
+        }
+
+        return Self { keys };
+    }
+
+    /// The words with the same key as the word, in list order
+    pub fn sounds_like(&self, word: &str) -> &[&'a str] {
+        return self.keys.get(&metaphone(word)).map_or(&[], |w| w);
+    }
+
+    /// Blend the words that sound like the word into the suggestions
+    /// found for it, ranked by the options' metric
+    pub fn add_to(
+        &self,
+        word: &str,
+        found: Vec<(f32, &'a str)>,
+        suggest: &SuggestOpts,
+        top: usize,
+    ) -> Vec<(f32, &'a str)> {
+        let alikes = suggest.rank(word, self.sounds_like(word), top);
+        return blend(found, &alikes, top);
+    }
+}
+
+/// Blend the words that sound like the misspelling, with their scores,
+/// into the suggestions found by spelling, keeping the `top` best.  A word
+/// that sounds right is moved up to at least SOUND_SCORE, so one that's
+/// spelled very differently can still make the list.
+pub fn blend<'a>(
+    found: Vec<(f32, &'a str)>,
+    alikes: &[(f32, &'a str)],
+    top: usize,
+) -> Vec<(f32, &'a str)> {
+    let mut ret = found;
+    ret.extend(
+        alikes
+            .iter()
+            .map(|&(score, w)| (SOUND_SCORE + (1.0 - SOUND_SCORE) * score, w)),
+    );
+    ret.sort_by(by_rank);
+    dedup_variants(&mut ret);
+    ret.truncate(top);
+
+    return ret;
+}
+
+#[test]
+fn test_metaphone() {
+    assert_eq!(metaphone("physician"), "FSXN");
+    assert_eq!(metaphone("fizishun"), "FSXN");
+    assert_eq!(metaphone("Knight"), "NT");
+    assert_eq!(metaphone("thumb"), "0M");
+    assert_eq!(metaphone("school"), "SKL");
+    assert_eq!(metaphone("church"), "XRX");
+    assert_eq!(metaphone("accept"), "AKSPT");
+    assert_eq!(metaphone("edge"), "EJ");
+    assert_eq!(metaphone("nation"), "NXN");
+    assert_eq!(metaphone("Xavier"), "SFR");
+    assert_eq!(metaphone("whistle"), "WSTL");
+    assert_eq!(metaphone("definately"), metaphone("definitely"));
+    assert_eq!(metaphone("café"), "KF");
+    assert_eq!(metaphone("123"), "");
+}
+
+#[test]
+fn test_phonetic_blend() {
+    let words = ["fission", "physician", "Physician", "fishing", "cat"];
+    let index = PhoneticIndex::new(&words);
+    assert_eq!(index.sounds_like("fizishun"), ["physician", "Physician"]);
+    assert!(index.sounds_like("zzz").is_empty());
+    let index = PhoneticIndex::for_misspellings(&words, &["fizishun", "kat"]);
+    assert_eq!(index.sounds_like("fizishun"), ["physician", "Physician"]);
+    assert_eq!(index.sounds_like("kat"), ["cat"]);
+    assert!(index.sounds_like("fishing").is_empty());
+
+    let found = vec![(0.9, "fission"), (0.5, "fishing")];
+    let alikes = [(0.2, "physician")];
+    assert_eq!(
+        blend(found.clone(), &alikes, 5),
+        [(0.9, "fission"), (0.8, "physician"), (0.5, "fishing")]
+    );
+    assert_eq!(blend(found, &alikes, 1), [(0.9, "fission")]);
+    // The better of 2 forms of the same word
+    assert_eq!(
+        blend(vec![(0.9, "fishing")], &[(0.2, "fishing")], 5),
+        [(0.9, "fishing")]
+    );
+}
@@ -0,0 +1,123 @@
+use crate::util::tokenize;
+use std::{collections::HashMap, sync::Mutex};
+
+/// The multi-word entries ("ad hoc", "status quo") from the dictionary and
+/// ignore list, indexed by their first word
+#[derive(Debug, Default)]
+pub struct PhraseIndex {
+    by_first: HashMap<String, Vec<Vec<String>>>,
+    /// The number of times each phrase has been found, by the phrase
+    /// joined by spaces
+    found: Mutex<HashMap<String, usize>>,
+}
+
+impl PhraseIndex {
+    /// Build the index from any entries that contain whitespace, all
+    /// others are ignored
+    pub fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(entries: I) -> Self {
+        let mut by_first: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+
+        for entry in entries {
+            let entry = entry.as_ref();
+            if !entry.trim().contains(char::is_whitespace) {
+                continue;
+            }
+
+            let toks = tokenize(entry);
+            if toks.len() < 2 {
+                continue;
+            }
+
+            debug!("Adding phrase: {:?}", toks);
+            by_first.entry(toks[0].clone()).or_default().push(toks);
+        }
+
+        // Try the longest phrases first
+        for phrases in by_first.values_mut() {
+            phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        }
+
+        return Self {
+            by_first,
+            found: Mutex::default(),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.by_first.is_empty();
+    }
+
+    /// Return a mask of which tokens are part of a known phrase
+    pub fn covered<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<bool> {
+        let mut ret = vec![false; tokens.len()];
+        if self.is_empty() {
+            return ret;
+        }
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let rest = &tokens[i..];
+            let matched = self.by_first.get(rest[0].as_ref()).and_then(|phrases| {
+                phrases.iter().find(|p| {
+                    p.len() <= rest.len() && p.iter().zip(rest).all(|(a, b)| a == b.as_ref())
+                })
+            });
+
+            match matched {
+                Some(phrase) => {
+                    let len = phrase.len();
+                    ret[i..i + len].iter_mut().for_each(|c| *c = true);
+                    i += len;
+                    *self
+                        .found
+                        .lock()
+                        .unwrap()
+                        .entry(phrase.join(" "))
+                        .or_default() += 1;
+                }
+                None => i += 1,
+            }
+        }
+
+        return ret;
+    }
+
+    /// The number of times `covered()` has found the entry so far
+    pub fn count(&self, entry: &str) -> usize {
+        let found = self.found.lock().unwrap();
+        return found.get(&tokenize(entry).join(" ")).copied().unwrap_or(0);
+    }
+}
+
+#[test]
+fn test_phrase_covered() {
+    let entries: Vec<String> = ["ad hoc", "status quo", "Foo Bar Baz", "single"]
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    let idx = PhraseIndex::new(&entries);
+
+    let toks = tokenize("an ad hoc fix for the status quo of foo bar baz");
+    let covered = idx.covered(&toks);
+    let hits: Vec<&str> = toks
+        .iter()
+        .zip(&covered)
+        .filter(|(_, c)| **c)
+        .map(|(t, _)| t.as_str())
+        .collect();
+    assert_eq!(
+        hits,
+        vec!["ad", "hoc", "status", "quo", "foo", "bar", "baz"]
+    );
+
+    // Half a phrase doesn't count
+    assert_eq!(idx.covered(&tokenize("hoc ad")), vec![false, false]);
+
+    idx.covered(&tokenize("ad hoc"));
+    assert_eq!(idx.count("ad hoc"), 2);
+    assert_eq!(idx.count("Foo Bar Baz"), 1);
+    assert_eq!(idx.count("status  quo ante"), 0);
+    assert_eq!(PhraseIndex::new(&entries).count("ad hoc"), 0);
+
+    assert!(PhraseIndex::new(["single"]).is_empty());
+}
@@ -0,0 +1,79 @@
+use crate::util::{extend_dict, get_ignore_file_contents};
+use std::path::{Path, PathBuf};
+
+/// The directory in a repo that word lists are picked up from
+pub const PROJECT_DIR: &str = ".spel";
+
+/// The word lists committed to a repo under .spel/, so everyone who clones
+/// it checks with the same words without any setup
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProjectLists {
+    /// The .spel directory they were found in
+    pub dir: Option<PathBuf>,
+    /// From words.txt.  These are added to the dictionary, so they can be
+    /// suggested too.
+    pub words: Vec<String>,
+    /// From ignore.txt, which works like the --ignore-file
+    pub ignore: Vec<String>,
+}
+
+impl ProjectLists {
+    /// Look for a .spel directory in `start` or the ones above it, up to
+    /// the root of the repo
+    pub fn find(start: &Path) -> Self {
+        for dir in start.ancestors() {
+            let lists = dir.join(PROJECT_DIR);
+            if lists.is_dir() {
+                return Self::load(&lists);
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        return Self::default();
+    }
+
+    pub fn load(dir: &Path) -> Self {
+        return Self {
+            dir: Some(dir.to_owned()),
+            words: get_ignore_file_contents(&dir.join("words.txt")),
+            ignore: get_ignore_file_contents(&dir.join("ignore.txt")),
+        };
+    }
+
+    /// Add the words the dictionary doesn't have yet to the end of it
+    pub fn extend_dict(&self, dict: &'static str) -> &'static str {
+        return extend_dict(dict, &self.words);
+    }
+}
+
+#[test]
+fn test_project_lists() {
+    use crate::util::get_words;
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    let sub = dir.path().join("repo/docs/api");
+    fs::create_dir_all(&sub).unwrap();
+    assert_eq!(ProjectLists::find(&sub), ProjectLists::default());
+
+    let lists = dir.path().join("repo").join(PROJECT_DIR);
+    fs::create_dir(&lists).unwrap();
+    fs::write(lists.join("words.txt"), "tokio\ncat\n\nserde\n").unwrap();
+    fs::write(lists.join("ignore.txt"), "spel\n").unwrap();
+
+    let found = ProjectLists::find(&sub);
+    assert_eq!(found.dir, Some(lists.clone()));
+    assert_eq!(found.words, ["tokio", "cat", "serde"]);
+    assert_eq!(found.ignore, ["spel"]);
+
+    // Only words the dictionary doesn't have are added
+    let dict = found.extend_dict("cat\ndog\n");
+    assert_eq!(get_words(dict), ["cat", "dog", "tokio", "serde"]);
+    assert_eq!(ProjectLists::default().extend_dict("cat\n"), "cat\n");
+
+    // The search stops at the root of the repo
+    fs::create_dir(sub.join(".git")).unwrap();
+    assert_eq!(ProjectLists::find(&sub), ProjectLists::default());
+}
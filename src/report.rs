@@ -0,0 +1,174 @@
+use crate::diagnostic::{Diagnostic, Kind};
+use crate::util::{check_files, CheckOpts, Lookups, SkippedFile};
+use crate::wordset::WordSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+/// An unknown word found across a corpus, with where it was seen
+#[derive(Debug, Default, PartialEq)]
+pub struct VocabEntry {
+    pub word: String,
+    pub count: usize,
+    /// The first few file:line locations the word was seen at
+    pub examples: Vec<(PathBuf, u64)>,
+}
+
+/// The unknown words seen so far in a corpus
+#[derive(Debug, Default)]
+pub struct Vocab {
+    pub entries: HashMap<String, VocabEntry>,
+    /// How many example locations to keep for each word
+    pub max_examples: usize,
+}
+
+impl Vocab {
+    pub fn new(max_examples: usize) -> Self {
+        return Self {
+            entries: HashMap::new(),
+            max_examples,
+        };
+    }
+
+    /// Tally up the unknown words in a file's findings, the misspellings
+    /// and the words that only differ from a dictionary word by their
+    /// accents.  The others are of words that are spelled right.
+    pub fn add(&mut self, diags: &[Diagnostic]) {
+        for d in diags {
+            if !matches!(d.kind, Kind::Misspelling | Kind::Variant) {
+                continue;
+            }
+
+            let entry = self
+                .entries
+                .entry(d.word.clone())
+                .or_insert_with(|| VocabEntry {
+                    word: d.word.clone(),
+                    ..Default::default()
+                });
+            entry.count += d.count;
+            if entry.examples.len() < self.max_examples {
+                entry.examples.push((d.path.clone(), d.line));
+            }
+        }
+    }
+
+    /// Get the entries sorted by the most common words first
+    pub fn sorted(self) -> Vec<VocabEntry> {
+        let mut ret: Vec<VocabEntry> = self.entries.into_values().collect();
+        ret.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+
+        return ret;
+    }
+}
+
+/// Build the vocabulary report for the files from what checking them
+/// finds, sorted by the most common unknown words first
+pub fn vocab_report(
+    files: &[PathBuf],
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+    jobs: usize,
+    max_examples: usize,
+) -> (Vec<VocabEntry>, Vec<SkippedFile>) {
+    let mut vocab = Vocab::new(max_examples);
+    // Only the findings are needed, not the text
+    let opts = CheckOpts {
+        keep_text: false,
+        ..opts.clone()
+    };
+    let skipped = check_files(
+        files,
+        words,
+        ign_list,
+        &opts,
+        lookups,
+        jobs,
+        |_, _, diags| vocab.add(&diags),
+    );
+
+    return (vocab.sorted(), skipped);
+}
+
+pub fn print_vocab(vocab: &[VocabEntry]) {
+    let width = vocab
+        .iter()
+        .map(|v| v.word.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("WORD".len());
+
+    println!(
+        "{:>7}  {:<width$}  EXAMPLES",
+        "COUNT",
+        "WORD",
+        width = width
+    );
+    for v in vocab {
+        let examples: Vec<String> = v
+            .examples
+            .iter()
+            .map(|(f, l)| format!("{}:{}", f.display(), l))
+            .collect();
+
+        println!(
+            "{:>7}  {:<width$}  {}",
+            v.count,
+            v.word,
+            examples.join(", "),
+            width = width,
+        );
+    }
+}
+
+#[test]
+fn test_vocab_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("a.txt"), dir.path().join("b.md"));
+    std::fs::write(&a, "the catt sat\nspel the catt\ncatt dogg\n").unwrap();
+    std::fs::write(&b, "The catt\n\n```\ncode dogg\n```\n").unwrap();
+
+    let words = WordSet::new(&["the", "cat", "sat", "café"], Default::default());
+    let ign_list: HashSet<String> = ["spel".to_string()].into_iter().collect();
+    let opts = CheckOpts {
+        max_file_size: 1024,
+        fold_diacritics: true,
+        ..Default::default()
+    };
+    let lookups = Lookups::build(&words, &ign_list, &opts, None);
+
+    let files = [a.clone(), b.clone()];
+    let (vocab, skipped) = vocab_report(&files, &words, &ign_list, &opts, &lookups, 2, 2);
+    assert!(skipped.is_empty());
+    // Markdown code blocks are left out, the same as when checking
+    assert_eq!(
+        vocab,
+        [
+            VocabEntry {
+                word: "catt".to_string(),
+                count: 4,
+                examples: vec![(a.clone(), 1), (a.clone(), 2)],
+            },
+            VocabEntry {
+                word: "dogg".to_string(),
+                count: 1,
+                examples: vec![(a.clone(), 3)],
+            },
+        ]
+    );
+
+    // Variants are unknown too, but not the notes on known words
+    let mut vocab = Vocab::new(1);
+    let span = crate::diagnostic::Span::default();
+    let diag = |word: &str, kind: Kind| Diagnostic::new(b.clone(), 1, span.clone(), word, kind);
+    vocab.add(&[
+        diag("cafe", Kind::Variant),
+        diag("affect", Kind::Note),
+        diag("there", Kind::Confusion),
+    ]);
+    assert_eq!(vocab.entries.len(), 1);
+    assert_eq!(vocab.entries["cafe"].count, 1);
+}
@@ -0,0 +1,245 @@
+use crate::diagnostic::{Diagnostic, Kind, Span};
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+/// A line of the output of `spel -f --format json`.  Only what's needed to
+/// match findings up and show them is read.
+#[derive(Debug, Deserialize)]
+struct Entry {
+    path: String,
+    line: u64,
+    column: usize,
+    #[serde(default)]
+    end_column: Option<usize>,
+    word: String,
+    kind: String,
+    #[serde(default)]
+    suggestions: Vec<String>,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    count: Option<usize>,
+    /// Missing from results written before findings had them
+    #[serde(default)]
+    fingerprint: String,
+}
+
+impl Entry {
+    /// The diagnostic the entry was written from.  Its line and columns are
+    /// as they were written, in whatever encoding and base the run used.
+    fn into_diagnostic(self) -> Option<Diagnostic> {
+        let kind = [
+            Kind::Misspelling,
+            Kind::Variant,
+            Kind::Confusion,
+            Kind::Note,
+        ]
+        .into_iter()
+        .find(|k| k.to_string() == self.kind)?;
+        let cols = self.column..self.end_column.unwrap_or(self.column);
+        let span = Span::new(cols.clone(), cols.clone(), cols, 0, 0);
+
+        let mut ret = Diagnostic::new(self.path.into(), self.line, span, &self.word, kind)
+            .with_suggestions(self.suggestions)
+            .with_scope(self.scope.as_deref());
+        ret.note = self.note;
+        ret.count = self.count.unwrap_or(1);
+        ret.fingerprint = self.fingerprint;
+
+        return Some(ret);
+    }
+}
+
+/// What a finding is matched on between 2 runs: its fingerprint, or where
+/// it is and the word when it doesn't have one
+fn key(d: &Diagnostic) -> String {
+    if !d.fingerprint.is_empty() {
+        return d.fingerprint.clone();
+    }
+
+    return format!(
+        "{}:{}:{}\0{}",
+        d.path.display(),
+        d.line,
+        d.span.line_chars.start,
+        d.word
+    );
+}
+
+/// Render a finding read back from the results as file:line:col "word",
+/// with the line and column as they were written
+pub fn show_result(d: &Diagnostic) -> String {
+    return format!(
+        "{}:{}:{} {}",
+        d.path.display(),
+        d.line,
+        d.span.line_chars.start,
+        d.describe()
+    );
+}
+
+/// Read the findings from a file of JSON results, 1 per line
+pub fn read_results(path: &Path) -> Result<Vec<Diagnostic>> {
+    let mut ret = vec![];
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: Entry = match serde_json::from_str(line) {
+            Ok(e) => e,
+            Err(e) => bail!("{}:{}: bad finding: {}", path.display(), i + 1, e),
+        };
+        let kind = entry.kind.clone();
+        match entry.into_diagnostic() {
+            Some(d) => ret.push(d),
+            None => bail!("{}:{}: unknown kind \"{}\"", path.display(), i + 1, kind),
+        }
+    }
+
+    return Ok(ret);
+}
+
+/// How the findings of 2 runs compare, each in the order of its run
+#[derive(Debug, Default, PartialEq)]
+pub struct ResultsDiff {
+    /// Only in the new run
+    pub new: Vec<Diagnostic>,
+    /// Only in the old run
+    pub fixed: Vec<Diagnostic>,
+    /// In both, as they are in the new run
+    pub persisting: Vec<Diagnostic>,
+}
+
+/// Compare the findings of an old run with a new one.  A finding that's
+/// there more than once is matched up as many times as it's in both.
+pub fn diff_results(old: &[Diagnostic], new: &[Diagnostic]) -> ResultsDiff {
+    let mut ret = ResultsDiff::default();
+    let mut unmatched: HashMap<String, usize> = HashMap::new();
+    for f in old {
+        *unmatched.entry(key(f)).or_default() += 1;
+    }
+
+    for f in new {
+        match unmatched.get_mut(&key(f)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                ret.persisting.push(f.clone());
+            }
+            _ => ret.new.push(f.clone()),
+        }
+    }
+    // The old ones that weren't matched, last ones first, are fixed
+    for f in old.iter().rev() {
+        if let Some(count) = unmatched.get_mut(&key(f)).filter(|c| **c > 0) {
+            *count -= 1;
+            ret.fixed.push(f.clone());
+        }
+    }
+    ret.fixed.reverse();
+
+    return ret;
+}
+
+#[test]
+fn test_diff_results() {
+    let finding = |line: u64, word: &str, fingerprint: &str| {
+        let mut d = Diagnostic::new(
+            "a.md".into(),
+            line,
+            Span::new(1..4, 1..4, 1..4, 0, 0),
+            word,
+            Kind::Misspelling,
+        );
+        d.fingerprint = fingerprint.to_string();
+        return d;
+    };
+
+    // Lines were added above "teh", so it moved, but its fingerprint didn't
+    let old = [
+        finding(1, "recieve", "01"),
+        finding(3, "teh", "02"),
+        finding(4, "teh", "02"),
+        finding(7, "wrold", ""),
+    ];
+    let new = [
+        finding(5, "teh", "02"),
+        finding(7, "wrold", ""),
+        finding(8, "speling", "03"),
+    ];
+    let diff = diff_results(&old, &new);
+    assert_eq!(diff.new, [finding(8, "speling", "03")]);
+    assert_eq!(
+        diff.fixed,
+        [finding(1, "recieve", "01"), finding(4, "teh", "02")]
+    );
+    assert_eq!(
+        diff.persisting,
+        [finding(5, "teh", "02"), finding(7, "wrold", "")]
+    );
+
+    // Without a fingerprint, a finding that moves is new
+    let diff = diff_results(&[finding(7, "wrold", "")], &[finding(8, "wrold", "")]);
+    assert_eq!((diff.new.len(), diff.fixed.len()), (1, 1));
+
+    assert_eq!(show_result(&finding(2, "teh", "")), "a.md:2:1 \"teh\"");
+}
+
+#[test]
+fn test_read_results() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("results.json");
+    fs::write(
+        &path,
+        r#"{"path":"a.md","line":2,"column":3,"end_column":6,"word":"teh","kind":"misspelling","fingerprint":"ab"}
+
+{"path":"b.md","line":1,"column":1,"word":"wrold","kind":"misspelling"}
+"#,
+    )
+    .unwrap();
+    let found = read_results(&path).unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].fingerprint, "ab");
+    assert_eq!(found[1].fingerprint, "");
+    assert_eq!(show_result(&found[0]), "a.md:2:3 \"teh\"");
+
+    // What the run said about the finding is shown too
+    fs::write(
+        &path,
+        r#"{"path":"a.md","line":0,"column":4,"word":"affect","kind":"note","note":"to influence","count":2}
+{"path":"a.md","line":1,"column":0,"word":"teh","kind":"misspelling","suggestions":["the"]}
+"#,
+    )
+    .unwrap();
+    let found: Vec<String> = read_results(&path)
+        .unwrap()
+        .iter()
+        .map(show_result)
+        .collect();
+    assert_eq!(
+        found,
+        [
+            "a.md:0:4 \"affect\" (note: to influence) (2 times)",
+            "a.md:1:0 \"teh\" (did you mean \"the\"?)"
+        ]
+    );
+
+    fs::write(
+        &path,
+        r#"{"path":"a.md","line":1,"column":1,"word":"a","kind":"typo"}"#,
+    )
+    .unwrap();
+    let err = read_results(&path).unwrap_err().to_string();
+    assert!(
+        err.contains("results.json:1: unknown kind \"typo\""),
+        "{}",
+        err
+    );
+
+    fs::write(&path, "{\"path\":\"a.md\"}\n").unwrap();
+    let err = read_results(&path).unwrap_err().to_string();
+    assert!(err.contains("results.json:1: bad finding"), "{}", err);
+}
@@ -0,0 +1,189 @@
+#[cfg(test)]
+use crate::util::{find_word, within_distance};
+
+/// Only the first this many chars of a word are indexed, which keeps the
+/// number of deletes for long words down.  The candidates are checked
+/// against the whole word anyway.
+const PREFIX_LEN: usize = 7;
+
+/// The bits of an entry that hold the word's position in the list, the
+/// rest are the hash of the delete
+const INDEX_BITS: u32 = 24;
+
+/// A SymSpell style index of the words in a list: every string that can be
+/// made by deleting up to `max_distance` chars from (the start of) each
+/// word.  A word within that many edits of a misspelling shares one of
+/// these deletes with it, so the candidates for a misspelling are found by
+/// looking its own deletes up, rather than going through the whole list.
+#[derive(Debug, Default)]
+pub struct DeleteIndex {
+    /// The hash of each delete and the position of its word, packed into
+    /// one number and sorted, so the words for a delete are together
+    entries: Vec<u64>,
+    pub max_distance: usize,
+}
+
+impl DeleteIndex {
+    /// Build the index over the words, which have to be the ones it's
+    /// searched with later
+    pub fn new(words: &[&str], max_distance: usize) -> Self {
+        assert!(words.len() < 1 << INDEX_BITS, "too many words to index");
+
+        let mut entries = vec![];
+        for (i, word) in words.iter().enumerate() {
+            for key in delete_keys(word, max_distance) {
+                entries.push(key << INDEX_BITS | i as u64);
+            }
+        }
+        entries.sort_unstable();
+
+        return Self {
+            entries,
+            max_distance,
+        };
+    }
+
+    /// The words that could be within `max_distance` edits of the word, at
+    /// most the distance the index was built for, in list order.  Some of
+    /// them will be further off.
+    pub fn candidates<'a>(
+        &self,
+        word: &str,
+        words: &[&'a str],
+        max_distance: usize,
+    ) -> Vec<&'a str> {
+        let mut found = vec![];
+        for key in delete_keys(word, max_distance.min(self.max_distance)) {
+            let start = self.entries.partition_point(|e| e >> INDEX_BITS < key);
+            found.extend(
+                self.entries[start..]
+                    .iter()
+                    .take_while(|e| *e >> INDEX_BITS == key)
+                    .map(|e| (e & ((1 << INDEX_BITS) - 1)) as usize),
+            );
+        }
+        found.sort_unstable();
+        found.dedup();
+
+        return found.into_iter().map(|i| words[i]).collect();
+    }
+
+    /// Find the `top` most similar words out of the ones within
+    /// `max_distance` edits of the word (at most the index's), the same as
+    /// find_word() does over those words
+    #[cfg(test)]
+    pub fn find<'a>(
+        &self,
+        word: &str,
+        words: &[&'a str],
+        top: usize,
+        max_distance: usize,
+    ) -> Vec<(f32, &'a str)> {
+        let max_distance = max_distance.min(self.max_distance);
+        let near: Vec<&'a str> = self
+            .candidates(word, words, max_distance)
+            .into_iter()
+            .filter(|w| within_distance(word, w, max_distance))
+            .collect();
+
+        return find_word(word, &near, top);
+    }
+}
+
+/// The hashes of every string made by deleting up to `max` chars from
+/// the start of the word, including the start itself.  The strings are
+/// never built, the hash just skips the deleted chars.
+fn delete_keys(word: &str, max: usize) -> Vec<u64> {
+    let chars: Vec<char> = word.chars().take(PREFIX_LEN).collect();
+    let mut ret = vec![];
+    add_keys(&chars, 0, max, &mut vec![], &mut ret);
+    ret.sort_unstable();
+    ret.dedup();
+
+    return ret;
+}
+
+/// Add the key with the chars in `skip` deleted, and then the ones with
+/// another char after those deleted as well
+fn add_keys(chars: &[char], from: usize, max: usize, skip: &mut Vec<usize>, ret: &mut Vec<u64>) {
+    ret.push(hash_except(chars, skip));
+    if skip.len() == max {
+        return;
+    }
+    for i in from..chars.len() {
+        skip.push(i);
+        add_keys(chars, i + 1, max, skip, ret);
+        skip.pop();
+    }
+}
+
+/// FNV-1a of the chars, leaving out the ones in `skip`.  It's quick for
+/// short strings and the same on every run.
+fn hash_except(chars: &[char], skip: &[usize]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    let mut buf = [0; 4];
+    for (i, c) in chars.iter().enumerate() {
+        if skip.contains(&i) {
+            continue;
+        }
+        for b in c.encode_utf8(&mut buf).bytes() {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    // Leave room for the word's position
+    return h >> INDEX_BITS;
+}
+
+#[test]
+fn test_delete_keys() {
+    let key = |s: &str| hash_except(&s.chars().collect::<Vec<_>>(), &[]);
+    let mut want: Vec<u64> = ["at", "ca", "cat", "ct"].map(key).to_vec();
+    want.sort_unstable();
+    assert_eq!(delete_keys("cat", 1), want);
+    assert_eq!(delete_keys("cat", 2).len(), 7);
+    // The same string deleted 2 ways is only there once
+    assert_eq!(delete_keys("aab", 1).len(), 3);
+    assert_eq!(delete_keys("naïve", 1).len(), 6);
+    assert_eq!(delete_keys("spelling", 0), [key("spellin")]);
+}
+
+#[test]
+fn test_delete_index() {
+    use crate::util::{dict_text, get_words, BUILTIN_DICT};
+
+    let words = get_words(dict_text(BUILTIN_DICT));
+    let sample: Vec<&str> = words.iter().step_by(97).copied().collect();
+    let index = DeleteIndex::new(&sample, 2);
+
+    // The same as filtering the whole list by distance
+    for word in [
+        "recieve",
+        "teh",
+        "a",
+        "spelling",
+        "xyzzy",
+        "naïve",
+        "acommodation",
+    ] {
+        for max in [1, 2] {
+            let near: Vec<&str> = sample
+                .iter()
+                .copied()
+                .filter(|w| within_distance(word, w, max))
+                .collect();
+            assert_eq!(
+                index.find(word, &sample, 5, max),
+                find_word(word, &near, 5),
+                "{} max {}",
+                word,
+                max
+            );
+        }
+    }
+
+    // The distance is capped at the one the index was built for
+    let index = DeleteIndex::new(&["cat", "coat", "boats"], 1);
+    assert_eq!(index.find("cat", &["cat", "coat", "boats"], 5, 3).len(), 2);
+}
@@ -0,0 +1,94 @@
+/// Misspellings that are common enough, and have only 1 sensible
+/// correction, that they're safe to fix automatically.  A correction is
+/// always a single word, so that it can take the misspelling's place
+/// without anyone looking, like "alot" can't.
+pub const COMMON_TYPOS: &[(&str, &str)] = &[
+    ("acheive", "achieve"),
+    ("adress", "address"),
+    ("arguement", "argument"),
+    ("basicly", "basically"),
+    ("becuase", "because"),
+    ("begining", "beginning"),
+    ("beleive", "believe"),
+    ("buisness", "business"),
+    ("catagory", "category"),
+    ("collegue", "colleague"),
+    ("comming", "coming"),
+    ("commited", "committed"),
+    ("completly", "completely"),
+    ("concensus", "consensus"),
+    ("concious", "conscious"),
+    ("definately", "definitely"),
+    ("embarass", "embarrass"),
+    ("existance", "existence"),
+    ("familar", "familiar"),
+    ("finaly", "finally"),
+    ("foriegn", "foreign"),
+    ("fourty", "forty"),
+    ("freind", "friend"),
+    ("goverment", "government"),
+    ("grammer", "grammar"),
+    ("happend", "happened"),
+    ("harrass", "harass"),
+    ("hieght", "height"),
+    ("independant", "independent"),
+    ("interupt", "interrupt"),
+    ("knowlege", "knowledge"),
+    ("mispell", "misspell"),
+    ("neccessary", "necessary"),
+    ("neice", "niece"),
+    ("occassion", "occasion"),
+    ("occurance", "occurrence"),
+    ("occured", "occurred"),
+    ("peice", "piece"),
+    ("perseverence", "perseverance"),
+    ("persistant", "persistent"),
+    ("posession", "possession"),
+    ("posible", "possible"),
+    ("reccomend", "recommend"),
+    ("recieve", "receive"),
+    ("recomend", "recommend"),
+    ("religous", "religious"),
+    ("remeber", "remember"),
+    ("responsability", "responsibility"),
+    ("rythm", "rhythm"),
+    ("sentance", "sentence"),
+    ("seperate", "separate"),
+    ("shedule", "schedule"),
+    ("sieze", "seize"),
+    ("similiar", "similar"),
+    ("speach", "speech"),
+    ("strenght", "strength"),
+    ("suprise", "surprise"),
+    ("tommorow", "tomorrow"),
+    ("tounge", "tongue"),
+    ("truely", "truly"),
+    ("usally", "usually"),
+    ("vaccuum", "vacuum"),
+    ("vehical", "vehicle"),
+    ("writting", "writing"),
+];
+
+/// Look up the correction for a common typo
+pub fn common_typo(word: &str) -> Option<&'static str> {
+    return COMMON_TYPOS
+        .iter()
+        .find(|(typo, _)| *typo == word)
+        .map(|(_, fix)| *fix);
+}
+
+#[test]
+fn test_common_typo() {
+    assert_eq!(common_typo("recieve"), Some("receive"));
+    assert_eq!(common_typo("receive"), None);
+
+    // Keep the table sorted, and make sure none of the corrections are
+    // themselves typos or more than one word
+    for pair in COMMON_TYPOS.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{} is out of order", pair[1].0);
+    }
+    for (_, fix) in COMMON_TYPOS {
+        assert_eq!(common_typo(fix), None);
+        assert!(!fix.contains(' '), "{} is more than one word", fix);
+    }
+}
@@ -1,51 +1,422 @@
+use crate::bktree::BkTree;
+use crate::buckets::WordBuckets;
+use crate::compound::{split_compound, CompoundConfig};
+use crate::config::{glob_matches, relative_path};
+use crate::confusables::{confusable_hint, real_word_error};
+use crate::diagnostic::{fingerprint, Diagnostic, Kind, Span};
+use crate::distance::{KeyboardScorer, Metric, Scorer};
+use crate::filter::{CodeSpanFilter, CommentsFilter, Filter, LineFilter, MarkdownOpts};
+use crate::gitignore::Gitignore;
+use crate::keyboard::Layout;
+use crate::modeline::Modeline;
+use crate::ngram::BigramModel;
+use crate::normalize::{build_folded, find_variant, transliterate};
+use crate::phonetic::PhoneticIndex;
+use crate::phrase::PhraseIndex;
+use crate::symspell::DeleteIndex;
+use crate::wordlist::WordList;
+use crate::wordset::{fold_case, Caseless, SetImpl, WordSet};
 use anyhow::Result;
-use difflib::sequencematcher::SequenceMatcher;
+use bumpalo::Bump;
+use clap::ValueEnum;
+use memchr::memchr;
+use serde_json::json;
 use std::{
-    collections::HashSet,
-    env,
-    fs::File,
-    io::{BufRead, BufReader, Lines, Read},
-    path::PathBuf,
-    vec,
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    env, fmt,
+    fs::{self, File},
+    hash::{Hash, RandomState},
+    io::{self, BufRead, BufReader, Cursor, Lines, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc, Condvar, Mutex, RwLock,
+    },
+    thread, vec,
 };
 
-/// This processes the dictionary file stored as bytes in the binary itself
-pub fn get_words(fbytes: &[u8]) -> Vec<String> {
-    let mut ret: Vec<String> = Vec::new();
-    let mut buf: Vec<u8> = Vec::new();
+/// The English dictionary that's used when no other is given
+pub static BUILTIN_DICT: &[u8] = include_bytes!("../english.txt");
 
-    let nl: u8 = b'\n';
-    for c in fbytes {
-        if c == &nl {
-            ret.push(String::from_utf8_lossy(&buf).to_string());
-            buf = vec![];
-        } else {
-            buf.push(*c);
+/// Get the text of a dictionary that lives for the whole run, like the one
+/// embedded in the binary or a file that's been leaked.  The words are
+/// borrowed from it rather than each being allocated.
+pub fn dict_text(fbytes: &'static [u8]) -> &'static str {
+    return match std::str::from_utf8(fbytes) {
+        Ok(text) => text,
+        Err(_) => String::from_utf8_lossy(fbytes).into_owned().leak(),
+    };
+}
+
+/// Put the dictionaries together into one, leaving out the words an
+/// earlier one already has.  The text is kept for the whole run, like the
+/// dictionaries themselves.
+pub fn merge_dicts(texts: &[&str]) -> &'static str {
+    let mut seen = HashSet::new();
+    let mut ret = String::new();
+    for word in texts.iter().flat_map(|t| get_words(t)) {
+        if seen.insert(word) {
+            ret.push_str(word);
+            ret.push('\n');
         }
     }
 
-    if buf.len() > 0 {
-        ret.push(String::from_utf8_lossy(&buf).to_string());
+    return ret.leak();
+}
+
+/// Add the words the dictionary doesn't have yet to the end of it.  The
+/// text is kept for the whole run, like the dictionary's.
+pub fn extend_dict(dict: &'static str, words: &[String]) -> &'static str {
+    let mut known = WordBuckets::new(dict);
+    let new: Vec<&str> = words
+        .iter()
+        .map(|w| w.as_str())
+        .filter(|w| !known.knows(w))
+        .collect();
+    if new.is_empty() {
+        return dict;
+    }
+
+    let text = format!("{}\n{}\n", dict.trim_end_matches('\n'), new.join("\n"));
+    return text.leak();
+}
+
+/// This splits the dictionary text into its words, 1 per line
+pub fn get_words(text: &str) -> Vec<&str> {
+    let mut ret: Vec<&str> = text.split('\n').collect();
+    if ret.last() == Some(&"") {
+        ret.pop();
     }
 
     return ret;
 }
 
-pub fn find_word<'a>(word: &'a str, word_list: &'a Vec<String>) -> Vec<(f32, &'a str)> {
-    let mut ret: Vec<(f32, &str)> = Vec::new();
+/// The similarity ratio for a number of matching bytes, the same way
+/// SequenceMatcher works it out, so the bounds compare exactly
+pub fn ratio_for(matches: usize, total_len: usize) -> f32 {
+    if total_len == 0 {
+        return 1.0;
+    }
 
-    let mut seq = SequenceMatcher::new(word, &word_list[0]);
-    for word in word_list {
-        seq.set_second_seq(word);
-        ret.push((seq.ratio(), word));
+    return 2.0 * matches as f32 / total_len as f32;
+}
+
+/// The order suggestions are ranked in: the best ratio first, and ties in
+/// alphabetical order, so the results don't depend on the order of the
+/// dictionary
+pub fn by_rank(a: &(f32, &str), b: &(f32, &str)) -> Ordering {
+    return b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1));
+}
+
+/// Check whether 2 words only differ by case, like "American" and
+/// "american", which makes them the same suggestion
+pub fn same_word(a: &str, b: &str) -> bool {
+    return a == b || a.to_lowercase() == b.to_lowercase();
+}
+
+/// Keep only the first of the matches that are the same word
+pub fn dedup_variants(found: &mut Vec<(f32, &str)>) {
+    let mut seen = HashSet::new();
+    found.retain(|f| seen.insert(f.1.to_lowercase()));
+}
+
+/// Find the `top` most similar words in the list by the difflib ratio, the
+/// same as find_scored() with the ratio metric
+pub fn find_word<'a>(word: &str, word_list: &[&'a str], top: usize) -> Vec<(f32, &'a str)> {
+    return find_scored(Metric::Ratio.scorer(word).as_mut(), word_list, top);
+}
+
+/// Find the `top` most similar words in the list, best first, as ranked by
+/// `by_rank()` with the scorer's scores, counting words that only differ by
+/// case once.  Candidates the scorer can rule out with a quick look are
+/// dropped when they can't beat the worst of the best so far, so the full
+/// score is only worked out for a few of them.
+pub fn find_scored<'a: 'b, 'b>(
+    scorer: &mut dyn Scorer<'b>,
+    word_list: &[&'a str],
+    top: usize,
+) -> Vec<(f32, &'a str)> {
+    let mut ret: Vec<(f32, &str)> = Vec::with_capacity(top + 1);
+    if top == 0 {
+        return ret;
+    }
+
+    for cand in word_list {
+        // A candidate has to beat the worst so far, which a tie does if it
+        // comes first alphabetically
+        let worst = ret.get(top - 1).copied();
+        let beats = |ratio: f32| match worst {
+            Some(w) => by_rank(&(ratio, cand), &w) == Ordering::Less,
+            None => true,
+        };
+        if !scorer.could_beat(cand, &beats) {
+            continue;
+        }
+
+        let ratio = scorer.score(cand);
+        if !beats(ratio) {
+            continue;
+        }
+        // Only the better of 2 forms of the same word is kept
+        if let Some(i) = ret.iter().position(|r| same_word(r.1, cand)) {
+            if by_rank(&ret[i], &(ratio, cand)) == Ordering::Less {
+                continue;
+            }
+            ret.remove(i);
+        }
+
+        let pos = ret.partition_point(|r| by_rank(r, &(ratio, cand)) == Ordering::Less);
+        ret.insert(pos, (ratio, cand));
+        ret.truncate(top);
     }
-    ret.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
 
     return ret;
 }
 
+/// Check whether 2 sequences are within `max` edits of each other, counting
+/// an insertion, deletion or substitution as 1 edit.  This gives up as
+/// soon as a row of the table is all over `max`.
+fn seq_within<T: PartialEq>(a: &[T], b: &[T], max: usize) -> bool {
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let sub = prev[j] + usize::from(x != y);
+            cur[j + 1] = sub.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        if cur.iter().all(|&d| d > max) {
+            return false;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    return prev[b.len()] <= max;
+}
+
+/// Check whether 2 words are within `max` edits of each other, counting in
+/// chars
+pub fn within_distance(a: &str, b: &str, max: usize) -> bool {
+    // A char is 4 bytes at most
+    if a.len().abs_diff(b.len()) > max * 4 {
+        return false;
+    }
+    if a.is_ascii() && b.is_ascii() {
+        return seq_within(a.as_bytes(), b.as_bytes(), max);
+    }
+
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    return seq_within(&a, &b, max);
+}
+
+/// How suggestions are found in the dictionary
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SuggestIndex {
+    /// Compare the misspelling with every word
+    #[default]
+    Scan,
+    /// Look the candidates up in an index of the words with up to
+    /// --max-distance (2 by default) letters deleted, built at startup
+    Symspell,
+    /// Search a BK-tree of the words for the ones within --max-distance
+    /// (2 by default) edits, built at startup
+    BkTree,
+}
+
+/// How far off suggestions can be with an index, if --max-distance isn't
+/// given
+pub const INDEX_DISTANCE: usize = 2;
+
+/// An index built over the words for finding suggestions
+enum Index {
+    Symspell(DeleteIndex),
+    BkTree(BkTree),
+}
+
+/// How many suggestions to find for a misspelling, and how far off they
+/// can be
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuggestOpts {
+    /// The most suggestions to show
+    pub top: usize,
+    /// Only suggest words within this many edits of the misspelling
+    pub max_distance: Option<usize>,
+    /// What's used by a `Suggester` to find them
+    pub index: SuggestIndex,
+    /// How they're ranked
+    pub metric: Metric,
+    /// Add the words that sound like the misspelling, however it's spelled
+    pub phonetic: bool,
+    /// Rank letters changed to the ones on the keys next to them higher,
+    /// on this layout
+    pub keyboard: Option<Layout>,
+}
+
+impl SuggestOpts {
+    pub fn new(top: usize) -> Self {
+        return Self {
+            top,
+            max_distance: None,
+            index: SuggestIndex::Scan,
+            metric: Metric::Ratio,
+            phonetic: false,
+            keyboard: None,
+        };
+    }
+
+    /// Find the `top` most similar words in the list, like `find_word()`,
+    /// but only out of the ones close enough
+    pub fn find<'a>(&self, word: &str, word_list: &[&'a str]) -> Vec<(f32, &'a str)> {
+        return self.find_n(word, word_list, self.top);
+    }
+
+    /// The same as `find()`, for `top` words rather than the option's
+    pub fn find_n<'a>(&self, word: &str, word_list: &[&'a str], top: usize) -> Vec<(f32, &'a str)> {
+        let Some(max) = self.max_distance else {
+            return self.rank(word, word_list, top);
+        };
+        let near: Vec<&str> = word_list
+            .iter()
+            .copied()
+            .filter(|w| self.within(word, w, max))
+            .collect();
+
+        return self.rank(word, &near, top);
+    }
+
+    /// Check whether the words are within `max` edits of each other, by
+    /// the metric's idea of an edit
+    pub fn within(&self, a: &str, b: &str, max: usize) -> bool {
+        return self.metric.within(a, b, max);
+    }
+
+    /// The `top` most similar words in the list, by the metric
+    pub fn rank<'a>(&self, word: &str, word_list: &[&'a str], top: usize) -> Vec<(f32, &'a str)> {
+        return find_scored(self.scorer(word).as_mut(), word_list, top);
+    }
+
+    /// What scores the candidates for the word: the metric, plus the
+    /// keyboard's bonus if there's a layout
+    pub fn scorer<'b>(&self, word: &'b str) -> Box<dyn Scorer<'b> + 'b> {
+        let scorer = self.metric.scorer(word);
+        return match self.keyboard {
+            Some(layout) => Box::new(KeyboardScorer::new(scorer, word, layout)),
+            None => scorer,
+        };
+    }
+}
+
+/// Adds suggestions to the misspellings found in files, only looking for
+/// them once for each word
+pub struct SuggestCache<'a> {
+    suggester: Suggester<'a>,
+    cache: HashMap<String, Vec<String>>,
+}
+
+impl<'a> SuggestCache<'a> {
+    pub fn new(words: &'a [&'a str], opts: SuggestOpts) -> Self {
+        return Self {
+            suggester: Suggester::new(words, opts),
+            cache: HashMap::new(),
+        };
+    }
+
+    /// Fill in the suggestions for a misspelling that doesn't have any yet
+    pub fn add_to(&mut self, d: &mut Diagnostic) {
+        if d.kind != Kind::Misspelling || !d.suggestions.is_empty() {
+            return;
+        }
+        let suggester = &self.suggester;
+        d.suggestions = self
+            .cache
+            .entry(d.word.clone())
+            .or_insert_with(|| {
+                suggester
+                    .find(&d.word)
+                    .into_iter()
+                    .map(|(_, w)| w.to_string())
+                    .collect()
+            })
+            .clone();
+    }
+}
+
+/// Finds suggestions in a list of words, using an index over them if the
+/// options ask for one, which is built once up front
+pub struct Suggester<'a> {
+    pub words: &'a [&'a str],
+    pub opts: SuggestOpts,
+    index: Option<Index>,
+    phonetic: Option<PhoneticIndex<'a>>,
+}
+
+impl<'a> Suggester<'a> {
+    pub fn new(words: &'a [&'a str], opts: SuggestOpts) -> Self {
+        let index = match opts.index {
+            SuggestIndex::Scan => None,
+            SuggestIndex::Symspell => Some(Index::Symspell(DeleteIndex::new(
+                words,
+                opts.max_distance.unwrap_or(INDEX_DISTANCE),
+            ))),
+            SuggestIndex::BkTree => Some(Index::BkTree(BkTree::new(words))),
+        };
+        let phonetic = opts.phonetic.then(|| PhoneticIndex::new(words));
+
+        return Self {
+            words,
+            opts,
+            index,
+            phonetic,
+        };
+    }
+
+    /// Find the `top` most similar words, the same as `SuggestOpts::find()`
+    /// unless there's an index, which only finds the ones within its
+    /// distance
+    pub fn find(&self, word: &str) -> Vec<(f32, &'a str)> {
+        return self.find_n(word, self.opts.top);
+    }
+
+    /// The same as `find()`, for `top` words rather than the option's
+    pub fn find_n(&self, word: &str, top: usize) -> Vec<(f32, &'a str)> {
+        let found = self.find_spelled(word, top);
+        return match &self.phonetic {
+            Some(phonetic) => phonetic.add_to(word, found, &self.opts, top),
+            None => found,
+        };
+    }
+
+    /// The suggestions by spelling alone
+    fn find_spelled(&self, word: &str, top: usize) -> Vec<(f32, &'a str)> {
+        let max = self.opts.max_distance.unwrap_or(INDEX_DISTANCE);
+        let near = match &self.index {
+            Some(Index::Symspell(index)) => index.candidates(word, self.words, max),
+            // The tree is by Levenshtein distance, where a swap is 2 edits
+            Some(Index::BkTree(tree)) => match self.opts.metric {
+                Metric::Damerau => tree.within(word, self.words, max * 2),
+                _ => tree.within(word, self.words, max),
+            },
+            None => return self.opts.find_n(word, self.words, top),
+        };
+        let near: Vec<&str> = near
+            .into_iter()
+            .filter(|w| self.opts.within(word, w, max))
+            .collect();
+
+        return self.opts.rank(word, &near, top);
+    }
+}
+
 /// Convert a word_list to a hashset -- destructive
-pub fn to_hashset(word_list: Vec<String>) -> HashSet<String> {
+pub fn to_hashset<T: Eq + Hash>(word_list: Vec<T>) -> HashSet<T> {
     let mut ret = HashSet::new();
 
     for word in word_list {
@@ -55,8 +426,8 @@ pub fn to_hashset(word_list: Vec<String>) -> HashSet<String> {
     return ret;
 }
 
-pub fn read_lines(filename: &PathBuf) -> Result<Lines<BufReader<File>>> {
-    let file = File::open(&filename)?;
+pub fn read_lines(filename: &Path) -> Result<Lines<BufReader<File>>> {
+    let file = File::open(filename)?;
 
     return Ok(BufReader::new(file).lines());
 }
@@ -64,13 +435,13 @@ pub fn read_lines(filename: &PathBuf) -> Result<Lines<BufReader<File>>> {
 /// Check that the token actually looks like a word, return true if it looks
 /// at least somewhat legit
 pub fn check_token(token: &str) -> bool {
-    if token.len() == 0 {
+    if token.is_empty() {
         return false;
     }
 
     let mut ok = false;
     for c in token.chars() {
-        if c.is_ascii_alphabetic() {
+        if c.is_alphabetic() {
             ok = true;
             break;
         }
@@ -79,115 +450,1240 @@ pub fn check_token(token: &str) -> bool {
     return ok;
 }
 
-pub fn strip_apost(word: &str) -> String {
-    let mut ret = word.to_string();
-    if ret.ends_with("'s") {
-        // Strip off apostrophe s and eval the regular ret
-        ret = ret.strip_suffix("'s").unwrap().to_string();
+pub fn strip_apost(word: &str) -> &str {
+    // Strip off apostrophe s and eval the regular word
+    let ret = word.strip_suffix("'s").unwrap_or(word);
+
+    // Strip trailing apostrophes
+    return ret.strip_suffix('\'').unwrap_or(ret);
+}
+
+/// go through the line and return the words, removing any special chars
+pub fn tokenize(line: &str) -> Vec<String> {
+    return tokenize_spans(line).into_iter().map(|(_, w)| w).collect();
+}
+
+/// Same as `tokenize()`, but also return the byte range of each word in the
+/// original line
+pub fn tokenize_spans(line: &str) -> Vec<(Range<usize>, String)> {
+    let mut ret = vec![];
+    for_each_token(line, |span, word| {
+        ret.push((span, fold_case(&word).into_owned()))
+    });
+
+    return ret;
+}
+
+/// Same as `tokenize_spans()`, but the words keep their case and are
+/// borrowed from the line.  Only words with filler in them need copying,
+/// which goes in the arena.
+pub fn tokenize_spans_in<'b>(line: &'b str, arena: &'b Bump) -> Vec<(Range<usize>, &'b str)> {
+    let mut ret = vec![];
+    for_each_token(line, |span, word| {
+        let word = match word {
+            Cow::Borrowed(w) => w,
+            Cow::Owned(w) => &*arena.alloc_str(&w),
+        };
+        ret.push((span, word));
+    });
+
+    return ret;
+}
+
+/// Call `f` with the byte range and text of each word in the line, which
+/// is borrowed from the line unless filler had to be taken out of it
+fn for_each_token<'l, F: FnMut(Range<usize>, Cow<'l, str>)>(line: &'l str, mut f: F) {
+    let mut start = None;
+    let mut filler = false;
+
+    // Add the word if it looks legit, with the span of the word trimmed
+    // down to match any stripped apostrophes
+    let mut push = |start: usize, end: usize, filler: bool| {
+        let text = &line[start..end];
+        let tmp = match filler {
+            true => Cow::Owned(text.replace('\0', "")),
+            false => Cow::Borrowed(text),
+        };
+        if !check_token(&tmp) {
+            return;
+        }
+
+        let stripped = tmp.len() - strip_apost(&tmp).len();
+        let word = match tmp {
+            Cow::Borrowed(w) => Cow::Borrowed(strip_apost(w)),
+            Cow::Owned(w) => Cow::Owned(strip_apost(&w).to_string()),
+        };
+        f(start..end - stripped, word);
+    };
+
+    for (i, c) in line.char_indices() {
+        // Filler from a filter, like the rest of a decoded HTML entity
+        if c == '\0' {
+            filler |= start.is_some();
+            continue;
+        }
+
+        // Alphabetic chars, dashes and apostrophes are ok
+        if c.is_alphanumeric() || c == '-' || c == '\'' {
+            if start.is_none() {
+                (start, filler) = (Some(i), false);
+            }
+        } else if let Some(s) = start.take() {
+            // If we get here, we've found a word boundary of some sort
+            push(s, i, filler);
+        }
     }
 
-    if ret.ends_with("'") {
-        // Strip trailing apostrophes
-        ret = ret.strip_suffix("'").unwrap().to_string();
+    if let Some(s) = start {
+        push(s, line.len(), filler);
     }
+}
 
-    return ret;
+/// Make the replacement follow the casing of the original word: all caps,
+/// capitalized or as is
+pub fn match_case(orig: &str, repl: &str) -> String {
+    let mut letters = orig.chars().filter(|c| c.is_alphabetic());
+    let first_upper = letters.next().is_some_and(|c| c.is_uppercase());
+
+    if first_upper && orig.chars().count() > 1 && letters.all(|c| c.is_uppercase()) {
+        return repl.to_uppercase();
+    }
+
+    if first_upper {
+        let mut chars = repl.chars();
+        return match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+    }
+
+    return repl.to_string();
 }
 
-/// go through the line and return the words, removing any special chars
-pub fn tokenize(line: &str) -> Vec<String> {
+/// Options that control how the words are checked in --file and --phrase
+/// modes.  Outside of the crate, they're set with the `with_` methods on
+/// top of the defaults.
+#[derive(Debug, Clone)]
+pub struct CheckOpts {
+    /// Files larger than this, in bytes, are skipped
+    pub(crate) max_file_size: u64,
+    /// Treat words that only differ by diacritics as variants
+    pub(crate) fold_diacritics: bool,
+    /// Accept unknown words that can be split into known words
+    pub(crate) compounds: Option<CompoundConfig>,
+    /// Flag known words that the bigram model says are likely the wrong
+    /// word from a confusion set
+    pub(crate) real_word: bool,
+    /// Add a usage hint for commonly confused words
+    pub(crate) confusable_notes: bool,
+    /// Use this filter for every file, rather than picking one by the
+    /// file's name
+    pub(crate) filter: Option<Filter>,
+    /// Which Markdown elements get checked
+    pub(crate) markdown: MarkdownOpts,
+    /// Skip `inline code` in files that aren't otherwise filtered
+    pub(crate) skip_code_spans: bool,
+    /// Only check the comments in source files that aren't otherwise
+    /// filtered
+    pub(crate) comments_only: bool,
+    /// Check generated files too, instead of skipping them
+    pub(crate) check_generated: bool,
+    /// Check minified files too, instead of skipping them
+    pub(crate) check_minified: bool,
+    /// Hand each file's text to the caller of `check_files()`.  Without
+    /// it, big files are checked straight from disk instead of being read
+    /// in whole, and the caller gets empty text for them.  One that turns
+    /// out not to be utf-8 past its start is still skipped whole.
+    pub(crate) keep_text: bool,
+}
+
+impl Default for CheckOpts {
+    /// The same as the defaults on the command line
+    fn default() -> Self {
+        return Self {
+            max_file_size: 10 * 1024 * 1024,
+            fold_diacritics: false,
+            compounds: None,
+            real_word: false,
+            confusable_notes: false,
+            filter: None,
+            markdown: MarkdownOpts::default(),
+            skip_code_spans: false,
+            comments_only: false,
+            check_generated: false,
+            check_minified: false,
+            keep_text: true,
+        };
+    }
+}
+
+impl CheckOpts {
+    /// Treat words that only differ by diacritics as variants
+    pub fn with_fold_diacritics(mut self, fold: bool) -> Self {
+        self.fold_diacritics = fold;
+        return self;
+    }
+
+    /// Accept unknown words that can be split into known words
+    pub fn with_compounds(mut self, compounds: Option<CompoundConfig>) -> Self {
+        self.compounds = compounds;
+        return self;
+    }
+
+    /// Add a usage hint for commonly confused words
+    pub fn with_confusable_notes(mut self, notes: bool) -> Self {
+        self.confusable_notes = notes;
+        return self;
+    }
+
+    /// Use this filter for all the text, rather than picking one by its
+    /// name
+    pub fn with_filter(mut self, filter: Option<Filter>) -> Self {
+        self.filter = filter;
+        return self;
+    }
+
+    pub fn with_markdown(mut self, markdown: MarkdownOpts) -> Self {
+        self.markdown = markdown;
+        return self;
+    }
+
+    pub fn with_skip_code_spans(mut self, skip: bool) -> Self {
+        self.skip_code_spans = skip;
+        return self;
+    }
+
+    pub fn with_comments_only(mut self, comments_only: bool) -> Self {
+        self.comments_only = comments_only;
+        return self;
+    }
+
+    /// Load a file to check, or stdin if the path is "-", skipping it if
+    /// it's generated, unless those are checked too
+    pub fn load(&self, fpath: &Path) -> std::result::Result<String, SkipReason> {
+        let content = match fpath.as_os_str() == "-" {
+            true => load_stdin(self.max_file_size)?,
+            false => load_file(fpath, self.max_file_size)?,
+        };
+        if !self.check_generated && is_generated(&content) {
+            return Err(SkipReason::Generated);
+        }
+        if !self.check_minified && is_minified(&content) {
+            return Err(SkipReason::Minified);
+        }
+
+        return Ok(content);
+    }
+
+    /// Start the filter for a file, if it needs one
+    pub fn line_filter(&self, fname: &Path) -> Option<Box<dyn LineFilter>> {
+        let filter = self
+            .filter
+            .or_else(|| Filter::for_path(fname))
+            .and_then(|f| f.line_filter(&self.markdown));
+
+        if filter.is_some() {
+            return filter;
+        }
+        if let Some(comments) = CommentsFilter::for_path(fname).filter(|_| self.comments_only) {
+            return Some(Box::new(comments));
+        }
+
+        return match self.skip_code_spans {
+            true => Some(Box::new(CodeSpanFilter)),
+            false => None,
+        };
+    }
+
+    /// The name of the filter a file gets, going the same way as
+    /// `line_filter()`
+    pub fn filter_name(&self, fname: &Path) -> String {
+        let filter = self.filter.or_else(|| Filter::for_path(fname));
+        if let Some(f) = filter.filter(|f| *f != Filter::Plain) {
+            return f.to_possible_value().unwrap().get_name().to_string();
+        }
+        if self.comments_only && CommentsFilter::for_path(fname).is_some() {
+            return "comments".to_string();
+        }
+
+        return match self.skip_code_spans {
+            true => "code spans".to_string(),
+            false => "none".to_string(),
+        };
+    }
+}
+
+/// What a word is, going by the dictionary and ignore list alone, without
+/// the words around it
+#[derive(Debug, Clone, PartialEq)]
+enum Class {
+    Ignored,
+    Known,
+    Compound,
+    /// Differs from this dictionary word by its diacritics
+    Variant(String),
+    /// Transliterates to this dictionary word
+    Translit(String),
+    Unknown,
+}
+
+/// Lookup tables derived from the dictionary and ignore list, built once
+/// per run
+#[derive(Debug, Default)]
+pub struct Lookups {
+    /// Folded form -> dictionary word, with --fold-diacritics
+    pub folded: Option<HashMap<String, String>>,
+    /// Multi-word dictionary and ignore entries
+    pub phrases: PhraseIndex,
+    /// The bigram model, for --real-word checks
+    pub ngrams: Option<BigramModel>,
+    /// The words seen so far in the run and what they are, so that
+    /// repeated words aren't looked up again.  They're shared by the
+    /// threads checking files, which mostly read them.
+    classes: RwLock<hashbrown::HashMap<Caseless<String>, Class, RandomState>>,
+    /// The number of times each ignore list word has been skipped
+    ignored: Mutex<HashMap<String, usize>>,
+}
+
+impl Lookups {
+    pub fn build(
+        words: &WordSet,
+        ign_list: &HashSet<String>,
+        opts: &CheckOpts,
+        ngrams: Option<BigramModel>,
+    ) -> Self {
+        let folded = if opts.fold_diacritics {
+            Some(build_folded(words))
+        } else {
+            None
+        };
+
+        return Self {
+            folded,
+            phrases: PhraseIndex::new(
+                words
+                    .iter()
+                    .chain(ign_list.iter().map(|w| Cow::Borrowed(w.as_str()))),
+            ),
+            ngrams,
+            classes: RwLock::default(),
+            ignored: Mutex::default(),
+        };
+    }
+
+    /// Check whether a word in any case is spelled right on its own: it's
+    /// in the dictionary or ignore list, or made up of dictionary words
+    pub fn is_correct(
+        &self,
+        word: &str,
+        words: &WordSet,
+        ign_list: &HashSet<String>,
+        opts: &CheckOpts,
+    ) -> bool {
+        return matches!(
+            self.classify(word, words, ign_list, opts),
+            Class::Known | Class::Ignored | Class::Compound
+        );
+    }
+
+    /// Classify a word in any case, from the cache if it's been seen before
+    fn classify(
+        &self,
+        word: &str,
+        words: &WordSet,
+        ign_list: &HashSet<String>,
+        opts: &CheckOpts,
+    ) -> Class {
+        // Words are only lowercased the first time they're seen
+        if let Some(class) = self.classes.read().unwrap().get(&Caseless(word)) {
+            return class.clone();
+        }
+
+        let word = &*fold_case(word);
+        let class = if ign_list.contains(word) {
+            Class::Ignored
+        } else if words.contains(word) {
+            Class::Known
+        } else if is_compound(word, words, opts) {
+            Class::Compound
+        } else if let Some(variant) = self
+            .folded
+            .as_ref()
+            .and_then(|f| find_variant(word, words, f))
+        {
+            Class::Variant(variant)
+        } else if let Some(ascii) = translit_fallback(word, words) {
+            Class::Translit(ascii)
+        } else {
+            Class::Unknown
+        };
+        self.classes
+            .write()
+            .unwrap()
+            .insert(Caseless(word.to_string()), class.clone());
+
+        return class;
+    }
+
+    /// The number of times an ignore list entry has matched so far
+    pub fn ignore_count(&self, entry: &str) -> usize {
+        if entry.trim().contains(char::is_whitespace) {
+            return self.phrases.count(entry);
+        }
+
+        let ignored = self.ignored.lock().unwrap();
+        return ignored.get(&*fold_case(entry)).copied().unwrap_or(0);
+    }
+}
+
+/// If a non-ascii word isn't in the dictionary, see if the ascii
+/// transliteration of it is, and return that if so
+pub fn translit_fallback(word: &str, words: &WordSet) -> Option<String> {
+    if word.is_ascii() {
+        return None;
+    }
+
+    let ascii = transliterate(word);
+    if words.contains(ascii.as_str()) {
+        return Some(ascii);
+    }
+
+    return None;
+}
+
+/// With --compounds, check whether an unknown word can be split into known
+/// words
+pub fn is_compound(word: &str, words: &WordSet, opts: &CheckOpts) -> bool {
+    let parts = opts
+        .compounds
+        .as_ref()
+        .and_then(|cfg| split_compound(word, words, cfg));
+
+    if let Some(parts) = &parts {
+        debug!("Accepting compound \"{}\": {:?}", word, parts);
+    }
+
+    return parts.is_some();
+}
+
+/// With --real-word, check whether a correctly spelled word is likely the
+/// wrong one from its confusion set, given the tokens around it
+fn real_word_check(
+    opts: &CheckOpts,
+    lookups: &Lookups,
+    tokens: &[Cow<str>],
+    i: usize,
+) -> Option<&'static str> {
+    if !opts.real_word {
+        return None;
+    }
+
+    let prev = i.checked_sub(1).map(|p| tokens[p].as_ref());
+    let next = tokens.get(i + 1).map(|n| n.as_ref());
+
+    return real_word_error(lookups.ngrams.as_ref()?, prev, &tokens[i], next);
+}
+
+/// The most of a line that's checked at once.  Longer lines, like minified
+/// JS, are checked a piece at a time so they don't all have to be held.
+/// The lines of filtered files are always checked whole, as the filters
+/// go by where each line starts.
+const MAX_PIECE: usize = 64 * 1024;
+
+/// Read the rest of the line onto the end of `buf`, stopping early if
+/// `buf` fills up to `max` bytes.  Returns whether the line ended, which
+/// it does at the end of the input too.
+fn read_piece<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>, max: usize) -> io::Result<bool> {
+    loop {
+        let avail = reader.fill_buf()?;
+        if avail.is_empty() {
+            return Ok(true);
+        }
+
+        let room = max.saturating_sub(buf.len()).min(avail.len());
+        if let Some(i) = memchr(b'\n', &avail[..room]) {
+            buf.extend_from_slice(&avail[..=i]);
+            reader.consume(i + 1);
+            return Ok(true);
+        }
+
+        buf.extend_from_slice(&avail[..room]);
+        reader.consume(room);
+        if buf.len() >= max {
+            return Ok(false);
+        }
+    }
+}
+
+/// Where to cut a piece of a long line: after the last char that can't be
+/// part of a word, so no word is split, or at a char boundary if it's all
+/// one word
+fn piece_end(buf: &[u8]) -> usize {
+    let is_boundary = |b: &u8| b.is_ascii() && !b.is_ascii_alphanumeric() && !b"-'\0".contains(b);
+    if let Some(i) = buf.iter().rposition(is_boundary) {
+        return i + 1;
+    }
+
+    // Leave off a multibyte char that's cut short
+    return match std::str::from_utf8(buf) {
+        Err(e) if e.error_len().is_none() && e.valid_up_to() > 0 => e.valid_up_to(),
+        _ => buf.len(),
+    };
+}
+
+/// Read the file by lines, and return a diagnostic for each misspelled
+/// word.  Words that are part of a known phrase are skipped, words that
+/// only differ from a dictionary word by their diacritics (with
+/// --fold-diacritics) are reported as variants, and words whose
+/// transliteration is known get that as a suggestion.  With --real-word,
+/// correctly spelled words that are likely confused with another are
+/// reported at a lower severity, as are usage hints for commonly confused
+/// words with `--notes confusables`.  Files that have a filter for their
+/// type only have their prose checked.  Text that isn't all utf-8 gives no
+/// findings, the same as a file that isn't is skipped.
+pub fn check_file<R: BufRead>(
+    fname: &Path,
+    reader: R,
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+) -> Vec<Diagnostic> {
+    return match check_pieces(fname, reader, words, ign_list, opts, lookups, MAX_PIECE) {
+        Ok((diags, _)) => diags,
+        Err(reason) => {
+            warn!("Not checking \"{}\": {}", fname.display(), reason);
+            vec![]
+        }
+    };
+}
+
+/// Same as `check_file()`, checking lines longer than `max_piece` bytes a
+/// piece at a time.  Also returns how many lines there were, or that the
+/// text isn't utf-8, in which case none of it counts.
+fn check_pieces<R: BufRead>(
+    fname: &Path,
+    mut reader: R,
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+    max_piece: usize,
+) -> std::result::Result<(Vec<Diagnostic>, u64), SkipReason> {
     let mut ret = vec![];
-    let mut tmp = String::new();
-    for c in line.chars() {
-        if c.is_ascii_alphanumeric() || c == '-' || c == '\'' {
-            // Alphabetic chars, dashes and apostrophes are ok
-            if c == '-' || c == '\'' {
-                tmp.push(c);
-            } else {
-                tmp.push(c.to_ascii_lowercase());
+    let mut buf = vec![];
+    let mut lcount = 1;
+    // Where the current line starts in the file
+    let mut start_byte = 0;
+    let mut start_char = 0;
+    // Where the current piece starts in its line, for long lines
+    let (mut off_byte, mut off_char, mut off_u16) = (0, 0, 0);
+    let mut filter = opts.line_filter(fname);
+    let max_piece = match filter {
+        Some(_) => usize::MAX,
+        None => max_piece,
+    };
+    // The words of the current line, reset for each line so the file only
+    // takes a few allocations for all its words
+    let mut arena = Bump::new();
+    // The findings on the current line start here, and the pieces of a
+    // long line are kept, so their fingerprints can go by the whole line
+    let mut line_diags = 0;
+    let mut long_line = String::new();
+
+    loop {
+        arena.reset();
+        let ended = match read_piece(&mut reader, &mut buf, max_piece) {
+            Ok(ended) => ended,
+            Err(e) => {
+                warn!("Error reading \"{}\": {}", fname.display(), e);
+                break;
+            }
+        };
+        if buf.is_empty() {
+            break;
+        }
+
+        // The end of a long line's piece that doesn't fit is kept for the
+        // next piece
+        let split = match ended {
+            true => buf.len(),
+            false => piece_end(&buf),
+        };
+        let Ok(piece) = std::str::from_utf8(&buf[..split]) else {
+            return Err(SkipReason::BadEncoding);
+        };
+        let l = match ended {
+            true => piece.trim_end_matches(['\n', '\r']),
+            false => piece,
+        };
+        let masked = filter.as_mut().map(|f| f.mask(l));
+        debug_assert!(masked.as_ref().is_none_or(|m| m.len() == l.len()));
+
+        let spans = tokenize_spans_in(masked.as_deref().unwrap_or(l), &arena);
+        let scope = filter.as_ref().and_then(|f| f.scope());
+        // Phrases and the bigram model go by the lowercase words, and most
+        // runs need neither
+        let tokens: Vec<Cow<str>> = match lookups.phrases.is_empty() && !opts.real_word {
+            true => vec![],
+            false => spans.iter().map(|(_, w)| fold_case(w)).collect(),
+        };
+        let in_phrase = match tokens.is_empty() {
+            true => vec![false; spans.len()],
+            false => lookups.phrases.covered(&tokens),
+        };
+
+        // Track the char and utf-16 offsets of the last token, so we only
+        // count the chars in between instead of from the start of the line
+        // each time
+        let (mut last_byte, mut last_char, mut last_u16) = (0, 0, 0);
+
+        for (i, ((bytes, word), in_phrase)) in spans.into_iter().zip(in_phrase).enumerate() {
+            let between = &l[last_byte..bytes.start];
+            let col = last_char + between.chars().count();
+            let col16 = last_u16 + between.encode_utf16().count();
+            (last_byte, last_char, last_u16) = (bytes.start, col, col16);
+
+            if in_phrase {
+                continue;
+            }
+            let class = lookups.classify(word, words, ign_list, opts);
+            if class == Class::Ignored {
+                *lookups
+                    .ignored
+                    .lock()
+                    .unwrap()
+                    .entry(fold_case(word).into_owned())
+                    .or_default() += 1;
             }
+            if matches!(class, Class::Ignored | Class::Compound) {
+                continue;
+            }
+
+            let text = &l[bytes.clone()];
+            let (col, col16) = (off_char + col, off_u16 + col16);
+            let span = Span::new(
+                off_byte + bytes.start..off_byte + bytes.end,
+                col..col + text.chars().count(),
+                col16..col16 + text.encode_utf16().count(),
+                start_byte,
+                start_char,
+            );
+            let word = fold_case(word);
+            let diag = |kind| {
+                Diagnostic::new(fname.to_owned(), lcount, span.clone(), &word, kind)
+                    .with_scope(scope)
+            };
+
+            match class {
+                Class::Known => {
+                    if let Some(alt) = real_word_check(opts, lookups, &tokens, i) {
+                        ret.push(diag(Kind::Confusion).with_suggestions(vec![alt.to_string()]));
+                    } else if let Some(hint) =
+                        confusable_hint(&word).filter(|_| opts.confusable_notes)
+                    {
+                        ret.push(diag(Kind::Note).with_note(hint));
+                    }
+                }
+                Class::Variant(variant) => {
+                    ret.push(diag(Kind::Variant).with_suggestions(vec![variant]));
+                }
+                Class::Translit(ascii) => {
+                    ret.push(diag(Kind::Misspelling).with_suggestions(vec![ascii]));
+                }
+                _ => ret.push(diag(Kind::Misspelling)),
+            }
+        }
+
+        if ended {
+            let line = match long_line.is_empty() {
+                true => l,
+                false => {
+                    long_line.push_str(l);
+                    &long_line
+                }
+            };
+            set_fingerprints(&mut ret[line_diags..], line);
+            long_line.clear();
+            line_diags = ret.len();
+
+            start_byte += off_byte + piece.len();
+            start_char += off_char + piece.chars().count();
+            lcount += 1;
+            (off_byte, off_char, off_u16) = (0, 0, 0);
         } else {
-            // If we get here, we've found a word boundary of some sort,
-            // append a copy of the word to our return set
-            if check_token(&tmp) {
-                ret.push(strip_apost(&tmp));
+            long_line.push_str(piece);
+            off_byte += piece.len();
+            off_char += piece.chars().count();
+            off_u16 += piece.encode_utf16().count();
+        }
+        buf.drain(..split);
+    }
+
+    return Ok((ret, lcount - 1));
+}
+
+/// Work out the fingerprints of the findings on a line, counting which of
+/// each word's findings on the line each one is
+fn set_fingerprints(diags: &mut [Diagnostic], line: &str) {
+    let mut found: HashMap<&str, usize> = HashMap::new();
+    for d in diags {
+        let nth = found.entry(&d.word).or_default();
+        d.fingerprint = fingerprint(&d.path, &d.word, line, *nth);
+        *nth += 1;
+    }
+}
+
+/// The reason a file was skipped in a `--file` run
+#[derive(Debug, PartialEq)]
+pub enum SkipReason {
+    OpenFailed(String),
+    Binary,
+    TooLarge(u64),
+    BadEncoding,
+    Generated,
+    Minified,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkipReason::OpenFailed(e) => write!(f, "failed to open: {}", e),
+            SkipReason::Binary => write!(f, "binary file"),
+            SkipReason::TooLarge(size) => write!(f, "too large ({} bytes)", size),
+            SkipReason::BadEncoding => write!(f, "not valid utf-8"),
+            SkipReason::Generated => write!(f, "generated file"),
+            SkipReason::Minified => write!(f, "minified file"),
+        }
+    }
+}
+
+/// A file that was not checked, and why
+#[derive(Debug)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// Read in the file contents, making sure it's something we can actually
+/// spell check.  A file that isn't all utf-8 is skipped, rather than just
+/// the lines that aren't, as a fix has to write back exactly what it read.
+pub fn load_file(fpath: &Path, max_size: u64) -> std::result::Result<String, SkipReason> {
+    let meta = fs::metadata(fpath).map_err(|e| SkipReason::OpenFailed(e.to_string()))?;
+    if meta.len() > max_size {
+        return Err(SkipReason::TooLarge(meta.len()));
+    }
+
+    let content = read_bytes(fpath).map_err(|e| SkipReason::OpenFailed(e.to_string()))?;
+
+    return to_text(content);
+}
+
+/// Read all of stdin to check, the same way as load_file() reads a file
+pub fn load_stdin(max_size: u64) -> std::result::Result<String, SkipReason> {
+    return load_reader(io::stdin().lock(), max_size);
+}
+
+/// Read in what's left of the reader, holding no more than `max_size`
+/// bytes of it, as it can't be told how big a stream is up front
+fn load_reader<R: Read>(mut reader: R, max_size: u64) -> std::result::Result<String, SkipReason> {
+    let read_failed = |e: io::Error| SkipReason::OpenFailed(e.to_string());
+    let mut content = vec![];
+    (&mut reader)
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut content)
+        .map_err(read_failed)?;
+    if content.len() as u64 > max_size {
+        // Only counting the rest, for how big it was
+        let rest = io::copy(&mut reader, &mut io::sink()).map_err(read_failed)?;
+        return Err(SkipReason::TooLarge(content.len() as u64 + rest));
+    }
+
+    return to_text(content);
+}
+
+/// The contents of a file as text, unless it's binary or not utf-8
+fn to_text(content: Vec<u8>) -> std::result::Result<String, SkipReason> {
+    if is_binary(&content) {
+        return Err(SkipReason::Binary);
+    }
+
+    return String::from_utf8(content).map_err(|_| SkipReason::BadEncoding);
+}
+
+/// Same heuristic as git/grep, a NUL byte early on means binary
+fn is_binary(content: &[u8]) -> bool {
+    return content[..content.len().min(8000)].contains(&0);
+}
+
+/// The start of a file as text, the same way as `to_text()`, leaving off
+/// a char that's cut short at the end
+fn text_prefix(content: &[u8]) -> std::result::Result<&str, SkipReason> {
+    if is_binary(content) {
+        return Err(SkipReason::Binary);
+    }
+
+    return match std::str::from_utf8(content) {
+        Ok(text) => Ok(text),
+        // Only the missing end of the last char
+        Err(e) if e.error_len().is_none() => {
+            Ok(std::str::from_utf8(&content[..e.valid_up_to()]).unwrap())
+        }
+        Err(_) => Err(SkipReason::BadEncoding),
+    };
+}
+
+/// What tools put at the top of the files they write, lowercased
+const GENERATED_MARKERS: [&str; 6] = [
+    "@generated",
+    "do not edit",
+    "generated by",
+    "auto-generated",
+    "autogenerated",
+    "automatically generated",
+];
+
+/// Check whether the text starts with a header saying a tool wrote it,
+/// like "Code generated by protoc-gen-go. DO NOT EDIT.", in its first few
+/// lines.  Generated files are full of names nobody would spell check.
+pub fn is_generated(text: &str) -> bool {
+    return text.lines().take(5).any(|line| {
+        let line = line.to_lowercase();
+        return GENERATED_MARKERS.iter().any(|m| line.contains(m));
+    });
+}
+
+/// With lines this long on average, a high entropy or a lot of symbols
+/// says the file is minified code or encoded data rather than prose with
+/// long paragraphs.  Long lines alone don't, as prose without hard wraps
+/// has them too.
+const DENSE_LINE_LEN: usize = 150;
+/// In bits per byte.  Prose, even with some code in it, comes in under
+/// this, while minified code is about 5.2 and base64 data about 6.
+const DENSE_ENTROPY: f64 = 5.0;
+/// The share of the non-whitespace chars that are ascii punctuation.
+/// Prose has a few percent, minified code about a third.
+const DENSE_SYMBOLS: f64 = 0.2;
+
+/// The Shannon entropy of the bytes of the text, in bits per byte
+fn entropy(text: &str) -> f64 {
+    let mut counts = [0_usize; 256];
+    for b in text.bytes() {
+        counts[b as usize] += 1;
+    }
+    let len = text.len() as f64;
+
+    return counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            return -p * p.log2();
+        })
+        .sum();
+}
+
+/// The share of the non-whitespace chars in the text that are ascii
+/// punctuation
+fn symbol_ratio(text: &str) -> f64 {
+    let (mut symbols, mut total) = (0, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        total += 1;
+        if c.is_ascii_punctuation() {
+            symbols += 1;
+        }
+    }
+
+    return symbols as f64 / total.max(1) as f64;
+}
+
+/// Check whether the text looks like minified or bundled code, going by
+/// how long its lines are and how dense its symbols are
+pub fn is_minified(text: &str) -> bool {
+    let line_len = text.len() / text.lines().count().max(1);
+
+    return line_len >= DENSE_LINE_LEN
+        && (entropy(text) >= DENSE_ENTROPY || symbol_ratio(text) >= DENSE_SYMBOLS);
+}
+
+/// Which files are picked when collecting the files to check
+#[derive(Debug, Default, Clone)]
+pub struct WalkOpts {
+    /// Only pick the files matching one of these globs, if there are any
+    pub include: Vec<String>,
+    /// Leave out the files and directories matching any of these globs
+    pub exclude: Vec<String>,
+    /// Don't follow the `.gitignore` and `.ignore` files in the directories
+    pub no_ignore: bool,
+}
+
+impl WalkOpts {
+    /// Check whether the path matches the glob, relative to `root`, the
+    /// directory being walked, or to the current directory.  A glob with a
+    /// `/` in it matches the whole path, like "vendor/**", and one without
+    /// matches any file or directory name in it, like "*.md".
+    fn glob(glob: &str, path: &Path, root: &Path) -> bool {
+        let rel = relative_path(path, root);
+        if glob.contains('/') {
+            return glob_matches(glob, &rel)
+                || glob_matches(glob, &relative_path(path, Path::new("")));
+        }
+
+        return rel.components().any(|c| match c {
+            Component::Normal(name) => glob_matches(glob, Path::new(name)),
+            _ => false,
+        });
+    }
+
+    fn excluded(&self, path: &Path, root: &Path) -> bool {
+        return self.exclude.iter().any(|g| Self::glob(g, path, root));
+    }
+
+    fn included(&self, file: &Path, root: &Path) -> bool {
+        return self.include.is_empty() || self.include.iter().any(|g| Self::glob(g, file, root));
+    }
+}
+
+/// Expand any directories in the list into the files under them,
+/// recursively, keeping the ones that `walk` picks.  Hidden files and
+/// directories, and the ones left out by the `.gitignore` and `.ignore`
+/// files along the way, are skipped when walking a directory, but not when
+/// they're given explicitly.
+pub fn collect_files(paths: &[PathBuf], walk: &WalkOpts) -> Vec<PathBuf> {
+    let mut ret = vec![];
+    let cwd = Path::new("");
+
+    for path in paths {
+        if path.is_dir() {
+            let mut rules = if walk.no_ignore {
+                Gitignore::default()
+            } else {
+                Gitignore::above(path)
+            };
+            walk_dir(path, path, walk, &mut rules, &mut ret);
+        } else if path.as_os_str() == "-" || (walk.included(path, cwd) && !walk.excluded(path, cwd))
+        {
+            ret.push(path.to_owned());
+        }
+    }
+
+    return ret;
+}
+
+/// Walk `dir`, somewhere under `root`, the directory that was given
+fn walk_dir(
+    dir: &Path,
+    root: &Path,
+    walk: &WalkOpts,
+    rules: &mut Gitignore,
+    files: &mut Vec<PathBuf>,
+) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(rd) => rd.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            warn!("Failed to read directory \"{}\": {}", dir.display(), e);
+            return;
+        }
+    };
+    entries.sort();
+    let len = if walk.no_ignore { 0 } else { rules.enter(dir) };
+
+    for path in entries {
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        let is_dir = path.is_dir();
+        if hidden || walk.excluded(&path, root) || rules.ignored(&path, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            walk_dir(&path, root, walk, rules, files);
+        } else if walk.included(&path, root) {
+            files.push(path);
+        }
+    }
+    rules.truncate(len);
+}
+
+/// How many files past the first one that's still being checked each
+/// thread can go on to
+const WINDOW_PER_JOB: usize = 4;
+
+/// Check all the files on `jobs` threads, handing the contents and
+/// diagnostics for each file to `on_file` as it's done, in the order of
+/// the files, and return the list of files that were skipped.  The lookups
+/// have to be built with the same words, ignore list and options.
+pub fn check_files<F: FnMut(&Path, &str, Vec<Diagnostic>)>(
+    files: &[PathBuf],
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+    jobs: usize,
+    mut on_file: F,
+) -> Vec<SkippedFile> {
+    let mut skipped = vec![];
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    let jobs = jobs.clamp(1, files.len().max(1));
+    // The first file that hasn't been handed to `on_file` yet.  The files
+    // are only checked up to a window past it, so no more than that are
+    // held at once when one takes a long time.
+    let window = jobs * WINDOW_PER_JOB;
+    let first_left = (Mutex::new(0), Condvar::new());
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            let (next, tx, first_left) = (&next, tx.clone(), &first_left);
+            s.spawn(move || loop {
+                let i = next.fetch_add(1, AtomicOrdering::Relaxed);
+                let Some(fpath) = files.get(i) else {
+                    return;
+                };
+                let (lock, moved) = first_left;
+                drop(moved.wait_while(lock.lock().unwrap(), |first| i >= *first + window));
+
+                let checked = check_one(fpath, words, ign_list, opts, lookups);
+                if tx.send((i, checked)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(tx);
+
+        // The files finish out of order, so hold on to them until the ones
+        // before them are done
+        let mut done = HashMap::new();
+        let mut first = 0;
+        for (i, checked) in rx {
+            done.insert(i, checked);
+            while let Some(checked) = done.remove(&first) {
+                let fpath = &files[first];
+                match checked {
+                    Ok((content, diags)) => on_file(fpath, &content, diags),
+                    Err(reason) => {
+                        debug!("Skipping \"{}\": {}", fpath.display(), reason);
+                        skipped.push(SkippedFile {
+                            path: fpath.to_owned(),
+                            reason,
+                        });
+                    }
+                }
+                first += 1;
             }
+            *first_left.0.lock().unwrap() = first;
+            first_left.1.notify_all();
+        }
+    });
+
+    return skipped;
+}
 
-            tmp = String::new();
+/// Files bigger than this are checked straight from disk, when their
+/// text isn't kept, rather than being read in whole
+const STREAM_SIZE: u64 = 1024 * 1024;
+/// How much of the start and the end of a file checked from disk is read
+/// to tell whether it's skipped, and to find its modeline
+const SNIFF_LEN: usize = 64 * 1024;
+
+/// Check a file, returning its contents and diagnostics, or why it was
+/// skipped
+fn check_one(
+    fpath: &Path,
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+) -> std::result::Result<(String, Vec<Diagnostic>), SkipReason> {
+    if !opts.keep_text && fpath.as_os_str() != "-" {
+        let size = fs::metadata(fpath)
+            .map_err(|e| SkipReason::OpenFailed(e.to_string()))?
+            .len();
+        if size > STREAM_SIZE {
+            let diags = stream_one(fpath, size, words, ign_list, opts, lookups)?;
+            return Ok((String::new(), diags));
         }
     }
 
-    if check_token(&tmp) {
-        ret.push(strip_apost(&tmp));
+    let content = opts.load(fpath)?;
+
+    let modeline = file_modeline(fpath, Modeline::find(&content));
+    let file_opts = match &modeline {
+        Some(m) => Cow::Owned(m.apply(opts)),
+        None => Cow::Borrowed(opts),
+    };
+
+    let mut diags = check_file(
+        fpath,
+        Cursor::new(&content),
+        words,
+        ign_list,
+        &file_opts,
+        lookups,
+    );
+    if let Some(m) = &modeline {
+        m.retain(&mut diags);
+    }
+
+    return Ok((content, diags));
+}
+
+/// Check a big file a piece at a time from disk, going by its start for
+/// whether it's skipped, and by its first and last lines for its modeline
+fn stream_one(
+    fpath: &Path,
+    size: u64,
+    words: &WordSet,
+    ign_list: &HashSet<String>,
+    opts: &CheckOpts,
+    lookups: &Lookups,
+) -> std::result::Result<Vec<Diagnostic>, SkipReason> {
+    if size > opts.max_file_size {
+        return Err(SkipReason::TooLarge(size));
+    }
+
+    let open_failed = |e: io::Error| SkipReason::OpenFailed(e.to_string());
+    let mut f = File::open(fpath).map_err(open_failed)?;
+    let (mut head, mut tail) = (vec![], vec![]);
+    (&mut f)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .map_err(open_failed)?;
+    f.seek(SeekFrom::End(-(SNIFF_LEN as i64)))
+        .and_then(|_| f.read_to_end(&mut tail))
+        .and_then(|_| f.rewind())
+        .map_err(open_failed)?;
+
+    let head = text_prefix(&head)?;
+    if !opts.check_generated && is_generated(head) {
+        return Err(SkipReason::Generated);
+    }
+    if !opts.check_minified && is_minified(head) {
+        return Err(SkipReason::Minified);
+    }
+
+    // The tail can start partway through a char, but the last line can't
+    let tail = String::from_utf8_lossy(&tail);
+    let last = tail
+        .trim_end_matches(['\n', '\r'])
+        .rsplit_once('\n')
+        .map(|(_, l)| l);
+    let first = head.lines().next().unwrap_or("");
+    let (modeline, at_end) = match Modeline::find(first) {
+        Some(found) => (file_modeline(fpath, Some(found)), false),
+        None => (file_modeline(fpath, last.and_then(Modeline::find)), true),
+    };
+    let file_opts = match &modeline {
+        Some(m) => Cow::Owned(m.apply(opts)),
+        None => Cow::Borrowed(opts),
+    };
+
+    let reader = BufReader::new(f);
+    let (mut diags, lines) = check_pieces(
+        fpath, reader, words, ign_list, &file_opts, lookups, MAX_PIECE,
+    )?;
+    if let Some(mut m) = modeline {
+        if at_end {
+            m.line = lines;
+        }
+        m.retain(&mut diags);
     }
 
-    return ret;
+    return Ok(diags);
 }
 
-/// Read the file by lines, and output the filename:line number for each
-/// misspelled word
-pub fn check_file(
-    fname: &PathBuf,
-    reader: Lines<BufReader<File>>,
-    words: &HashSet<String>,
-    ign_list: &HashSet<String>,
-) {
-    let mut lcount: u64 = 1;
-    for line in reader {
-        if let Ok(l) = line {
-            let tokens = tokenize(&l);
-            for word in &tokens {
-                if !words.contains(word) && !ign_list.contains(word) {
-                    println!("{}:{} \"{}\"", fname.display(), lcount, word);
-                }
-            }
-        }
-
-        lcount += 1;
+/// The modeline that was found in a file, warning about the options in it
+/// that were left out
+fn file_modeline(fpath: &Path, found: Option<Modeline>) -> Option<Modeline> {
+    for problem in found.iter().flat_map(|m| &m.problems) {
+        warn!(
+            "Ignoring an option in the modeline of \"{}\": {}",
+            fpath.display(),
+            problem
+        );
     }
+
+    return found;
 }
 
-pub fn check_files(files: &Vec<PathBuf>, words: &HashSet<String>, ign_list: &HashSet<String>) {
-    for fpath in files {
-        let reader = match read_lines(&fpath) {
-            Err(e) => {
-                warn!(
-                    "Failed to open \"{}\" for reading, skipping: {}",
-                    fpath.display(),
-                    e
-                );
-                continue;
-            }
-            Ok(reader) => reader,
-        };
+/// Print a table of the skipped files, and the reasons, to stderr
+pub fn print_skip_summary(skipped: &[SkippedFile]) {
+    if skipped.is_empty() {
+        return;
+    }
 
-        check_file(fpath, reader, words, ign_list);
+    let width = skipped
+        .iter()
+        .map(|s| s.path.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("FILE".len());
+
+    eprintln!("\nSkipped {} file(s):", skipped.len());
+    eprintln!("  {:<width$}  REASON", "FILE", width = width);
+    for s in skipped {
+        eprintln!(
+            "  {:<width$}  {}",
+            s.path.display().to_string(),
+            s.reason,
+            width = width,
+        );
     }
 }
 
-/// This will basically just handle a ~/, which is silly that I have to
-/// do this, but whatever
-pub fn parse_path(fpath: &PathBuf) -> PathBuf {
-    if !fpath.starts_with("~") {
+/// This will basically just handle a ~/ or ~user/, which is silly that I
+/// have to do this, but whatever
+pub fn parse_path(fpath: &Path) -> PathBuf {
+    let path_str = match fpath.to_str() {
+        Some(p) if p.starts_with('~') => p.strip_prefix('~').unwrap(),
         // If it doesn't start with a ~, we just return it
-        return fpath.to_owned();
-    }
+        _ => return fpath.to_owned(),
+    };
 
-    let mut ret = PathBuf::new();
+    // Split off the user name, if any, from the rest of the path
+    let (user, rest) = match path_str.split_once('/') {
+        Some((user, rest)) => (user, rest),
+        None => (path_str, ""),
+    };
 
-    // Turn it into a str with the ~ stripped
-    let mut path_str = fpath.to_str().unwrap().strip_prefix("~").unwrap();
-    if path_str.starts_with("/") {
-        path_str = path_str.strip_prefix("/").unwrap();
-    }
+    let home = if user.is_empty() {
+        env::var("HOME").ok().map(PathBuf::from)
+    } else {
+        user_home_dir(user)
+    };
+
+    let mut ret = match home {
+        Some(h) => h,
+        None => {
+            warn!("Could not resolve the home directory for \"~{}\"", user);
+            return fpath.to_owned();
+        }
+    };
 
-    ret.push(env::var("HOME").unwrap());
-    ret.push(path_str);
+    if !rest.is_empty() {
+        ret.push(rest);
+    }
 
     return ret;
 }
 
-pub fn get_ignore_file_contents(fpath: &PathBuf) -> Vec<String> {
+/// Look up the home directory for another user on the system
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    use uzers::os::unix::UserExt;
+
+    return uzers::get_user_by_name(user).map(|u| u.home_dir().to_owned());
+}
+
+#[cfg(not(unix))]
+fn user_home_dir(_user: &str) -> Option<PathBuf> {
+    return None;
+}
+
+/// The words in a word list file, 1 per line, leaving out the comments
+/// and the entries whose "# until=YYYY-MM-DD" date has passed
+pub fn get_ignore_file_contents(fpath: &Path) -> Vec<String> {
     let mut ret: Vec<String> = vec![];
 
     let real_path = parse_path(fpath);
@@ -198,7 +1694,7 @@ pub fn get_ignore_file_contents(fpath: &PathBuf) -> Vec<String> {
         return ret;
     }
 
-    let reader = match read_lines(&real_path) {
+    let text = match fs::read_to_string(&real_path) {
         Err(e) => {
             warn!(
                 "Failed to open \"{}\" for reading ignore content: {}",
@@ -207,79 +1703,256 @@ pub fn get_ignore_file_contents(fpath: &PathBuf) -> Vec<String> {
             );
             return ret;
         }
-        Ok(r) => r,
+        Ok(t) => t,
     };
 
-    for line in reader {
-        if let Ok(l) = line {
-            let word = l.trim();
-            if word.len() > 0 {
-                debug!("Adding '{}' from ignore file", word);
-                ret.push(word.to_string());
-            }
-        }
-    }
+    ret = WordList::parse(&text).current_words(&fpath.display().to_string());
+    debug!("Adding {:?} from ignore file", ret);
 
     return ret;
 }
 
 /// Return a list of the ignored words specified on eithe the command-line
 /// or via an ignore file
-pub fn get_ignore_list(to_ign: &Option<String>, ign_file: &PathBuf) -> Vec<String> {
-    let mut ret = get_ignore_file_contents(ign_file);
-    if to_ign.is_none() {
-        // If we don't actually have an ignore list, return an empty vec
+#[cfg(test)]
+pub fn get_ignore_list(to_ign: &[String], ign_file: &Path) -> Vec<String> {
+    return WordLists::default().ignore_list(to_ign, ign_file);
+}
+
+/// The words from each --ignore: either a comma-separated list, or
+/// "@file" for the words in a file, 1 per line like an ignore file
+pub fn ignore_words(to_ign: &[String]) -> Vec<String> {
+    return WordLists::default().ignore_words(to_ign);
+}
+
+/// The word lists that have been read, by their path, so that one used by
+/// more than one group of files is only read, and warned about, once
+#[derive(Debug, Default)]
+pub struct WordLists {
+    read: HashMap<PathBuf, Vec<String>>,
+}
+
+impl WordLists {
+    /// Same as `get_ignore_list()`
+    pub fn ignore_list(&mut self, to_ign: &[String], ign_file: &Path) -> Vec<String> {
+        let mut ret = self.ignore_file(ign_file);
+        ret.extend(self.ignore_words(to_ign));
+
         return ret;
     }
 
-    let ign = to_ign.as_ref().unwrap();
-
-    let tmp: Vec<String> = ign.split(',').map(|w| w.trim().to_string()).collect();
+    /// Same as `get_ignore_file_contents()`
+    pub fn ignore_file(&mut self, fpath: &Path) -> Vec<String> {
+        return self
+            .read
+            .entry(fpath.to_owned())
+            .or_insert_with(|| get_ignore_file_contents(fpath))
+            .clone();
+    }
 
-    // Filter empty values
-    for item in tmp {
-        if item.len() > 0 {
-            ret.push(item);
+    /// Same as `ignore_words()`
+    pub fn ignore_words(&mut self, to_ign: &[String]) -> Vec<String> {
+        let mut ret = vec![];
+        for ign in to_ign {
+            let Some(path) = ign.strip_prefix('@') else {
+                ret.extend(
+                    ign.split(',')
+                        .map(|w| w.trim())
+                        .filter(|w| !w.is_empty())
+                        .map(|w| w.to_string()),
+                );
+                continue;
+            };
+            let words = self.read.entry(PathBuf::from(path)).or_insert_with(|| {
+                return match fs::read_to_string(parse_path(Path::new(path))) {
+                    Ok(text) => WordList::parse(&text).current_words(path),
+                    Err(e) => {
+                        warn!(
+                            "Failed to read the words to ignore from \"{}\": {}",
+                            path, e
+                        );
+                        vec![]
+                    }
+                };
+            });
+            ret.extend(words.iter().cloned());
         }
+
+        return ret;
     }
+}
 
-    return ret;
+/// Split a list of words, 1 per line or NUL separated, leaving out blanks
+pub fn word_list(text: &str) -> Vec<String> {
+    return text
+        .split(['\n', '\0'])
+        .map(|w| w.trim())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect();
 }
 
-/// This will spell check words supplied on the command-line
-pub fn spell_check_words(word_list: &Vec<String>, words: Vec<String>, top: usize, debug: bool) {
-    let mut topn = top;
-    if words.len() < top {
-        // Handle the custom word list case where
-        topn = words.len();
+/// Find the suggestions for a misspelled word given on the command-line,
+/// along with the words that sound like it if there's a phonetic index
+fn word_suggestions<'a>(
+    word: &str,
+    words: &mut WordBuckets<'a>,
+    suggest: &SuggestOpts,
+    phonetic: Option<&PhoneticIndex<'a>>,
+) -> Vec<(f32, &'a str)> {
+    let mut query = word.to_string();
+    if !word.is_ascii() {
+        // Try the plain ascii version of the word for suggestions
+        query = transliterate(word);
+        debug!("Transliterated \"{}\" to \"{}\"", word, query);
     }
-    debug!("topn: {}", topn);
 
+    // Only the plain ratio rules out lengths without a distance
+    let found = match (suggest.max_distance, suggest.metric, suggest.keyboard) {
+        (Some(max), _, _) => suggest.find(&query, &words.near(&query, max)),
+        (None, Metric::Ratio, None) => words.find_word(&query, suggest.top),
+        (None, _, _) => suggest.find(&query, &words.all()),
+    };
+
+    return match phonetic {
+        Some(phonetic) => phonetic.add_to(&query, found, suggest, suggest.top),
+        None => found,
+    };
+}
+
+/// This will spell check words supplied on the command-line.  A word in the
+/// dictionary is reported as correct without looking for suggestions.  When
+/// there's more than one word, each one's result goes under a `== word ==`
+/// heading, and with `json` each is a JSON object on its own line instead.
+/// Returns whether they were all correct.
+pub fn spell_check_words(
+    word_list: &[String],
+    mut words: WordBuckets,
+    suggest: SuggestOpts,
+    json: bool,
+    debug: bool,
+) -> bool {
+    // A word can sound like one of any length, so the phonetic index needs
+    // every word, which is only worth loading for the misspellings
+    let misspelled: Vec<&str> = word_list
+        .iter()
+        .filter(|w| !words.knows(w))
+        .map(|w| w.as_str())
+        .collect();
+    let phonetic = match suggest.phonetic && !misspelled.is_empty() {
+        true => Some(PhoneticIndex::for_misspellings(&words.all(), &misspelled)),
+        false => None,
+    };
+    let mut all_correct = true;
     for (i, word) in word_list.iter().enumerate() {
-        let matches = find_word(word, &words);
+        let correct = words.knows(word);
+        all_correct &= correct;
+        let matches = match correct {
+            true => vec![],
+            false => word_suggestions(word, &mut words, &suggest, phonetic.as_ref()),
+        };
 
-        for j in 0..topn {
-            let (ratio, word) = matches[j];
+        if json {
+            let suggestions: Vec<&str> = matches.iter().map(|m| m.1).collect();
+            println!(
+                "{}",
+                json!({"word": word, "correct": correct, "suggestions": suggestions})
+            );
+            continue;
+        }
+
+        if word_list.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("== {} ==", word);
+        }
+        if correct {
+            println!("{}: correct", word);
+        }
+        for &(ratio, word) in &matches {
             if debug {
                 println!("{}: {}", word, ratio);
             } else {
                 println!("{}", word);
             }
+        }
+    }
 
-            if ratio == 1.0 {
-                debug!("Found an exact match for our check");
-                // If we have an exact match, just break out
-                break;
+    return all_correct;
+}
+
+/// How many of the best matches get reranked with a bigram model
+const RERANK_POOL: usize = 20;
+
+/// Check the words in a phrase (or sentence) supplied on the command-line,
+/// showing suggestions for the misspelled ones and then the phrase with
+/// each misspelling replaced with its top suggestion.  If a bigram model is
+/// supplied, suggestions are reranked by the words around them.
+pub fn spell_check_phrase(
+    phrase: &str,
+    words: Vec<&str>,
+    ign_list: &HashSet<String>,
+    suggest: SuggestOpts,
+    ngrams: Option<&BigramModel>,
+    opts: &CheckOpts,
+    set_impl: SetImpl,
+) {
+    let wset = WordSet::new(&words, set_impl);
+    let suggester = Suggester::new(&words, suggest);
+    let tokens = tokenize_spans(phrase);
+    let mut corrected = String::new();
+    let mut last = 0;
+    let mut noted = false;
+
+    for (i, (span, word)) in tokens.iter().enumerate() {
+        if ign_list.contains(word) {
+            continue;
+        }
+
+        if wset.contains(word.as_str()) {
+            if let (true, Some(model)) = (opts.real_word, ngrams) {
+                let prev = i.checked_sub(1).map(|p| tokens[p].1.as_str());
+                let next = tokens.get(i + 1).map(|(_, n)| n.as_str());
+                if let Some(alt) = real_word_error(model, prev, word, next) {
+                    println!("\"{}\": note: possible confusion with \"{}\"", word, alt);
+                    noted = true;
+                    continue;
+                }
+            }
+
+            if let Some(hint) = confusable_hint(word).filter(|_| opts.confusable_notes) {
+                println!("\"{}\": note: {}", word, hint);
+                noted = true;
             }
+            continue;
+        }
+
+        let query = translit_fallback(word, &wset).unwrap_or_else(|| word.clone());
+        let mut matches = suggester.find_n(&query, RERANK_POOL.max(suggest.top));
+        if let Some(model) = ngrams {
+            let prev = i.checked_sub(1).map(|p| tokens[p].1.as_str());
+            let next = tokens.get(i + 1).map(|(_, n)| n.as_str());
+            matches = model.rerank(&matches, prev, next);
         }
+        let suggestions: Vec<&str> = matches.iter().take(suggest.top).map(|(_, w)| *w).collect();
+        println!("\"{}\": {}", word, suggestions.join(", "));
 
-        if i != word_list.len() - 1 {
-            println!("\n-----\n");
+        if let Some(best) = suggestions.first() {
+            corrected.push_str(&phrase[last..span.start]);
+            corrected.push_str(&match_case(&phrase[span.clone()], best));
+            last = span.end;
         }
     }
+    corrected.push_str(&phrase[last..]);
+
+    if last > 0 || noted {
+        println!();
+    }
+    println!("{}", corrected);
 }
 
-pub fn read_bytes(path: &PathBuf) -> Result<Vec<u8>> {
+pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
     let real_path = parse_path(path);
     let mut f = File::open(real_path)?;
     let mut ret = vec![];
@@ -288,6 +1961,72 @@ pub fn read_bytes(path: &PathBuf) -> Result<Vec<u8>> {
     return Ok(ret);
 }
 
+/// Replace the contents of a file without ever leaving it truncated: the
+/// new contents go to a temp file in the same directory, which gets the
+/// original's permissions, owner and extended attributes (where we're
+/// allowed), and is then renamed over it.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    // Write to the target of a symlink, rather than replacing the link
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let meta = fs::metadata(path).ok();
+
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".spel")
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    tmp.write_all(contents.as_bytes())?;
+    tmp.as_file().sync_all()?;
+
+    if let Some(meta) = meta {
+        fs::set_permissions(tmp.path(), meta.permissions())?;
+        copy_owner_and_xattrs(path, tmp.path(), &meta);
+    }
+
+    tmp.persist(path)?;
+
+    return Ok(());
+}
+
+/// Best effort copying of the owner and extended attributes, which can
+/// fail for an unprivileged user or a filesystem without xattrs
+#[cfg(unix)]
+fn copy_owner_and_xattrs(from: &Path, to: &Path, meta: &fs::Metadata) {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    if let Err(e) = chown(to, Some(meta.uid()), Some(meta.gid())) {
+        debug!(
+            "Could not preserve the owner of \"{}\": {}",
+            from.display(),
+            e
+        );
+    }
+
+    let names = match xattr::list(from) {
+        Ok(names) => names,
+        Err(_) => return,
+    };
+
+    for name in names {
+        if let Ok(Some(value)) = xattr::get(from, &name) {
+            if let Err(e) = xattr::set(to, &name, &value) {
+                debug!(
+                    "Could not preserve xattr {:?} of \"{}\": {}",
+                    name,
+                    from.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner_and_xattrs(_from: &Path, _to: &Path, _meta: &fs::Metadata) {}
+
 #[test]
 fn test_readlines() {
     let fname = "english.txt"; // This should always be here
@@ -332,28 +2071,437 @@ fn test_tokenize() {
     let test3 = "A Bad Deal";
     let res = tokenize(test3);
     assert_eq!(res, vec!["a", "bad", "deal"]);
+
+    // Test non-ascii letters
+    let test4 = "Her Résumé, naïvely";
+    let res = tokenize(test4);
+    assert_eq!(res, vec!["her", "résumé", "naïvely"]);
+}
+
+#[test]
+fn test_tokenize_spans() {
+    let line = "Jay's  hyphen-ated, players' (words)";
+    let res = tokenize_spans(line);
+    let spans: Vec<&str> = res.iter().map(|(s, _)| &line[s.clone()]).collect();
+    assert_eq!(spans, vec!["Jay", "hyphen-ated", "players", "words"]);
+    assert_eq!(res[0].1, "jay");
+
+    let line = "naïve café";
+    let res = tokenize_spans(line);
+    assert_eq!(&line[res[1].0.clone()], "café");
+
+    // The arena version gives the same words, but keeps their case and
+    // borrows them from the line
+    let arena = Bump::new();
+    let line = "Jay's  hyphen-ated, players' (words)";
+    let res = tokenize_spans_in(line, &arena);
+    let folded: Vec<(Range<usize>, String)> = res
+        .iter()
+        .map(|(span, w)| (span.clone(), w.to_lowercase()))
+        .collect();
+    assert_eq!(folded, tokenize_spans(line));
+    assert_eq!(res[0].1, "Jay");
+    assert_eq!(res[0].1.as_ptr(), line.as_ptr());
+    assert_eq!(arena.allocated_bytes(), 0);
+
+    // NUL filler is dropped from inside words, but still part of the span
+    let line = "caf\u{e9}\0\0 don'\0\0t";
+    let res = tokenize_spans(line);
+    assert_eq!(res[0], (0..7, "café".to_string()));
+    assert_eq!(res[1], (8..15, "don't".to_string()));
+    assert_eq!(tokenize_spans_in(line, &arena)[0], (0..7, "café"));
+}
+
+#[test]
+fn test_match_case() {
+    assert_eq!(match_case("Recieve", "receive"), "Receive");
+    assert_eq!(match_case("RECIEVE", "receive"), "RECEIVE");
+    assert_eq!(match_case("recieve", "receive"), "receive");
+    assert_eq!(match_case("I", "a"), "A");
+}
+
+#[test]
+fn test_check_file() {
+    let words = WordSet::new(&["a", "dog"], SetImpl::Hash);
+    let ign_list = to_hashset(vec!["spel".to_string()]);
+    let opts = CheckOpts {
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    let lookups = Lookups::build(&words, &ign_list, &opts, None);
+    let fname = PathBuf::from("a.txt");
+
+    let text = "a dgo\r\nspel a dog\n\na café cat";
+    let res = check_file(
+        &fname,
+        Cursor::new(text),
+        &words,
+        &ign_list,
+        &opts,
+        &lookups,
+    );
+    assert_eq!(res.len(), 3);
+    assert_eq!(
+        res[0],
+        Diagnostic::new(
+            fname.clone(),
+            1,
+            Span::new(2..5, 2..5, 2..5, 0, 0),
+            "dgo",
+            Kind::Misspelling
+        )
+        .with_fingerprint("a dgo", 0)
+    );
+    assert_eq!(res[1].word, "café");
+    assert_eq!(res[1].span, Span::new(2..7, 2..6, 2..6, 19, 19));
+    assert_eq!(&text[res[1].span.bytes.clone()], "café");
+
+    // Each distinct word is only classified once
+    assert_eq!(lookups.classes.read().unwrap().len(), 6);
+    assert_eq!(
+        lookups.classes.read().unwrap()[&Caseless("DGO")],
+        Class::Unknown
+    );
+
+    // The multibyte é shifts the byte offsets, but not the chars
+    assert_eq!(res[2].span.bytes, 27..30);
+    assert_eq!(res[2].span.chars, 26..29);
+    assert_eq!(res[2].span.line_chars, 7..10);
+
+    // A word found twice on a line gets a fingerprint for each
+    let res = check_file(
+        &fname,
+        Cursor::new("dgo a dgo\n\n dgo  a  dgo"),
+        &words,
+        &ign_list,
+        &opts,
+        &lookups,
+    );
+    let ids: Vec<&str> = res.iter().map(|d| d.fingerprint.as_str()).collect();
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(ids[..2], ids[2..]);
+
+    // Code spans are only skipped when asked, but not in files with their
+    // own filter
+    let check = |fname: &str, text: &str, opts: &CheckOpts| {
+        let res = check_file(
+            Path::new(fname),
+            Cursor::new(text),
+            &words,
+            &ign_list,
+            opts,
+            &lookups,
+        );
+        return res.len();
+    };
+    assert_eq!(check("a.txt", "a `dgo` dog", &opts), 1);
+    // Any case of a known word is fine
+    assert_eq!(check("a.txt", "A Dog DOG dOG", &opts), 0);
+    let opts = CheckOpts {
+        skip_code_spans: true,
+        ..opts
+    };
+    assert_eq!(check("a.txt", "a `dgo` dog", &opts), 0);
+    assert_eq!(check("a.eml", "a `dgo` dog", &opts), 1);
+
+    // Only the comments of source files, with --comments-only
+    let text = "int dgo; // a dgo";
+    let opts = CheckOpts {
+        comments_only: true,
+        ..opts
+    };
+    assert_eq!(check("a.c", text, &opts), 1);
+    assert_eq!(check("a.txt", text, &opts), 3);
+}
+
+#[test]
+fn test_ignore_count() {
+    let words = WordSet::new(&["a", "dog"], SetImpl::Hash);
+    let ign_list = to_hashset(
+        ["spel", "toml", "ad hoc", "status quo", "Dog"]
+            .map(String::from)
+            .to_vec(),
+    );
+    let opts = CheckOpts {
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    let lookups = Lookups::build(&words, &ign_list, &opts, None);
+    let text = "Spel a dog, ad hoc\nspel";
+    check_file(
+        Path::new("a.txt"),
+        Cursor::new(text),
+        &words,
+        &ign_list,
+        &opts,
+        &lookups,
+    );
+
+    assert_eq!(lookups.ignore_count("spel"), 2);
+    assert_eq!(lookups.ignore_count("ad hoc"), 1);
+    assert_eq!(lookups.ignore_count("toml"), 0);
+    assert_eq!(lookups.ignore_count("status quo"), 0);
+    // Found, but as a dictionary word
+    assert_eq!(lookups.ignore_count("Dog"), 0);
+}
+
+#[test]
+fn test_check_long_lines() {
+    let words = WordSet::new(&["a", "dog"], SetImpl::Hash);
+    let ign_list = HashSet::new();
+    let opts = CheckOpts {
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    let lookups = Lookups::build(&words, &ign_list, &opts, None);
+    let check = |text: &str, max_piece| {
+        let fname = Path::new("a.txt");
+        let reader = Cursor::new(text);
+        return check_pieces(fname, reader, &words, &ign_list, &opts, &lookups, max_piece)
+            .unwrap()
+            .0;
+    };
+
+    // Checking a piece at a time finds the same words in the same places
+    let text = "a dgo, dog café ".repeat(20) + "\nnext dgo\n" + &"dgo ".repeat(10);
+    let whole = check(&text, MAX_PIECE);
+    assert_eq!(whole.len(), 52);
+    assert_eq!(check(&text, 16), whole);
+    assert_eq!(check(&text, 7), whole);
+    assert_eq!(whole[39].span.line_chars, 315..319);
+    assert_eq!(whole[39].span.line_bytes, 334..339);
+    assert_eq!(
+        (whole[40].line, whole[40].span.bytes.start),
+        (2, text.find("next").unwrap())
+    );
+
+    // A word longer than a piece gets split up, without breaking a char
+    let res = check("ééééé dgo", 4);
+    assert_eq!(res.last().unwrap().span.line_bytes, 11..14);
+    assert_eq!(res.last().unwrap().span.line_chars, 6..9);
+
+    // The lines of filtered files aren't split, as a filter only knows
+    // where it is in a whole line
+    let opts = CheckOpts {
+        skip_code_spans: true,
+        ..opts.clone()
+    };
+    let (res, lines) = check_pieces(
+        Path::new("a.txt"),
+        Cursor::new("a `dgo dgo dgo` dgo"),
+        &words,
+        &ign_list,
+        &opts,
+        &lookups,
+        4,
+    )
+    .unwrap();
+    assert_eq!(lines, 1);
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].span.line_chars, 16..19);
+    // None of the text counts if any of it isn't utf-8
+    let bad = Cursor::new(&b"dgo dgo\ncaf\xe9\n"[..]);
+    let res = check_pieces(
+        Path::new("a.txt"),
+        bad,
+        &words,
+        &ign_list,
+        &opts,
+        &lookups,
+        4,
+    );
+    assert_eq!(res, Err(SkipReason::BadEncoding));
+
+    assert_eq!(piece_end(b"some words"), 5);
+    assert_eq!(piece_end("aé".as_bytes()), 3);
+    assert_eq!(piece_end(&"aé".as_bytes()[..2]), 1);
+}
+
+#[test]
+fn test_find_word() {
+    use difflib::sequencematcher::SequenceMatcher;
+
+    // Every ratio, best first, the slow way
+    fn find_all<'a>(word: &str, word_list: &[&'a str]) -> Vec<(f32, &'a str)> {
+        let mut ret: Vec<(f32, &str)> = word_list
+            .iter()
+            .map(|w| (SequenceMatcher::new(word, *w).ratio(), *w))
+            .collect();
+        ret.sort_by(by_rank);
+        dedup_variants(&mut ret);
+        return ret;
+    }
+
+    let words = get_words(dict_text(BUILTIN_DICT));
+    let sample: Vec<&str> = words.iter().step_by(97).copied().collect();
+    for word in ["recieve", "teh", "a", "spelling", "xyzzy", "naïve", ""] {
+        for top in [1, 5, 20] {
+            let mut want = find_all(word, &sample);
+            want.truncate(top);
+            assert_eq!(find_word(word, &sample, top), want, "{} top {}", word, top);
+        }
+    }
+    assert!(find_word("a", &sample, 0).is_empty());
+    assert!(find_word("a", &[], 5).is_empty());
+
+    // Ties are alphabetical, whatever order the list is in
+    let found = find_word("cat", &["cot", "bat", "cut", "car"], 3);
+    assert_eq!(
+        found.iter().map(|f| f.1).collect::<Vec<_>>(),
+        ["bat", "car", "cot"]
+    );
+
+    // Each word is only suggested once, in its best form
+    let found = find_word(
+        "american",
+        &["American", "america", "american", "american"],
+        5,
+    );
+    assert_eq!(
+        found.iter().map(|f| f.1).collect::<Vec<_>>(),
+        ["american", "america"]
+    );
+    assert!(same_word("ÉTÉ", "été"));
+    assert!(!same_word("Straße", "STRASSE"));
+}
+
+#[test]
+fn test_within_distance() {
+    assert!(within_distance("teh", "the", 2));
+    assert!(!within_distance("teh", "the", 1));
+    assert!(within_distance("recieve", "receive", 2));
+    assert!(within_distance("kitten", "sitting", 3));
+    assert!(!within_distance("kitten", "sitting", 2));
+    assert!(within_distance("", "ab", 2));
+    assert!(!within_distance("a", "abcd", 2));
+    // Counted in chars, not bytes
+    assert!(within_distance("naïve", "naive", 1));
+    assert!(within_distance("café", "cafe", 1));
+
+    let words = ["the", "then", "tea", "xylophone"];
+    let suggest = SuggestOpts {
+        max_distance: Some(1),
+        ..SuggestOpts::new(5)
+    };
+    let found: Vec<&str> = suggest
+        .find("teh", &words)
+        .iter()
+        .map(|(_, w)| *w)
+        .collect();
+    assert_eq!(found, ["tea"]);
+    assert_eq!(SuggestOpts::new(5).find("teh", &words).len(), 4);
+
+    // A swap is 1 edit with damerau, and ranks first
+    let damerau = SuggestOpts {
+        metric: Metric::Damerau,
+        ..suggest
+    };
+    let found: Vec<&str> = damerau
+        .find("teh", &words)
+        .iter()
+        .map(|(_, w)| *w)
+        .collect();
+    assert_eq!(found, ["the", "tea"]);
+
+    // W is next to E on the keyboard, but not I
+    let words = ["spill", "spell", "small"];
+    let found = SuggestOpts::new(5).find("spwll", &words);
+    assert_eq!(found[0].0, found[1].0);
+    let keyboard = SuggestOpts {
+        keyboard: Some(Layout::Qwerty),
+        ..SuggestOpts::new(5)
+    };
+    let found: Vec<&str> = keyboard
+        .find("spwll", &words)
+        .iter()
+        .map(|(_, w)| *w)
+        .collect();
+    assert_eq!(found, ["spell", "spill", "small"]);
+}
+
+#[test]
+fn test_suggest_cache() {
+    let words = ["receive", "relieve", "dog"];
+    let mut cache = SuggestCache::new(&words, SuggestOpts::new(2));
+    let finding =
+        |word: &str, kind| Diagnostic::new(PathBuf::new(), 1, Span::default(), word, kind);
+
+    let mut d = finding("recieve", Kind::Misspelling);
+    cache.add_to(&mut d);
+    assert_eq!(d.suggestions, ["receive", "relieve"]);
+    assert_eq!(cache.cache.len(), 1);
+    let mut d = finding("recieve", Kind::Misspelling);
+    cache.add_to(&mut d);
+    assert_eq!(d.suggestions, ["receive", "relieve"]);
+    assert_eq!(cache.cache.len(), 1);
+
+    // The ones that already have a suggestion keep it
+    let mut d = finding("cafe", Kind::Variant).with_suggestions(vec!["café".to_string()]);
+    cache.add_to(&mut d);
+    assert_eq!(d.suggestions, ["café"]);
+    let mut d = finding("dgo", Kind::Note);
+    cache.add_to(&mut d);
+    assert!(d.suggestions.is_empty());
+}
+
+#[test]
+fn test_ignore_file_until() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ignore");
+    fs::write(
+        &path,
+        "# until=2000-01-01\nold # until=2000-01-01\nnew # until=9999-12-31\nC#\nbad # until=soon\n",
+    )
+    .unwrap();
+    assert_eq!(get_ignore_file_contents(&path), ["new", "C#", "bad"]);
+
+    // The same for a list given with --ignore @file
+    let from_file = [format!("@{}", path.display())];
+    assert_eq!(ignore_words(&from_file), ["new", "C#", "bad"]);
+
+    // Each list is only read once, however often it's used
+    let mut lists = WordLists::default();
+    assert_eq!(lists.ignore_file(&path), ["new", "C#", "bad"]);
+    assert_eq!(lists.ignore_words(&from_file), ["new", "C#", "bad"]);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        lists.ignore_list(&["a".to_string()], &path),
+        ["new", "C#", "bad", "a"]
+    );
+    assert!(get_ignore_file_contents(&path).is_empty());
 }
 
 #[test]
 fn test_get_ignore_list() {
-    let s = Some("a,b,c".to_string());
+    let s = ["a,b,c".to_string()];
 
     assert_eq!(
         get_ignore_list(&s, &PathBuf::from("")),
         vec!["a".to_string(), "b".to_string(), "c".to_string()],
     );
 
-    let s2 = Some("a , b  , c,".to_string());
+    let s2 = ["a , b  , c,".to_string()];
     assert_eq!(
         get_ignore_list(&s2, &PathBuf::from("")),
         vec!["a".to_string(), "b".to_string(), "c".to_string()],
     );
 
-    let s3 = Some("  , ".to_string());
-    assert!(get_ignore_list(&s3, &PathBuf::from("")).len() == 0);
-
-    let s4 = None;
-    assert!(get_ignore_list(&s4, &PathBuf::from("")).len() == 0);
+    let s3 = ["  , ".to_string()];
+    assert!(get_ignore_list(&s3, &PathBuf::from("")).is_empty());
+
+    assert!(get_ignore_list(&[], &PathBuf::from("")).is_empty());
+
+    // Given more than once, and from a file
+    let dir = tempfile::tempdir().unwrap();
+    let extra = dir.path().join("extra.txt");
+    fs::write(&extra, "# More words\nd\n\n e \n").unwrap();
+    let s5 = [
+        "a,b".to_string(),
+        format!("@{}", extra.display()),
+        "c".to_string(),
+        format!("@{}", dir.path().join("nope").display()),
+    ];
+    assert_eq!(ignore_words(&s5), ["a", "b", "d", "e", "c"]);
 }
 
 #[test]
@@ -371,6 +2519,108 @@ fn test_parse_path() {
         let p = PathBuf::from(&format!("{}/some/file.txt", home_dir));
         assert_eq!(parse_path(&p), p);
     }
+
+    #[cfg(unix)]
+    if let Some(root) = uzers::get_user_by_uid(0) {
+        use uzers::os::unix::UserExt;
+
+        let p = PathBuf::from(format!(
+            "~{}/dicts/words.txt",
+            root.name().to_string_lossy()
+        ));
+        assert_eq!(parse_path(&p), root.home_dir().join("dicts/words.txt"));
+    }
+
+    // An unknown user leaves the path alone
+    let p = PathBuf::from("~no-such-user-spel/words.txt");
+    assert_eq!(parse_path(&p), p);
+}
+
+#[test]
+fn test_translit_fallback() {
+    let words = WordSet::new(&["strasse", "cafe"], SetImpl::Hash);
+
+    assert_eq!(
+        translit_fallback("straße", &words),
+        Some("strasse".to_string())
+    );
+    assert_eq!(translit_fallback("café", &words), Some("cafe".to_string()));
+    assert_eq!(translit_fallback("cafe", &words), None);
+    assert_eq!(translit_fallback("über", &words), None);
+}
+
+#[test]
+fn test_collect_files() {
+    use std::fs::{create_dir_all, write};
+
+    // Not a hidden directory, which a walk would skip
+    let tmp = tempfile::Builder::new().prefix("spel").tempdir().unwrap();
+    let dir = tmp.path().to_owned();
+    create_dir_all(dir.join("sub")).unwrap();
+    create_dir_all(dir.join(".hidden")).unwrap();
+    write(dir.join("b.txt"), "b").unwrap();
+    write(dir.join("sub/a.txt"), "a").unwrap();
+    write(dir.join(".hidden/c.txt"), "c").unwrap();
+
+    assert_eq!(
+        collect_files(
+            &[dir.clone(), PathBuf::from("other.txt")],
+            &WalkOpts::default()
+        ),
+        vec![
+            dir.join("b.txt"),
+            dir.join("sub/a.txt"),
+            PathBuf::from("other.txt")
+        ],
+    );
+
+    write(dir.join("sub/d.md"), "d").unwrap();
+    write(dir.join("e.md"), "e").unwrap();
+    let walk = |include: &[&str], exclude: &[&str]| WalkOpts {
+        include: include.iter().map(|g| g.to_string()).collect(),
+        exclude: exclude.iter().map(|g| g.to_string()).collect(),
+        no_ignore: false,
+    };
+    let dirs = [dir.clone()];
+    let md = collect_files(&dirs, &walk(&["*.md"], &[]));
+    assert_eq!(md, vec![dir.join("e.md"), dir.join("sub/d.md")]);
+    let md = collect_files(&dirs, &walk(&["*.md"], &["sub"]));
+    assert_eq!(md, vec![dir.join("e.md")]);
+    // Globs with a `/` go by the path under the directory being walked
+    let txt = collect_files(
+        &[dir.clone(), PathBuf::from("-")],
+        &walk(&[], &["sub/**", "*.md"]),
+    );
+    assert_eq!(txt, vec![dir.join("b.txt"), PathBuf::from("-")]);
+    // Or under the current directory
+    let cwd = std::env::current_dir().unwrap();
+    let src = collect_files(&[cwd.join("src/lib.rs")], &walk(&["src/*.rs"], &[]));
+    assert_eq!(src, vec![cwd.join("src/lib.rs")]);
+    // Given explicitly, they're still filtered
+    let given = collect_files(&[dir.join("b.txt")], &walk(&["*.md"], &[]));
+    assert!(given.is_empty());
+
+    // The ignore files are followed, unless told not to
+    write(dir.join(".gitignore"), "*.md\n").unwrap();
+    write(dir.join("sub/.ignore"), "!d.md\n").unwrap();
+    let walked = collect_files(&dirs, &WalkOpts::default());
+    assert_eq!(
+        walked,
+        vec![
+            dir.join("b.txt"),
+            dir.join("sub/a.txt"),
+            dir.join("sub/d.md")
+        ]
+    );
+    let no_ignore = WalkOpts {
+        no_ignore: true,
+        ..WalkOpts::default()
+    };
+    assert_eq!(collect_files(&dirs, &no_ignore).len(), 4);
+    assert_eq!(
+        collect_files(&[dir.join("e.md")], &WalkOpts::default()).len(),
+        1
+    );
 }
 
 #[test]
@@ -380,13 +2630,31 @@ fn test_check_token() {
     assert!(!check_token("1"));
 }
 
+#[test]
+fn test_word_list() {
+    assert_eq!(word_list("teh\r\n\n  recieve \n"), ["teh", "recieve"]);
+    assert_eq!(word_list("teh\0recieve\0"), ["teh", "recieve"]);
+    assert!(word_list("").is_empty());
+}
+
 #[test]
 fn test_get_words() {
-    let bytes = b"this\nis\na\nword\n";
-    assert_eq!(get_words(bytes), vec!["this", "is", "a", "word"]);
+    let text = "this\nis\na\nword\n";
+    assert_eq!(get_words(text), vec!["this", "is", "a", "word"]);
+
+    let text = "a\ndifferent\ntest";
+    assert_eq!(get_words(text), vec!["a", "different", "test"]);
+
+    // Invalid utf-8 is replaced rather than dropping the dictionary
+    assert_eq!(dict_text(b"caf\xe9\nok"), "caf\u{fffd}\nok");
+    assert_eq!(dict_text(b"ok"), "ok");
 
-    let bytes = b"a\ndifferent\ntest";
-    assert_eq!(get_words(bytes), vec!["a", "different", "test"]);
+    // Words are only taken from the first dictionary that has them
+    assert_eq!(
+        merge_dicts(&["cat\ndog\n", "dog\nfish"]),
+        "cat\ndog\nfish\n"
+    );
+    assert_eq!(merge_dicts(&[]), "");
 }
 
 #[test]
@@ -409,6 +2677,7 @@ fn test_strip_apost() {
 }
 
 #[test]
+#[allow(clippy::suspicious_open_options)]
 fn test_read_bytes() {
     use std::{
         fs::{remove_file, OpenOptions},
@@ -435,3 +2704,230 @@ fn test_read_bytes() {
         remove_file(&fname).unwrap();
     }
 }
+
+#[test]
+fn test_is_generated() {
+    assert!(is_generated("// Code generated by mockgen. DO NOT EDIT.\n"));
+    assert!(is_generated(
+        "#!/bin/sh\n\n# This file is @generated by a tool\n"
+    ));
+    assert!(is_generated("<!--\n  AUTO-GENERATED FILE\n-->\n"));
+    assert!(!is_generated("# Notes\nGenerated files are skipped\n"));
+    assert!(!is_generated("1\n2\n3\n4\n5\n# Do not edit below\n"));
+    assert!(!is_generated(""));
+}
+
+#[test]
+fn test_is_minified() {
+    let js = "!function(e,t){\"object\"==typeof exports&&\"undefined\"!=typeof module?\
+module.exports=t():\"function\"==typeof define&&define.amd?define(t):(e=e||self).Q=t()}\
+(this,function(){var n=[],r=0;function o(e){return n[e]||(n[e]=r++)}return{id:o,\
+ids:function(){return n.slice(0)},x:7*Math.PI/3|0}});";
+    assert!(is_minified(js));
+    assert!(is_minified(&format!("{}\n", js.repeat(10))));
+    // Base64 data, which has few symbols but a high entropy
+    let b64 = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let data: String = (0..3000_u32)
+        .map(|i| b64[(i.wrapping_mul(2654435761) >> 26) as usize] as char)
+        .collect();
+    assert!(is_minified(&data));
+
+    // Long lines of prose, like Markdown with a paragraph per line
+    let para = "A spell checker for the command line, which can check single \
+words, phrases or whole files, with suggestions for anything it doesn't know. ";
+    let text = format!("# Spel\n\n{}\n\n{}\n", para.repeat(3), para.repeat(2));
+    assert!(!is_minified(&text));
+    // A single paragraph that's longer than any line of code
+    assert!(!is_minified(&para.repeat(10)));
+    assert!(!is_minified(include_str!("util.rs")));
+    assert!(!is_minified(""));
+}
+
+#[test]
+fn test_load_file() {
+    use std::{fs::remove_file, io::Write};
+
+    let dir = tempfile::tempdir().unwrap();
+    let fname = dir.path().join("load_file_test");
+    let write = |bytes: &[u8]| {
+        let mut f = File::create(&fname).unwrap();
+        f.write_all(bytes).unwrap();
+    };
+
+    write(b"some text\n");
+    assert_eq!(load_file(&fname, 1024).unwrap(), "some text\n");
+    assert_eq!(load_file(&fname, 4), Err(SkipReason::TooLarge(10)));
+
+    write(b"bin\0ary");
+    assert_eq!(load_file(&fname, 1024), Err(SkipReason::Binary));
+
+    // A stream is only read in up to the limit
+    assert_eq!(load_reader(&b"some text"[..], 9).unwrap(), "some text");
+    assert_eq!(
+        load_reader(&b"some text"[..], 8),
+        Err(SkipReason::TooLarge(9))
+    );
+    assert_eq!(
+        load_reader(io::repeat(b'a').take(1 << 20), 4096),
+        Err(SkipReason::TooLarge(1 << 20))
+    );
+
+    write(b"caf\xe9");
+    assert_eq!(load_file(&fname, 1024), Err(SkipReason::BadEncoding));
+
+    write(b"// Code generated by protoc. DO NOT EDIT.\npackage pb\n");
+    let mut opts = CheckOpts::default();
+    assert_eq!(opts.load(&fname), Err(SkipReason::Generated));
+    opts.check_generated = true;
+    assert!(opts.load(&fname).is_ok());
+
+    write(
+        "var a={b:1,c:[2,3]};function f(x){return x*2};"
+            .repeat(40)
+            .as_bytes(),
+    );
+    opts.check_generated = false;
+    assert_eq!(opts.load(&fname), Err(SkipReason::Minified));
+    opts.check_minified = true;
+    assert!(opts.load(&fname).is_ok());
+
+    remove_file(&fname).unwrap();
+
+    assert!(matches!(
+        load_file(&fname, 1024),
+        Err(SkipReason::OpenFailed(_))
+    ));
+}
+
+#[test]
+fn test_check_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = vec![];
+    for i in 0..20 {
+        let path = dir.path().join(format!("{:02}.txt", i));
+        // Some bigger than others, so they finish out of order
+        fs::write(
+            &path,
+            "the cat sat on the mat\n".repeat(i % 3 * 200) + "wrod\n",
+        )
+        .unwrap();
+        files.push(path);
+    }
+    files.insert(5, dir.path().join("missing.txt"));
+
+    let words = ["the", "cat", "sat", "on", "mat"];
+    let wset = WordSet::new(&words, SetImpl::Hash);
+    let ign_list = HashSet::new();
+    let opts = CheckOpts::default();
+    let lookups = Lookups::build(&wset, &ign_list, &opts, None);
+    for jobs in [1, 4, 100] {
+        let mut seen = vec![];
+        let skipped = check_files(
+            &files,
+            &wset,
+            &ign_list,
+            &opts,
+            &lookups,
+            jobs,
+            |f, _, d| {
+                assert_eq!(d.len(), 1);
+                seen.push(f.to_owned());
+            },
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, files[5]);
+        assert_eq!(seen, [&files[..5], &files[6..]].concat());
+    }
+    assert!(check_files(&[], &wset, &ign_list, &opts, &lookups, 4, |_, _, _| ()).is_empty());
+}
+
+#[test]
+fn test_stream_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let big = "the cat sat on the mat\n".repeat(50_000);
+    let write = |name: &str, text: &[u8]| {
+        let path = dir.path().join(name);
+        fs::write(&path, text).unwrap();
+        return path;
+    };
+    let files = [
+        write("plain.txt", format!("wrod\n{}cta wrod", big).as_bytes()),
+        write(
+            "mode.txt",
+            format!("{}wrod\n# spel: lang=en_GB ignore=wrod skip-acronyms", big).as_bytes(),
+        ),
+        write("gen.txt", format!("// @generated\n{}", big).as_bytes()),
+        write("bin.txt", format!("a\0b\n{}", big).as_bytes()),
+    ];
+
+    let words = ["the", "cat", "sat", "on", "mat"];
+    let wset = WordSet::new(&words, SetImpl::Hash);
+    let ign_list = HashSet::new();
+    let mut opts = CheckOpts {
+        max_file_size: 10 << 20,
+        ..Default::default()
+    };
+    let lookups = Lookups::build(&wset, &ign_list, &opts, None);
+    let run = |files: &[PathBuf], opts: &CheckOpts| {
+        let mut found = vec![];
+        let skipped = check_files(files, &wset, &ign_list, opts, &lookups, 2, |_, text, d| {
+            found.push((text.len(), d));
+        });
+        let skipped: Vec<String> = skipped.iter().map(|s| s.reason.to_string()).collect();
+        return (found, skipped);
+    };
+
+    // The same findings without the text, and the same files skipped
+    let (kept, kept_skipped) = run(&files, &opts);
+    opts.keep_text = false;
+    let (streamed, skipped) = run(&files, &opts);
+    assert_eq!(skipped, kept_skipped);
+    assert_eq!(skipped, ["generated file", "binary file"]);
+    assert_eq!(kept.len(), 2);
+    for ((len, kept), (streamed_len, streamed)) in kept.iter().zip(&streamed) {
+        assert!(*len > 0);
+        assert_eq!(*streamed_len, 0);
+        assert_eq!(kept, streamed);
+    }
+    let words: Vec<&str> = streamed[0].1.iter().map(|d| d.word.as_str()).collect();
+    assert_eq!(words, ["wrod", "cta", "wrod"]);
+    assert_eq!(streamed[0].1[2].line, 50_002);
+    // The modeline at the end ignores the word, for all its unknown
+    // options, and isn't itself checked
+    assert!(streamed[1].1.is_empty());
+
+    // A file that isn't utf-8 past its start is skipped whole, the same
+    // as when it's read in
+    let bad = [write(
+        "bad.txt",
+        &[format!("{}wrod\n", big).as_bytes(), b"caf\xe9 wrod\n"].concat(),
+    )];
+    for opts in [&opts, &CheckOpts::default()] {
+        let (found, skipped) = run(&bad, opts);
+        assert!(found.is_empty());
+        assert_eq!(skipped, ["not valid utf-8"]);
+    }
+}
+
+#[test]
+fn test_write_atomic() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "old contents\n").unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+
+    write_atomic(&path, "new contents\n").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new contents\n");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+}
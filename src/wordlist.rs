@@ -0,0 +1,270 @@
+use crate::util::parse_path;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use std::{collections::HashSet, fs, io, path::Path};
+
+/// A word list file, like an ignore file or the personal dictionary, laid
+/// out the way it was written: sections split by blank lines, with lines
+/// starting with `#` as comments.  It's written back out with each
+/// section sorted and without duplicates.  The comments at the top of a
+/// section stay there, the others stay with the word under them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WordList {
+    sections: Vec<Section>,
+}
+
+/// The words between blank lines, along with the comments before the
+/// first of them and after the last
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Section {
+    heading: Vec<String>,
+    entries: Vec<Entry>,
+    trailing: Vec<String>,
+}
+
+/// A word, with the comments just above it and the one after it
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    comments: Vec<String>,
+    word: String,
+    note: Option<String>,
+}
+
+/// Split a line of a word list into the word and the comment after it, if
+/// there is one.  The `#` has to have a space before it, since some words,
+/// like "C#", have one.
+pub fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let line = line.trim();
+    let hash = line
+        .char_indices()
+        .find(|&(i, c)| c == '#' && line[..i].ends_with(char::is_whitespace));
+
+    return match hash {
+        Some((i, _)) => (line[..i].trim_end(), Some(line[i + 1..].trim())),
+        None => (line, None),
+    };
+}
+
+/// The last day an entry is honored, from an "until=YYYY-MM-DD" in the
+/// comment after it
+pub fn until(comment: &str) -> Option<Result<NaiveDate>> {
+    let date = comment
+        .split_whitespace()
+        .find_map(|w| w.strip_prefix("until="))?;
+    let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("\"until={}\" isn't a YYYY-MM-DD date", date));
+
+    return Some(parsed);
+}
+
+impl WordList {
+    pub fn parse(text: &str) -> Self {
+        let mut sections = vec![];
+        let mut cur = Section::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                if !cur.entries.is_empty() || !cur.trailing.is_empty() {
+                    sections.push(std::mem::take(&mut cur));
+                }
+            } else if line.starts_with('#') {
+                cur.trailing.push(line.to_string());
+            } else {
+                let mut comments = std::mem::take(&mut cur.trailing);
+                if cur.entries.is_empty() && cur.heading.is_empty() {
+                    cur.heading = std::mem::take(&mut comments);
+                }
+                let (word, note) = split_comment(line);
+                cur.entries.push(Entry {
+                    comments,
+                    word: word.to_string(),
+                    note: note.map(|n| n.to_string()),
+                });
+            }
+        }
+        if !cur.entries.is_empty() || !cur.trailing.is_empty() {
+            sections.push(cur);
+        }
+
+        return Self { sections };
+    }
+
+    /// Load the file, which is empty if it doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        return match fs::read_to_string(parse_path(path)) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    /// The words, in the order they're in the file
+    pub fn words(&self) -> Vec<&str> {
+        return self
+            .sections
+            .iter()
+            .flat_map(|s| &s.entries)
+            .map(|e| e.word.as_str())
+            .collect();
+    }
+
+    /// The words whose "# until=YYYY-MM-DD" date hasn't passed, in the
+    /// order they're in the file, with a warning about the others.  The
+    /// file is named as `source` in the warnings.
+    pub fn current_words(&self, source: &str) -> Vec<String> {
+        let today = Local::now().date_naive();
+        let mut ret = vec![];
+        for entry in self.sections.iter().flat_map(|s| &s.entries) {
+            let word = &entry.word;
+            match entry.note.as_deref().and_then(until) {
+                Some(Ok(date)) if date < today => {
+                    warn!(
+                        "\"{}\" in {} expired on {}, so it isn't used any more",
+                        word, source, date
+                    );
+                    continue;
+                }
+                Some(Err(e)) => warn!("\"{}\" in {}: {}", word, source, e),
+                _ => (),
+            }
+            ret.push(word.clone());
+        }
+
+        return ret;
+    }
+
+    /// Add the word to the end of the last section, returning false if
+    /// it's already there
+    pub fn add(&mut self, word: &str) -> bool {
+        let word = word.trim();
+        if word.is_empty() || self.words().contains(&word) {
+            return false;
+        }
+        if self.sections.is_empty() {
+            self.sections.push(Section::default());
+        }
+        self.sections.last_mut().unwrap().entries.push(Entry {
+            comments: vec![],
+            word: word.to_string(),
+            note: None,
+        });
+
+        return true;
+    }
+
+    /// Only keep the words that pass, along with their comments
+    pub fn retain<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        for section in &mut self.sections {
+            section.entries.retain(|e| keep(&e.word));
+        }
+    }
+
+    /// The text of the file, with the words of each section sorted.  Only
+    /// the first of a word that's there more than once is kept.
+    pub fn text(&self) -> String {
+        let mut seen = HashSet::new();
+        let mut blocks = vec![];
+        for section in &self.sections {
+            let mut entries: Vec<&Entry> = section
+                .entries
+                .iter()
+                .filter(|e| seen.insert(e.word.as_str()))
+                .collect();
+            entries.sort_by(|a, b| a.word.cmp(&b.word));
+
+            let mut lines: Vec<String> = section.heading.clone();
+            for entry in entries {
+                lines.extend(entry.comments.iter().cloned());
+                lines.push(match &entry.note {
+                    Some(note) => format!("{} # {}", entry.word, note),
+                    None => entry.word.clone(),
+                });
+            }
+            lines.extend(section.trailing.iter().cloned());
+            let block: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+            if !block.is_empty() {
+                blocks.push(block);
+            }
+        }
+
+        return blocks.join("\n");
+    }
+}
+
+#[test]
+fn test_word_list() {
+    let text = "\
+# Project names
+tokio
+# The crate, not the word
+serde\r
+tokio
+
+
+# Jargon
+kubectl   #until=2025-12-31
+# TODO: sort these out
+";
+    let mut list = WordList::parse(text);
+    assert_eq!(list.words(), ["tokio", "serde", "tokio", "kubectl"]);
+    assert_eq!(
+        list.text(),
+        "\
+# Project names
+# The crate, not the word
+serde
+tokio
+
+# Jargon
+kubectl # until=2025-12-31
+# TODO: sort these out
+"
+    );
+
+    assert!(list.add("axum"));
+    assert!(!list.add("serde"));
+    assert!(!list.add(" "));
+    list.retain(|w| w != "tokio");
+    assert_eq!(
+        list.text(),
+        "\
+# Project names
+# The crate, not the word
+serde
+
+# Jargon
+axum
+kubectl # until=2025-12-31
+# TODO: sort these out
+"
+    );
+
+    // Comments and blank lines are all there is
+    assert_eq!(WordList::parse("\n\n").text(), "");
+    assert_eq!(WordList::parse("# a\n\n\n# b").text(), "# a\n\n# b\n");
+    let mut empty = WordList::default();
+    assert!(empty.add("cat"));
+    assert_eq!(empty.text(), "cat\n");
+}
+
+#[test]
+fn test_until() {
+    assert_eq!(split_comment("C#"), ("C#", None));
+    assert_eq!(
+        split_comment(" C# # a language "),
+        ("C#", Some("a language"))
+    );
+    assert_eq!(split_comment("ad hoc\t#x"), ("ad hoc", Some("x")));
+
+    let date = |s: &str| until(s).map(|d| d.ok());
+    assert_eq!(
+        date("until=2025-12-31"),
+        Some(NaiveDate::from_ymd_opt(2025, 12, 31))
+    );
+    assert_eq!(
+        date("temporary, until=2026-01-02 then drop it"),
+        Some(NaiveDate::from_ymd_opt(2026, 1, 2))
+    );
+    assert_eq!(date("until=soon"), Some(None));
+    assert_eq!(date("a comment"), None);
+}
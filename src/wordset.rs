@@ -0,0 +1,193 @@
+use clap::ValueEnum;
+use hashbrown::Equivalent;
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet},
+    hash::{Hash, Hasher},
+};
+
+/// The data structures the dictionary can be looked up in, which trade
+/// memory for lookup speed differently
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SetImpl {
+    /// A hash set of the words, the fastest lookups
+    #[default]
+    Hash,
+    /// A B-tree set of the words
+    Btree,
+    /// A finite state transducer, by far the smallest
+    Fst,
+    /// A sorted list of the words, searched with a binary search
+    SortedVec,
+}
+
+/// Lowercase a word, only allocating when it has uppercase letters in it
+pub fn fold_case(word: &str) -> Cow<'_, str> {
+    if !word.chars().any(char::is_uppercase) {
+        return Cow::Borrowed(word);
+    }
+
+    return Cow::Owned(word.chars().flat_map(char::to_lowercase).collect());
+}
+
+/// A word that hashes and compares the same as its lowercase form, so a
+/// set of them can be checked for "The" without lowercasing it first
+#[derive(Debug, Clone, Copy)]
+pub struct Caseless<S>(pub S);
+
+impl<S: AsRef<str>> Caseless<S> {
+    fn folded(&self) -> impl Iterator<Item = char> + '_ {
+        return self.0.as_ref().chars().flat_map(char::to_lowercase);
+    }
+
+    fn eq_folded<T: AsRef<str>>(&self, other: &Caseless<T>) -> bool {
+        let (a, b) = (self.0.as_ref(), other.0.as_ref());
+        if a.is_ascii() && b.is_ascii() {
+            return a.eq_ignore_ascii_case(b);
+        }
+
+        return self.folded().eq(other.folded());
+    }
+}
+
+impl<S: AsRef<str>> PartialEq for Caseless<S> {
+    fn eq(&self, other: &Self) -> bool {
+        return self.eq_folded(other);
+    }
+}
+
+impl<S: AsRef<str>> Eq for Caseless<S> {}
+
+/// So a map with owned words can be looked up with a borrowed one
+impl Equivalent<Caseless<String>> for Caseless<&str> {
+    fn equivalent(&self, key: &Caseless<String>) -> bool {
+        return self.eq_folded(key);
+    }
+}
+
+impl<S: AsRef<str>> Hash for Caseless<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The lowercase bytes go to the hasher a buffer at a time, which
+        // splits them up the same way for any 2 equal words
+        let mut buf = [0_u8; 64];
+        let word = self.0.as_ref();
+        if word.len() <= buf.len() && word.is_ascii() {
+            if !word.bytes().any(|b| b.is_ascii_uppercase()) {
+                state.write(word.as_bytes());
+                return;
+            }
+            buf[..word.len()].copy_from_slice(word.as_bytes());
+            buf[..word.len()].make_ascii_lowercase();
+            state.write(&buf[..word.len()]);
+            return;
+        }
+
+        let mut len = 0;
+        for c in self.folded() {
+            if len + c.len_utf8() > buf.len() {
+                state.write(&buf[..len]);
+                len = 0;
+            }
+            len += c.encode_utf8(&mut buf[len..]).len();
+        }
+        state.write(&buf[..len]);
+    }
+}
+
+/// The dictionary, for checking whether words are in it.  Lookups ignore
+/// case, the hash set by comparing words as if they were lowercase and the
+/// others by keeping the lowercase form of each word.
+#[derive(Debug)]
+pub enum WordSet<'a> {
+    Hash(HashSet<Caseless<&'a str>>),
+    Btree(BTreeSet<Cow<'a, str>>),
+    Fst(fst::Set<Vec<u8>>),
+    SortedVec(Vec<Cow<'a, str>>),
+}
+
+impl<'a> WordSet<'a> {
+    pub fn new(words: &[&'a str], set_impl: SetImpl) -> Self {
+        let folded = words.iter().map(|w| fold_case(w));
+        return match set_impl {
+            SetImpl::Hash => WordSet::Hash(words.iter().map(|w| Caseless(*w)).collect()),
+            SetImpl::Btree => WordSet::Btree(folded.collect()),
+            SetImpl::Fst => {
+                let sorted: BTreeSet<Cow<str>> = folded.collect();
+                // Building from a sorted, deduplicated list can't fail
+                WordSet::Fst(fst::Set::from_iter(sorted.iter().map(|w| w.as_bytes())).unwrap())
+            }
+            SetImpl::SortedVec => {
+                let mut sorted: Vec<Cow<str>> = folded.collect();
+                sorted.sort_unstable();
+                sorted.dedup();
+                WordSet::SortedVec(sorted)
+            }
+        };
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        return match self {
+            WordSet::Hash(set) => set.contains(&Caseless(word)),
+            WordSet::Btree(set) => set.contains(fold_case(word).as_ref()),
+            WordSet::Fst(set) => set.contains(fold_case(word).as_ref()),
+            WordSet::SortedVec(list) => {
+                let word = fold_case(word);
+                list.binary_search_by(|w| w.as_ref().cmp(&word)).is_ok()
+            }
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return match self {
+            WordSet::Hash(set) => set.len(),
+            WordSet::Btree(set) => set.len(),
+            WordSet::Fst(set) => set.len(),
+            WordSet::SortedVec(list) => list.len(),
+        };
+    }
+
+    /// All the words, in no particular order.  The fst has to decode them,
+    /// so this is only for building other lookup tables up front.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Cow<'a, str>> + '_> {
+        return match self {
+            WordSet::Hash(set) => Box::new(set.iter().map(|w| Cow::Borrowed(w.0))),
+            WordSet::Btree(set) => Box::new(set.iter().cloned()),
+            WordSet::Fst(set) => {
+                let words = set.stream().into_strs().unwrap_or_default();
+                Box::new(words.into_iter().map(Cow::Owned))
+            }
+            WordSet::SortedVec(list) => Box::new(list.iter().cloned()),
+        };
+    }
+}
+
+#[test]
+fn test_word_set() {
+    let words = ["résumé", "cat", "ad hoc", "cat", "dog"];
+    for set_impl in SetImpl::value_variants() {
+        let set = WordSet::new(&words, *set_impl);
+        assert_eq!(set.len(), 4, "{:?}", set_impl);
+        assert!(set.contains("résumé") && set.contains("ad hoc"));
+        assert!(!set.contains("resume") && !set.contains("ca"));
+        assert!(set.contains("Cat") && set.contains("RÉSUMÉ"));
+
+        let mut all: Vec<Cow<str>> = set.iter().collect();
+        all.sort();
+        assert_eq!(all, vec!["ad hoc", "cat", "dog", "résumé"]);
+    }
+
+    // Entries with capitals are found in any case too
+    for set_impl in SetImpl::value_variants() {
+        let set = WordSet::new(&["Paris", "I"], *set_impl);
+        assert!(set.contains("paris") && set.contains("PARIS") && set.contains("i"));
+        assert!(!set.contains("pari"), "{:?}", set_impl);
+    }
+}
+
+#[test]
+fn test_fold_case() {
+    assert!(matches!(fold_case("plain"), Cow::Borrowed("plain")));
+    assert_eq!(fold_case("ÉCOLE"), "école");
+    assert_eq!(Caseless("The"), Caseless("tHE"));
+    assert_ne!(Caseless("The"), Caseless("then"));
+}
@@ -0,0 +1,89 @@
+#![allow(clippy::needless_return)]
+use std::{fs, path::Path, process::Command};
+
+/// Run spel in the directory, returning its exit code and stdout
+fn spel(dir: &Path, args: &[&str]) -> (i32, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_spel"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+
+    return (
+        out.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&out.stdout).into_owned(),
+    );
+}
+
+/// Subcommands still run once the config file and a profile are loaded
+#[test]
+fn test_subcommands_after_config() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("spel.toml"),
+        "max_file_size = 4096\n\n[profile.docs]\nskip_code_spans = true\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("a.md"), "Some wrods here\n").unwrap();
+
+    for profile in [&[][..], &["--profile", "docs"]] {
+        let args = [profile, &["distance", "teh", "the"]].concat();
+        let (code, out) = spel(dir.path(), &args);
+        assert_eq!(code, 0);
+        assert!(out.contains("damerau       1"), "{}", out);
+
+        let args = [profile, &["report", "vocab", "a.md"]].concat();
+        let (code, out) = spel(dir.path(), &args);
+        assert_eq!(code, 0);
+        assert!(out.contains("wrods"), "{}", out);
+    }
+
+    // An empty journal has nothing to revert, but it's still read
+    fs::write(dir.path().join("journal.jsonl"), "").unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_spel"))
+        .current_dir(dir.path())
+        .args(["--profile", "docs", "revert", "journal.jsonl"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Reverted 0 replacement(s)"));
+}
+
+/// Only the plain format can group the findings by word
+#[test]
+fn test_group_by_word_format() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.md"), "Some wrods here\n").unwrap();
+
+    let (code, out) = spel(dir.path(), &["-f", "--group-by", "word", "a.md"]);
+    assert_eq!(code, 1);
+    assert!(out.starts_with("\"wrods\" (1)\n"), "{}", out);
+
+    for format in ["annotate", "caret", "json", "sarif"] {
+        let args = ["-f", "--group-by", "word", "--format", format, "a.md"];
+        let (code, out) = spel(dir.path(), &args);
+        assert_eq!(code, 2, "{}", format);
+        assert!(out.is_empty(), "{}", out);
+    }
+}
+
+/// Context lines can be shown in any format but the json and sarif ones
+#[test]
+fn test_context_format() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.md"), "One\nSome wrods here\nTwo\n").unwrap();
+
+    let (code, out) = spel(dir.path(), &["-f", "-C", "1", "--format", "caret", "a.md"]);
+    assert_eq!(code, 1);
+    assert!(out.contains("> 2 | Some wrods here\n"), "{}", out);
+    assert!(out.ends_with("  3 | Two\n--\n"), "{}", out);
+
+    for format in ["json", "sarif"] {
+        let args = ["-f", "-C", "1", "--format", format, "a.md"];
+        let (code, out) = spel(dir.path(), &args);
+        assert_eq!(code, 2, "{}", format);
+        assert!(out.is_empty(), "{}", out);
+    }
+    let (code, _) = spel(dir.path(), &["-f", "--format", "json", "a.md"]);
+    assert_eq!(code, 1);
+}